@@ -28,32 +28,32 @@ uniform vec3 global_lighting;
 uniform vec4 camera_origin;
 
 // Parametros de reflexão specular
-uniform vec3 specular_reflectance;
+flat in vec3 object_specular_reflectance;
 
 // Parametros de luz ambiente
 uniform vec3 ambient_lighting;
 
 // Parametros de refletancia ambiente
-uniform vec3 ambient_reflectance;
+flat in vec3 object_ambient_reflectance;
 
 // Parametro de sobreescrita de cor
-uniform vec3 color_overide;
+flat in vec3 object_color_overide;
 
 // Parametro de expoente q de phong
-uniform float phong_q;
+flat in float object_phong_q;
 
 // Textura map type: Tipo de mapeamento da textura. 0 - Plano; 1- Planar XY; 2- Esferico; 3- Cilindrico
-uniform int texture_map_type;
+flat in int object_texture_map_type;
 
 // Direção da iluminação global
 uniform vec4 lighting_direction;
 
 // Possivel vetor de sobrescrita da iluminaçção global
-uniform vec4 lighting_source_override;
+flat in vec4 object_lighting_source_override;
 
 out vec3 color;
 void main()
 {
-    color=color_overide;
+    color=object_color_overide;
 }
 
@@ -10,12 +10,19 @@ in vec4 position_model;
 // Coordenadas de textura obtidas do arquivo OBJ (se existirem!)
 in vec2 texcoords;
 
+// Cor do vertice, interpolada pelo rasterizador (se existir!)
+in vec4 vertex_color;
+
 in vec3 lambert_diffuse_term;
 in vec3 phong_specular_term;
 
 // Variáveis para acesso das imagens de textura
 uniform sampler2D texture_overide;
 
+// Texturas de containers (KTX2, DDS) não são giradas no carregamento como png e jpg: são
+// amostradas com U e V invertidos, o que equivale a girar a imagem em 180 graus
+uniform bool flip_texture_uv;
+
 // Parâmetros da axis-aligned bounding box (AABB) do modelo
 uniform vec4 bbox_min;
 uniform vec4 bbox_max;
@@ -27,28 +34,28 @@ uniform vec3 global_lighting;
 uniform vec4 camera_origin;
 
 // Parametros de refletancia specular
-uniform vec3 specular_reflectance;
+flat in vec3 object_specular_reflectance;
 
 // Parametros de refletancia ambiente
-uniform vec3 ambient_reflectance;
+flat in vec3 object_ambient_reflectance;
 
 // Parametros de luz ambiente
 uniform vec3 ambient_lighting;
 
 // Parametro de sobreescrita de cor
-uniform vec3 color_overide;
+flat in vec3 object_color_overide;
 
 // Parametro de expoente q de phong
-uniform float phong_q;
+flat in float object_phong_q;
 
-// Textura map type: Tipo de mapeamento da textura. 0 - ARQUIVO OBJ; 1- Planar XY;2- Planar XZ; ; 3- Esferico; 4- Cilindrico
-uniform int texture_map_type;
+// Textura map type: Tipo de mapeamento da textura. 0 - ARQUIVO OBJ; 1- Planar XY;2- Planar XZ; ; 3- Esferico; 4- Cilindrico; 5- Cor dos vertices
+flat in int object_texture_map_type;
 
 // Direção da iluminação global
 uniform vec4 lighting_direction;
 
 // Possivel vetor de sobrescrita da iluminaçção global
-uniform vec4 lighting_source_override;
+flat in vec4 object_lighting_source_override;
 
 // Constantes
 #define M_PI 3.14159265358979323846
@@ -58,15 +65,15 @@ out vec3 color;
 
 void main()
 {
-    vec3 object_reflectance=color_overide;
+    vec3 object_reflectance=object_color_overide;
     
     // Coordenadas de textura U e V
     float U=0.;
     float V=0.;
     
     // Se não exite cor para sobreescrever textura atual, utiliza textura
-    if(color_overide==vec3(0.,0.,0.)){
-        if(texture_map_type==1){
+    if(object_color_overide==vec3(0.,0.,0.)){
+        if(object_texture_map_type==1){
             
             // Mapeia textura de maneira planar em xy
             float minx=bbox_min.x;
@@ -80,7 +87,7 @@ void main()
             
             U=(position_model.x-minx)/(maxx-minx);
             V=(position_model.y-miny)/(maxy-miny);
-        }else if(texture_map_type==2){
+        }else if(object_texture_map_type==2){
             
             // Mapeia textura de maneira planar em zx
             float minx=bbox_min.x;
@@ -95,7 +102,7 @@ void main()
             U=(position_model.x-minx)/(maxx-minx);
             V=(position_model.z-minz)/(maxz-minz);
         }
-        else if(texture_map_type==3){
+        else if(object_texture_map_type==3){
             
             vec4 bbox_center=(bbox_min+bbox_max)/2.;
             float radius=length(bbox_max.x-bbox_center.x);
@@ -106,7 +113,7 @@ void main()
             U=(theta+M_PI)/(2*M_PI);
             V=(phi+M_PI_2)/M_PI;
         }
-        else if(texture_map_type==4){
+        else if(object_texture_map_type==4){
             
             float theta=atan(position_model.x,position_model.z);
             U=(theta+M_PI)/(2*M_PI);
@@ -120,20 +127,25 @@ void main()
             V=texcoords.y;
         }
         
-        object_reflectance=texture(texture_overide,vec2(U,V)).rgb;
+        object_reflectance=texture(texture_overide,flip_texture_uv?vec2(1.-U,1.-V):vec2(U,V)).rgb;
+        
+        // Cor dos vertices no lugar da textura. Cores são lidas em sRGB e convertidas para linear
+        if(object_texture_map_type==5){
+            object_reflectance=pow(vertex_color.rgb,vec3(2.2,2.2,2.2));
+        }
     }
     
     vec3 final_ambient_reflectance=vec3((object_reflectance.x*.15)+.05,(object_reflectance.y*.15)+.05,(object_reflectance.z*.15)+.05);
     
     // Sobreescreve refletancia ambiente se existe alguma definida, se não utiliza cor do ponto para calcular
-    if(ambient_reflectance!=vec3(0.,0.,0.)){
-        final_ambient_reflectance=ambient_reflectance;
+    if(object_ambient_reflectance!=vec3(0.,0.,0.)){
+        final_ambient_reflectance=object_ambient_reflectance;
     }
     
     // Termo ambiente
     vec3 ambient_term=final_ambient_reflectance*ambient_lighting;
     
-    color=(lambert_diffuse_term*object_reflectance)+ambient_term+(specular_reflectance*phong_specular_term);
+    color=(lambert_diffuse_term*object_reflectance)+ambient_term+(object_specular_reflectance*phong_specular_term);
     
     color=pow(color,vec3(1.,1.,1.)/2.2);
 }
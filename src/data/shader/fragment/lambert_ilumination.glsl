@@ -9,14 +9,24 @@ in vec4 position_model;
 // Coordenadas de textura obtidas do arquivo OBJ (se existirem!)
 in vec2 texcoords;
 
+// Cor do vertice, interpolada pelo rasterizador (se existir!)
+in vec4 vertex_color;
+
 // Matrizes computadas no código C++ e enviadas para a GPU
 uniform mat4 model;
 uniform mat4 view;
 uniform mat4 projection;
 
+// Inversa da matriz view, calculada na CPU
+uniform mat4 view_inverse;
+
 // Variáveis para acesso das imagens de textura
 uniform sampler2D texture_overide;
 
+// Texturas de containers (KTX2, DDS) não são giradas no carregamento como png e jpg: são
+// amostradas com U e V invertidos, o que equivale a girar a imagem em 180 graus
+uniform bool flip_texture_uv;
+
 // Parâmetros da axis-aligned bounding box (AABB) do modelo
 uniform vec4 bbox_min;
 uniform vec4 bbox_max;
@@ -28,28 +38,28 @@ uniform vec3 global_lighting;
 uniform vec4 camera_origin;
 
 // Parametros de refletancia specular
-uniform vec3 specular_reflectance;
+flat in vec3 object_specular_reflectance;
 
 // Parametros de refletancia ambiente
-uniform vec3 ambient_reflectance;
+flat in vec3 object_ambient_reflectance;
 
 // Parametros de luz ambiente
 uniform vec3 ambient_lighting;
 
 // Parametro de sobreescrita de cor
-uniform vec3 color_overide;
+flat in vec3 object_color_overide;
 
 // Parametro de expoente q de phong
-uniform float phong_q;
+flat in float object_phong_q;
 
 // Textura map type: Tipo de mapeamento da textura. 0 - ARQUIVO OBJ; 1- Planar XY;2- Planar XZ; 3- 2- Planar YZ ; 4- Esferico; 5- Cilindrico
-uniform int texture_map_type;
+flat in int object_texture_map_type;
 
 // Direção da iluminação global
 uniform vec4 lighting_direction;
 
 // Possivel vetor de sobrescrita da iluminaçção global
-uniform vec4 lighting_source_override;
+flat in vec4 object_lighting_source_override;
 
 out vec3 color;
 
@@ -62,7 +72,7 @@ void main()
     // INICIALIZAÇÂO:
     
     // sistema de coordenadas da câmera.
-    vec4 camera_position=inverse(view)*camera_origin;
+    vec4 camera_position=view_inverse*camera_origin;
     
     vec4 p=position_world;
     
@@ -83,13 +93,13 @@ void main()
     // A refletancia especular, difusa, e ambiente é calculada a partir das cores da textura do obj
     // e podem ser sobreescritas pelo obj
     
-    vec3 object_reflectance=color_overide;
+    vec3 object_reflectance=object_color_overide;
     
     // FIM INICIALIZACAO
     
     // Se não exite cor para sobreescrever textura atual, utiliza textura
-    if(color_overide==vec3(0.,0.,0.)){
-        if(texture_map_type==1){
+    if(object_color_overide==vec3(0.,0.,0.)){
+        if(object_texture_map_type==1){
             
             // Mapeia textura de maneira planar em xy
             float minx=bbox_min.x;
@@ -103,7 +113,7 @@ void main()
             
             U=(position_model.x-minx)/(maxx-minx);
             V=(position_model.y-miny)/(maxy-miny);
-        }else if(texture_map_type==2){
+        }else if(object_texture_map_type==2){
             
             // Mapeia textura de maneira planar em zx
             float minx=bbox_min.x;
@@ -118,7 +128,7 @@ void main()
             U=(position_model.x-minx)/(maxx-minx);
             V=(position_model.z-minz)/(maxz-minz);
         }
-        else if(texture_map_type==3){
+        else if(object_texture_map_type==3){
             
             vec4 bbox_center=(bbox_min+bbox_max)/2.;
             float radius=length(bbox_max.x-bbox_center.x);
@@ -129,7 +139,7 @@ void main()
             U=(theta+M_PI)/(2*M_PI);
             V=(phi+M_PI_2)/M_PI;
         }
-        else if(texture_map_type==4){
+        else if(object_texture_map_type==4){
             float theta=atan(position_model.x,position_model.z);
             U=(theta+M_PI)/(2*M_PI);
             V=(position_model.y-bbox_min.y)/(bbox_max.y-bbox_min.y);
@@ -142,14 +152,19 @@ void main()
             V=texcoords.y;
         }
         
-        object_reflectance=texture(texture_overide,vec2(U,V)).rgb;
+        object_reflectance=texture(texture_overide,flip_texture_uv?vec2(1.-U,1.-V):vec2(U,V)).rgb;
+        
+        // Cor dos vertices no lugar da textura. Cores são lidas em sRGB e convertidas para linear
+        if(object_texture_map_type==5){
+            object_reflectance=pow(vertex_color.rgb,vec3(2.2,2.2,2.2));
+        }
     }
     
     vec3 final_ambient_reflectance=vec3((object_reflectance.x*.15)+.05,(object_reflectance.y*.15)+.05,(object_reflectance.z*.15)+.05);
     
     // Sobreescreve refletancia ambiente se existe alguma definida, se não utiliza cor do ponto para calcular
-    if(ambient_reflectance!=vec3(0.,0.,0.)){
-        final_ambient_reflectance=ambient_reflectance;
+    if(object_ambient_reflectance!=vec3(0.,0.,0.)){
+        final_ambient_reflectance=object_ambient_reflectance;
     }
     
     // Termo difuso utilizando a lei dos cossenos de Lambert
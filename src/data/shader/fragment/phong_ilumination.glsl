@@ -4,20 +4,38 @@
 in vec4 position_world;
 in vec4 normal;
 
+// Tangente interpolada (xyz) e sinal da bitangente (w), para o normal map
+in vec4 tangent;
+
 // Posição do vértice atual no sistema de coordenadas local do modelo.
 in vec4 position_model;
 
 // Coordenadas de textura obtidas do arquivo OBJ (se existirem!)
 in vec2 texcoords;
 
+// Cor do vertice, interpolada pelo rasterizador (se existir!)
+in vec4 vertex_color;
+
 // Matrizes computadas no código C++ e enviadas para a GPU
 uniform mat4 model;
 uniform mat4 view;
 uniform mat4 projection;
 
+// Inversa da matriz view, calculada na CPU
+uniform mat4 view_inverse;
+
 // Variáveis para acesso das imagens de textura
 uniform sampler2D texture_overide;
 
+// Texturas de containers (KTX2, DDS) não são giradas no carregamento como png e jpg: são
+// amostradas com U e V invertidos, o que equivale a girar a imagem em 180 graus
+uniform bool flip_texture_uv;
+
+// Normal map em espaço tangente, utilizado se has_normal_map for verdadeiro
+uniform sampler2D normal_map;
+uniform bool has_normal_map;
+uniform bool flip_normal_map_uv;
+
 // Parâmetros da axis-aligned bounding box (AABB) do modelo
 uniform vec4 bbox_min;
 uniform vec4 bbox_max;
@@ -29,28 +47,28 @@ uniform vec3 global_lighting;
 uniform vec4 camera_origin;
 
 // Parametros de refletancia specular
-uniform vec3 specular_reflectance;
+flat in vec3 object_specular_reflectance;
 
 // Parametros de refletancia ambiente
-uniform vec3 ambient_reflectance;
+flat in vec3 object_ambient_reflectance;
 
 // Parametros de luz ambiente
 uniform vec3 ambient_lighting;
 
 // Parametro de sobreescrita de cor
-uniform vec3 color_overide;
+flat in vec3 object_color_overide;
 
 // Parametro de expoente q de phong
-uniform float phong_q;
+flat in float object_phong_q;
 
-// Textura map type: Tipo de mapeamento da textura. 0 - ARQUIVO OBJ; 1- Planar XY;2- Planar XZ; ; 3- Esferico; 4- Cilindrico
-uniform int texture_map_type;
+// Textura map type: Tipo de mapeamento da textura. 0 - ARQUIVO OBJ; 1- Planar XY;2- Planar XZ; ; 3- Esferico; 4- Cilindrico; 5- Cor dos vertices
+flat in int object_texture_map_type;
 
 // Direção da iluminação global
 uniform vec4 lighting_direction;
 
 // Possivel vetor de sobrescrita da iluminaçção global
-uniform vec4 lighting_source_override;
+flat in vec4 object_lighting_source_override;
 
 out vec3 color;
 
@@ -63,7 +81,7 @@ void main()
     // INICIALIZAÇÂO:
     
     // sistema de coordenadas da câmera.
-    vec4 camera_position=inverse(view)*camera_origin;
+    vec4 camera_position=view_inverse*camera_origin;
     
     vec4 p=position_world;
     
@@ -71,16 +89,36 @@ void main()
     // normais de cada vértice.
     vec4 n=normalize(normal);
     
+    // Perturba a normal com o normal map, na base tangente da MikkTSpace:
+    // bitangente = sinal * cross(normal, tangente). Apenas com as coordenadas de textura do
+    // arquivo, para as quais as tangentes foram calculadas. Vertices com tangente nula
+    // (ex: polos de uma esfera) mantém a normal interpolada
+    if(has_normal_map&&object_texture_map_type==0&&dot(tangent.xyz,tangent.xyz)>0.){
+        // Gram-Schmidt: a tangente interpolada deixa de ser perpendicular a normal
+        vec3 t=normalize(tangent.xyz-n.xyz*dot(n.xyz,tangent.xyz));
+        vec3 b=tangent.w*cross(n.xyz,t);
+        vec3 normal_sample=texture(normal_map,flip_normal_map_uv?1.-texcoords:texcoords).rgb*2.-1.;
+        
+        // z reconstruido a partir de x e y: normal maps de 2 canais (BC5) não guardam z
+        normal_sample.z=sqrt(max(0.,1.-dot(normal_sample.xy,normal_sample.xy)));
+        
+        // As texturas são giradas em 180 graus (ou amostradas com U e V invertidos), espelhando o
+        // eixo U
+        normal_sample.x=-normal_sample.x;
+        
+        n=vec4(normalize(normal_sample.x*t+normal_sample.y*b+normal_sample.z*n.xyz),0.);
+    }
+    
     // Vetor que define o sentido da fonte de luz em relação ao ponto atual.
     vec4 l=vec4(0.,0.,0.,0.);
     
     // Sobreescreve iluminação global com direção relatica a alguma fonte de luz, se existir parametro
-    if(lighting_source_override.y==0.){
+    if(object_lighting_source_override.y==0.){
         l=normalize(lighting_direction);
         
     }
     else{
-        vec4 source_point=lighting_source_override-position_world;
+        vec4 source_point=object_lighting_source_override-position_world;
         l=normalize(source_point);
     }
     
@@ -91,7 +129,7 @@ void main()
     float U=0.;
     float V=0.;
     
-    vec3 object_reflectance=color_overide;
+    vec3 object_reflectance=object_color_overide;
     
     // Vetor que define o sentido da reflexão especular ideal.
     vec4 r=-l+2*n*(dot(n,l));
@@ -99,8 +137,8 @@ void main()
     // FIM INICIALIZACAO
     
     // Se não exite cor para sobreescrever textura atual, utiliza textura
-    if(color_overide==vec3(0.,0.,0.)){
-        if(texture_map_type==1){
+    if(object_color_overide==vec3(0.,0.,0.)){
+        if(object_texture_map_type==1){
             
             // Mapeia textura de maneira planar em xy
             float minx=bbox_min.x;
@@ -114,7 +152,7 @@ void main()
             
             U=(position_model.x-minx)/(maxx-minx);
             V=(position_model.y-miny)/(maxy-miny);
-        }else if(texture_map_type==2){
+        }else if(object_texture_map_type==2){
             
             // Mapeia textura de maneira planar em zx
             float minx=bbox_min.x;
@@ -129,7 +167,7 @@ void main()
             U=(position_model.x-minx)/(maxx-minx);
             V=(position_model.z-minz)/(maxz-minz);
         }
-        else if(texture_map_type==3){
+        else if(object_texture_map_type==3){
             
             vec4 bbox_center=(bbox_min+bbox_max)/2.;
             float radius=length(bbox_max.x-bbox_center.x);
@@ -140,7 +178,7 @@ void main()
             U=(theta+M_PI)/(2*M_PI);
             V=(phi+M_PI_2)/M_PI;
         }
-        else if(texture_map_type==4){
+        else if(object_texture_map_type==4){
             
             float theta=atan(position_model.x,position_model.z);
             U=(theta+M_PI)/(2*M_PI);
@@ -154,7 +192,12 @@ void main()
             V=texcoords.y;
         }
         
-        object_reflectance=texture(texture_overide,vec2(U,V)).rgb;
+        object_reflectance=texture(texture_overide,flip_texture_uv?vec2(1.-U,1.-V):vec2(U,V)).rgb;
+        
+        // Cor dos vertices no lugar da textura. Cores são lidas em sRGB e convertidas para linear
+        if(object_texture_map_type==5){
+            object_reflectance=pow(vertex_color.rgb,vec3(2.2,2.2,2.2));
+        }
     }
     
     // Termo difuso utilizando a lei dos cossenos de Lambert
@@ -163,19 +206,19 @@ void main()
     vec3 final_ambient_reflectance=vec3((object_reflectance.x*.15)+.05,(object_reflectance.y*.15)+.05,(object_reflectance.z*.15)+.05);
     
     // Sobreescreve refletancia ambiente se existe alguma definida, se não utiliza cor do ponto para calcular
-    if(ambient_reflectance!=vec3(0.,0.,0.)){
-        final_ambient_reflectance=ambient_reflectance;
+    if(object_ambient_reflectance!=vec3(0.,0.,0.)){
+        final_ambient_reflectance=object_ambient_reflectance;
     }
     
     // Termo ambiente
     vec3 ambient_term=final_ambient_reflectance*ambient_lighting;
     
     // Termo especular utilizando o modelo de iluminação de Phong
-    vec3 phong_specular_term=global_lighting*pow(max(0,dot(r,v)),phong_q);
+    vec3 phong_specular_term=global_lighting*pow(max(0,dot(r,v)),object_phong_q);
     
     // Multiplicamos o vetor de refletancia especular pela cor da textura
-    // Utilizamos um vetor (specular_reflectance) para controlar a intensidade da refletancia especular do objeto
-    color=(lambert_diffuse_term*object_reflectance)+ambient_term+(specular_reflectance*phong_specular_term);
+    // Utilizamos um vetor (object_specular_reflectance) para controlar a intensidade da refletancia especular do objeto
+    color=(lambert_diffuse_term*object_reflectance)+ambient_term+(object_specular_reflectance*phong_specular_term);
     
     color=pow(color,vec3(1.,1.,1.)/2.2);
 }
@@ -0,0 +1,25 @@
+#version 330 core
+
+in vec2 screen_position;
+
+// Inversa de projection * view sem a translação da camera
+uniform mat4 inverse_rotation_projection;
+
+// Cubemap do fundo da cena
+uniform samplerCube skybox;
+
+out vec3 color;
+
+void main()
+{
+    // Pontos do pixel no near e no far plane, vistos da camera na origem
+    // A direção entre eles funciona nas projeções perspectiva e ortografica
+    vec4 near=inverse_rotation_projection*vec4(screen_position,-1.,1.);
+    vec4 far=inverse_rotation_projection*vec4(screen_position,1.,1.);
+    vec3 direction=far.xyz/far.w-near.xyz/near.w;
+    
+    // Texturas de cor são lidas em linear, e a cor final é convertida para sRGB como nos
+    // shaders de iluminação
+    color=texture(skybox,normalize(direction)).rgb;
+    color=pow(color,vec3(1.,1.,1.)/2.2);
+}
@@ -3,30 +3,112 @@
 layout(location=0)in vec4 model_coefficients;
 layout(location=1)in vec4 normal_coefficients;
 layout(location=2)in vec2 texture_coefficients;
+layout(location=3)in vec4 color_coefficients;
+// location 4: tangente, utilizada apenas no phong shading
+
+// Atributos por instancia, utilizados apenas no desenho instanciado
+// Linhas da matriz model. A ultima linha é sempre 0 0 0 1
+layout(location=5)in vec4 instance_model_rows[3];
+// Colunas da matriz de normais (xyz), calculada na CPU, e fonte de luz (w de cada coluna)
+layout(location=8)in vec4 instance_normal_columns[3];
+// Cor (rgb) e q de phong (w)
+layout(location=11)in vec4 instance_color;
+// Refletancia especular (rgb) e tipo de mapeamento de textura (w)
+layout(location=12)in vec4 instance_specular;
+// Refletancia ambiente (rgb)
+layout(location=13)in vec4 instance_ambient;
+
+// Juntas (indices em joint_matrices) e pesos das 4 juntas que deformam o vertice
+// Utilizados apenas em malhas com esqueleto
+layout(location=14)in uvec4 joint_indices;
+layout(location=15)in vec4 joint_weights;
+
+// Verdadeiro no desenho instanciado, quando os valores do obj vem dos atributos da instancia
+uniform bool instanced;
+
+// Verdadeiro no desenho de objs com esqueleto
+uniform bool skinned;
+// Matrizes das juntas na pose atual: global da junta * inversa da pose de ligação (ver skeleton.rs)
+uniform mat4 joint_matrices[64];
 
 uniform mat4 model;
 uniform mat4 view;
 uniform mat4 projection;
 
+// Inversa da transposta da matriz model, calculada na CPU
+uniform mat4 normal_matrix;
+
+// Parametros do obj no desenho simples
+uniform vec3 color_overide;
+uniform vec3 specular_reflectance;
+uniform vec3 ambient_reflectance;
+uniform float phong_q;
+uniform int texture_map_type;
+uniform vec4 lighting_source_override;
+
 out vec4 position_world;
 out vec4 position_model;
 
 out vec4 normal;
 out vec2 texcoords;
+out vec4 vertex_color;
+
+// Valores do obj repassados sem interpolação para o fragment shader
+flat out vec3 object_color_overide;
+flat out vec3 object_specular_reflectance;
+flat out vec3 object_ambient_reflectance;
+flat out float object_phong_q;
+flat out int object_texture_map_type;
+flat out vec4 object_lighting_source_override;
 
 void main()
 {
+    // Valores do obj: uniforms no desenho simples ou atributos da instancia no desenho instanciado
+    mat4 object_model=model;
+    mat4 object_normal_matrix=normal_matrix;
+    object_color_overide=color_overide;
+    object_specular_reflectance=specular_reflectance;
+    object_ambient_reflectance=ambient_reflectance;
+    object_phong_q=phong_q;
+    object_texture_map_type=texture_map_type;
+    object_lighting_source_override=lighting_source_override;
+    
+    if(instanced){
+        object_model=transpose(mat4(instance_model_rows[0],instance_model_rows[1],instance_model_rows[2],vec4(0.,0.,0.,1.)));
+        object_normal_matrix=mat4(mat3(instance_normal_columns[0].xyz,instance_normal_columns[1].xyz,instance_normal_columns[2].xyz));
+        object_color_overide=instance_color.rgb;
+        object_phong_q=instance_color.w;
+        object_specular_reflectance=instance_specular.rgb;
+        object_texture_map_type=int(instance_specular.w);
+        object_ambient_reflectance=instance_ambient.rgb;
+        object_lighting_source_override=vec4(instance_normal_columns[0].w,instance_normal_columns[1].w,instance_normal_columns[2].w,1.);
+    }
+    
+    // Deformação pelo esqueleto: media das matrizes das juntas ponderada pelos pesos
+    // Vertices sem pesos ficam na posição original
+    mat4 skin_matrix=mat4(1.);
+    if(skinned&&dot(joint_weights,vec4(1.))>0.){
+        skin_matrix=joint_weights.x*joint_matrices[joint_indices.x]
+        +joint_weights.y*joint_matrices[joint_indices.y]
+        +joint_weights.z*joint_matrices[joint_indices.z]
+        +joint_weights.w*joint_matrices[joint_indices.w];
+    }
+    vec4 skinned_position=skin_matrix*model_coefficients;
+    // As juntas não tem escala não uniforme, a propria matriz transforma a normal
+    vec4 skinned_normal=skin_matrix*vec4(normal_coefficients.xyz,0.);
     
-    gl_Position=projection*view*model*model_coefficients;
+    gl_Position=projection*view*object_model*skinned_position;
     
-    position_world=model*model_coefficients;
+    position_world=object_model*skinned_position;
     
+    // Posição na pose de repouso: as texturas mapeadas pela bbox acompanham a deformação
     position_model=model_coefficients;
     
-    normal=inverse(transpose(model))*normal_coefficients;
+    normal=object_normal_matrix*skinned_normal;
     
     normal.w=0.;
     texcoords=texture_coefficients;
+    vertex_color=color_coefficients;
     
 }
 
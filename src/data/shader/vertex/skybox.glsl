@@ -0,0 +1,15 @@
+#version 330 core
+
+// Triangulo que cobre toda a tela, gerado a partir do indice do vertice, sem atributos
+// Vertices (-1,-1), (3,-1) e (-1,3) em coordenadas normalizadas do dispositivo
+
+// Posição do pixel na tela, em coordenadas normalizadas
+out vec2 screen_position;
+
+void main()
+{
+    screen_position=vec2(float((gl_VertexID&1)<<2),float((gl_VertexID&2)<<1))-1.;
+    
+    // Profundidade 1: o fundo fica no far plane, atrás de todos os objs
+    gl_Position=vec4(screen_position,1.,1.);
+}
@@ -1,9 +1,18 @@
 use glm::builtin::pow;
 use handle_input::handle_input;
+use models::asset_loader::is_loading;
+use models::asset_loader::loading_progress;
+use models::asset_loader::poll_assets;
+use models::asset_loader::request_texture;
+use models::asset_loader::request_texture_with_sampler;
 use models::draw::Draw;
-use models::load_texture::load_texture;
+use models::instanced::InstancedRenderer;
 use models::matrix::normalize_vector;
 use models::matrix::MatrixTransform;
+use models::quaternion::Quaternion;
+use models::resources::collect_resources;
+use models::sampler::SamplerDesc;
+use models::scene_object::raycast_objects;
 use models::scene_object::SceneObject;
 use rayon::iter::IndexedParallelIterator;
 use rayon::iter::IntoParallelRefIterator;
@@ -14,9 +23,21 @@ use std::thread::sleep;
 use std::time::Duration;
 use std::time::Instant;
 use world::free_camera::FreeCamera;
+use world::frustum::Frustum;
+use world::skybox::Skybox;
 use world::view::View;
 // Controle do loop principal do jogo
 
+// Cor de destaque do obj selecionado com o mouse
+static SELECTION_COLOR: glm::Vec3 = glm::Vec3 {
+    x: 1.0,
+    y: 0.85,
+    z: 0.0,
+};
+
+// Tempo maximo por frame de envio de assets carregados para a GPU
+static ASSET_UPLOAD_BUDGET: Duration = Duration::from_millis(4);
+
 // Controle de estado do jogo
 #[allow(dead_code, unused_assignments)]
 pub struct GameState {
@@ -39,6 +60,15 @@ pub struct GameState {
     pub max_framerate: f64,
     pub progression_multiplier: i32,
     pub lighting_source: glm::Vec4,
+    pub culled_objects: usize,
+    pub cursor_position: glm::Vec2,
+    pub window_size: glm::Vec2,
+    pub should_pick: bool,
+    pub selected_obj: Option<usize>,
+    pub instanced_rendering: bool,
+    pub frame_time: f32,
+    pub loading_progress: f32,
+    pub skybox: Option<Skybox>,
 }
 
 #[allow(dead_code, unused_assignments)]
@@ -109,30 +139,48 @@ pub unsafe fn game_loop(
         max_framerate: 90.0,
         progression_multiplier: 1,
         lighting_source: glm::vec4(0.0, 0.0, 0.0, 0.0),
+        culled_objects: 0,
+        cursor_position: glm::vec2(0.0, 0.0),
+        window_size: glm::vec2(1360.0, 768.0),
+        should_pick: false,
+        selected_obj: None,
+        instanced_rendering: true,
+        frame_time: 0.0,
+        loading_progress: 0.0,
+        skybox: None,
     };
 
-    ////////////////////// Carrega texturas do jogo /////////////////////////
-
-    let (sad_texture, _) = load_texture("src/data/textures/sad.jpg");
-    let (pearl_texture, _) = load_texture("src/data/textures/pearl.jpg");
+    // Buffer de instancias dos objs da fila de desenho
+    let mut instanced_renderer = InstancedRenderer::new();
 
-    let (copper_texture, _) = load_texture("src/data/textures/copper.jpg");
-    let (gold_texture, _) = load_texture("src/data/textures/gold.jpg");
-    let (ice_texture, _) = load_texture("src/data/textures/ice.jpg");
-    let (light_wood, _) = load_texture("src/data/textures/light_wood.jpg");
-
-    let (old_wood_texture, _) = load_texture("src/data/textures/old_wood.jpg");
-    let (sea_water_texture, _) = load_texture("src/data/textures/sea_water.jpg");
-    let (steel_texture, _) = load_texture("src/data/textures/steel.jpg");
-    let (earth_texture, _) = load_texture("src/data/textures/earth.jpg");
-    let (lava_texture, _) = load_texture("src/data/textures/lava.jpg");
-    let (fire_texture, _) = load_texture("src/data/textures/fire.jpg");
-
-    let (glass_texture, _) = load_texture("src/data/textures/glass.jpg");
-    let (corn, _) = load_texture("src/data/textures/corn.jpg");
-
-    let (pattern1, _) = load_texture("src/data/textures/pattern1.jpg");
-    let (pattern2, _) = load_texture("src/data/textures/pattern2.jpg");
+    ////////////////////// Carrega texturas do jogo /////////////////////////
+    // Texturas e objs são carregados no rayon (ver asset_loader.rs). Até o fim do carregamento
+    // são desenhados com texturas e malhas provisorias, e a barra de progresso é exibida
+
+    let sad_texture = request_texture("src/data/textures/sad.jpg");
+    let pearl_texture = request_texture("src/data/textures/pearl.jpg");
+
+    let copper_texture = request_texture("src/data/textures/copper.jpg");
+    let gold_texture = request_texture("src/data/textures/gold.jpg");
+    let ice_texture = request_texture("src/data/textures/ice.jpg");
+    let light_wood = request_texture("src/data/textures/light_wood.jpg");
+
+    let old_wood_texture = request_texture("src/data/textures/old_wood.jpg");
+    let sea_water_texture = request_texture("src/data/textures/sea_water.jpg");
+    let steel_texture = request_texture("src/data/textures/steel.jpg");
+    let earth_texture = request_texture("src/data/textures/earth.jpg");
+    let lava_texture = request_texture("src/data/textures/lava.jpg");
+    let fire_texture = request_texture("src/data/textures/fire.jpg");
+
+    let glass_texture = request_texture("src/data/textures/glass.jpg");
+    let corn = request_texture("src/data/textures/corn.jpg");
+
+    // Padrão do piso: repetido, com filtro anisotropico para o plano visto de lado
+    let pattern1 = request_texture_with_sampler(
+        "src/data/textures/pattern1.jpg",
+        &SamplerDesc::repeat().with_anisotropy(8.0),
+    );
+    let pattern2 = request_texture("src/data/textures/pattern2.jpg");
 
     let texture_pool = vec![
         &pearl_texture,
@@ -154,13 +202,22 @@ pub unsafe fn game_loop(
     ];
     let plane_pool = vec![&glass_texture, &pattern1];
 
+    // Fundo da cena. Sem skybox (ex: imagem não encontrada) o fundo é a cor de limpeza
+    game_state.skybox = match Skybox::from_equirectangular("src/data/textures/sky.png") {
+        Ok(skybox) => Some(skybox),
+        Err(error) => {
+            println!("{}", error);
+            None
+        }
+    };
+
     /////////////////////// Carrega objs do jogo /////////////////////////////
-    let mut plane = SceneObject::new("src/data/objs/plane.obj")
+    let mut plane = SceneObject::new_async("src/data/objs/plane.obj")
         .scale(5.0, 5.0, 5.0)
         .translate(0.0, game_state.obj_plane_height, 0.0)
         .with_color(&glm::vec3(0.6, 0.6, 0.6));
 
-    let mut sad_plane = SceneObject::new("src/data/objs/plane.obj")
+    let mut sad_plane = SceneObject::new_async("src/data/objs/plane.obj")
         .scale(1.0, 1.0, 1.0)
         .translate(0.0, game_state.obj_plane_height + 0.001, 0.0)
         .with_color(&glm::vec3(0.6, 0.6, 0.6))
@@ -169,46 +226,49 @@ pub unsafe fn game_loop(
         .with_specular_phong_q(&8.0);
 
     let sad_head =
-        SceneObject::new("src/data/objs/sphere.obj").with_color(&glm::vec3(0.0, 0.0, 0.0));
+        SceneObject::new_async("src/data/objs/sphere.obj").with_color(&glm::vec3(0.0, 0.0, 0.0));
 
-    let mut main_obj = SceneObject::new("src/data/objs/cube.obj")
+    let mut main_obj = SceneObject::new_async("src/data/objs/cube.obj")
         .with_color(&glm::vec3(1.0, 1.0, 1.0))
         .scale(0.2, 0.2, 0.2)
         .translate(0.0, game_state.obj_plane_height, -0.0);
 
-    let cow = SceneObject::new("src/data/objs/cow.obj")
+    let cow = SceneObject::new_async("src/data/objs/cow.obj")
         .scale(1.5, 1.5, 1.5)
         .translate(0.0, 0.9, 0.0)
         .with_texture_map_type(1);
 
-    let bunny = SceneObject::new("src/data/objs/bunny.obj")
+    let bunny = SceneObject::new_async("src/data/objs/bunny.obj")
         .translate(0.0, 0.8, 0.0)
         .with_texture_map_type(1);
 
-    let base_cube = SceneObject::new("src/data/objs/cube.obj").with_texture_map_type(1);
+    let base_cube = SceneObject::new_async("src/data/objs/cube.obj").with_texture_map_type(1);
 
-    let sphere = SceneObject::new("src/data/objs/sphere.obj")
+    let sphere = SceneObject::new_async("src/data/objs/sphere.obj")
         .translate(0.0, 0.4, 0.0)
         .with_color(&glm::vec3(0.6, 0.6, 0.2))
         .with_texture_map_type(3);
 
-    let cylinder = SceneObject::new("src/data/objs/cylinder.obj")
+    let cylinder = SceneObject::new_async("src/data/objs/cylinder.obj")
         .translate(0.0, 0.4, 0.0)
         .with_color(&glm::vec3(0.6, 0.6, 0.2))
         .with_texture_map_type(4);
 
-    let pyramid = SceneObject::new("src/data/objs/pyramid.obj")
+    let pyramid = SceneObject::new_async("src/data/objs/pyramid.obj")
         .rotate_z(1.5)
         .translate(0.0, 0.4, 0.0)
         .with_color(&glm::vec3(0.6, 0.6, 0.2))
         .with_texture_map_type(4);
 
-    let naked_dude = SceneObject::new("src/data/objs/naked_dude.obj")
+    // Caminha acenando com o braço direito
+    let naked_dude = SceneObject::new_async("src/data/objs/naked_dude.rig")
+        .play("walk")
+        .blend_clip("wave", 0.6)
         .scale(0.15, 0.15, 0.15)
         .translate(0.0, 0.4, 0.0)
         .with_texture_map_type(3);
 
-    let pyramid_head = SceneObject::new("src/data/objs/cylinder.obj")
+    let pyramid_head = SceneObject::new_async("src/data/objs/cylinder.obj")
         .translate(0.0, 0.8, 0.0)
         .with_texture_map_type(1)
         .add_children(
@@ -290,12 +350,19 @@ pub unsafe fn game_loop(
     let mut delta_vec_y = 0.0;
     let mut delta_vec_z = 0.0;
 
+    // Define se a tela de carregamento foi exibida na ultima frame
+    let mut was_loading = false;
+
+    // Cursor escondido na ultima frame
+    let mut cursor_hidden = false;
+
     loop {
         // Inicializa cronometro de tempo de renderização de uma frame
         let timer = Instant::now();
 
         // speed_mult unidades por segundo
         speed = delta_time * game_state.speed_mult;
+        game_state.frame_time = delta_time as f32;
         gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
         gl::ClearColor(0.3, 0.3, 0.3, 1.0);
 
@@ -319,9 +386,7 @@ pub unsafe fn game_loop(
 
             // Aumenta obj apos cada ponto
             // Calcula escalamento original do obj antes de ser escalamento
-            let init_x = main_obj.get_matrix().matrix.c0[0];
-            let init_y = main_obj.get_matrix().matrix.c1[1];
-            let init_z = main_obj.get_matrix().matrix.c2[2];
+            let init_scale = main_obj.get_transform().scale;
             // Se pontuação aumentou
             if last_score < game_state.score {
                 // Utiliza vetor de translação do obj para realizar uma translação - escalamento - translação
@@ -335,9 +400,10 @@ pub unsafe fn game_loop(
             }
 
             // Calcula aumento da altura da camera com diferença entre tamanho antes e depois do escalamento
-            delta_vec_x = delta_vec_x + main_obj.get_matrix().matrix.c0[0] - init_x;
-            delta_vec_y = delta_vec_y + main_obj.get_matrix().matrix.c1[1] - init_y;
-            delta_vec_z = delta_vec_z + main_obj.get_matrix().matrix.c2[2] - init_z;
+            let new_scale = main_obj.get_transform().scale;
+            delta_vec_x = delta_vec_x + new_scale.x - init_scale.x;
+            delta_vec_y = delta_vec_y + new_scale.y - init_scale.y;
+            delta_vec_z = delta_vec_z + new_scale.z - init_scale.z;
 
             let mut base_obj0 = &base_cube;
             let mut base_obj1 = &base_cube;
@@ -677,6 +743,29 @@ pub unsafe fn game_loop(
             view.perspective().render(&current_shader);
         }
 
+        // O cursor só fica escondido na camera em primeira pessoa, onde a seleção utiliza a mira
+        // no centro da tela. Na camera fixa o clique seleciona o obj sob o cursor
+        let should_hide_cursor = game_state.current_camera == 1;
+        if should_hide_cursor != cursor_hidden {
+            gl_window.window().hide_cursor(should_hide_cursor);
+            cursor_hidden = should_hide_cursor;
+        }
+
+        // Seleciona obj sob o cursor após um clique
+        if game_state.should_pick {
+            pick_object(&view, &mut game_state);
+        }
+
+        // Envia para a GPU os assets carregados e troca as malhas provisorias dos objs fixos
+        // Objs da fila de desenho são atualizados em draw_frame
+        poll_assets(ASSET_UPLOAD_BUDGET);
+        // Deleta os recursos cujo ultimo handle foi descartado fora desta thread
+        collect_resources();
+        game_state.loading_progress = loading_progress();
+        main_obj = main_obj.resolve_assets();
+        plane = plane.resolve_assets();
+        sad_plane = sad_plane.resolve_assets();
+
         // Desenha plano
         plane.draw(&current_shader);
         sad_plane.draw(&current_shader);
@@ -687,8 +776,33 @@ pub unsafe fn game_loop(
             &current_shader,
             &mut game_state,
             &free_camera,
+            &view.frustum(),
+            &mut instanced_renderer,
         );
 
+        // Fundo desenhado depois dos objs opacos, apenas nos pixels que ficaram vazios
+        if let Some(ref skybox) = game_state.skybox {
+            skybox.draw(&view);
+        }
+
+        // Mira no centro da tela, utilizada na seleção com o cursor escondido
+        if game_state.current_camera == 1 {
+            draw_crosshair(&game_state.window_size);
+        }
+
+        // Tela de carregamento sobre a cena enquanto houver assets pendentes
+        if is_loading() {
+            draw_loading_bar(game_state.loading_progress, &game_state.window_size);
+            gl_window.window().set_title(&format!(
+                "Upgrade - Carregando {:.0}%",
+                100.0 * game_state.loading_progress
+            ));
+            was_loading = true;
+        } else if was_loading {
+            gl_window.window().set_title("Upgrade");
+            was_loading = false;
+        }
+
         // Tempo de renderização de uma frame
         delta_time = timer.elapsed().as_secs_f64();
 
@@ -727,6 +841,8 @@ pub fn draw_frame(
     shader: &u32,
     game_state: &mut GameState,
     camera: &FreeCamera,
+    frustum: &Frustum,
+    instanced_renderer: &mut InstancedRenderer,
 ) {
     // Verifica se obj principal está sobre o plano
     if main.check_is_intersecting_fence(5.0, 5.0) {
@@ -751,19 +867,12 @@ pub fn draw_frame(
     let mut new_items: Vec<SceneObject> = vec![];
     let mut should_add_obj = false;
     let mut score = 0;
+    let mut culled_objects = 0;
+    let mut selected_obj = None;
     let intersecting_item_vec = Arc::new(Mutex::new(Vec::new()));
 
-    // Curva de bezier parametrizada pelo tempo
-    let b03 = pow(1.0 - game_state.curr_x, 3.0) as f32;
-    let b23 = 3.0 * pow(game_state.curr_x, 2.0) as f32 * (1.0 - game_state.curr_x) as f32;
-    let b13 = 3.0 * game_state.curr_x as f32 * pow(1.10 - game_state.curr_x, 2.0) as f32;
-    let b33 = pow(game_state.curr_x, 3.0) as f32;
-
-    let p1 = glm::vec4(-2.5, 0.4, 0.0, 0.0);
-    let p2 = glm::vec4(-2.00, 1.8, 1.25, 0.0);
-    let p3 = glm::vec4(2.0, 1.8, 0.5, 0.0);
-    let p4 = glm::vec4(4.5, 0.0, 1.25, 0.0);
-    let curve = (p1 * b03 + p2 * b13 + p3 * b23 + p4 * b33) / 4.0;
+    // Deslocamento e rotação dos objs em curva de bezier nesta frame
+    let (curve, bezier_rotation) = bezier_motion(game_state.curr_x);
 
     // Detecta intersecções de maneira paralela e guarda resultados
     game_state
@@ -801,27 +910,190 @@ pub fn draw_frame(
                 should_add_obj = true;
                 score = score + 1;
             } else {
+                // Obj selecionado é desenhado com cor de destaque e mantem a seleção na nova fila
+                let item = if game_state.selected_obj == Some(idx) {
+                    selected_obj = Some(new_items.len());
+                    item.with_color(&SELECTION_COLOR)
+                } else {
+                    item.clone()
+                };
+
                 // Desenha alguns objs com mov em curva de bezier
-                if game_state.with_bezier {
+                let item = if game_state.with_bezier {
                     item.with_lighting_source_override(&game_state.lighting_source)
-                        .trot_y(2.0 * 3.14 * game_state.curr_x as f32)
-                        .trot_x(2.0 * 3.14 * game_state.curr_x as f32)
+                        .t_rot_quat(&bezier_rotation)
                         .translate(curve.x, curve.y, curve.z)
-                        .draw(shader);
-
-                    new_items.push(item.clone());
                 } else {
                     item.with_lighting_source_override(&game_state.lighting_source)
-                        .draw(shader);
-                    new_items.push(item.clone());
-                }
+                };
+
+                // Objs repetidos são agrupados e desenhados de uma vez no fim da frame
+                culled_objects = culled_objects
+                    + if game_state.instanced_rendering {
+                        instanced_renderer.push_in_frustum(&item, shader, frustum)
+                    } else {
+                        item.draw_in_frustum(shader, frustum)
+                    };
+                // Avança as animações dos objs com esqueleto e troca malhas provisorias já carregadas
+                new_items.push(
+                    game_state.draw_queue[idx]
+                        .update_animation(game_state.frame_time)
+                        .resolve_assets(),
+                );
             }
         });
 
+    instanced_renderer.draw();
+
     // Atualiza estado do jogo
     game_state.draw_queue = new_items;
     game_state.should_add_obj = should_add_obj;
     game_state.score = score + game_state.score;
+
+    // Numero de objs simples fora do campo de visão nesta frame
+    game_state.culled_objects = culled_objects;
+
+    // Seleção é descartada se o obj selecionado foi removido
+    game_state.selected_obj = selected_obj;
+}
+
+// Posição na curva de bezier parametrizada pelo tempo (curr_x em [0..1]) e rotação dos objs que
+// se movem nela. Utilizada tanto no desenho quanto na seleção, para que os cliques atinjam os
+// objs na posição em que aparecem na tela
+pub fn bezier_motion(curr_x: f64) -> (glm::Vec4, Quaternion) {
+    let b03 = pow(1.0 - curr_x, 3.0) as f32;
+    let b23 = 3.0 * pow(curr_x, 2.0) as f32 * (1.0 - curr_x) as f32;
+    let b13 = 3.0 * curr_x as f32 * pow(1.10 - curr_x, 2.0) as f32;
+    let b33 = pow(curr_x, 3.0) as f32;
+
+    let p1 = glm::vec4(-2.5, 0.4, 0.0, 0.0);
+    let p2 = glm::vec4(-2.00, 1.8, 1.25, 0.0);
+    let p3 = glm::vec4(2.0, 1.8, 0.5, 0.0);
+    let p4 = glm::vec4(4.5, 0.0, 1.25, 0.0);
+    let curve = (p1 * b03 + p2 * b13 + p3 * b23 + p4 * b33) / 4.0;
+
+    // Rotação composta uma unica vez por frame
+    // O produto Z * Y aplica a rotação em Y e depois a rotação em Z, como o trot_y seguido de
+    // trot_x anterior (trot_x rotaciona em torno de Z), sem acumular erro entre as rotações
+    let bezier_angle = 2.0 * 3.14 * curr_x as f32;
+    let rotation = Quaternion::from_axis_angle(glm::vec4(0.0, 0.0, 1.0, 0.0), bezier_angle)
+        * Quaternion::from_axis_angle(glm::vec4(0.0, 1.0, 0.0, 0.0), bezier_angle);
+
+    (curve, rotation)
+}
+
+// Barra de progresso do carregamento na parte de baixo da tela, desenhada limpando retangulos
+// do framebuffer, sem shaders
+pub unsafe fn draw_loading_bar(progress: f32, window_size: &glm::Vec2) {
+    let width = (window_size.x * 0.6) as i32;
+    let height = (window_size.y * 0.02).max(4.0) as i32;
+    let x = ((window_size.x as i32) - width) / 2;
+    let y = (window_size.y * 0.08) as i32;
+
+    gl::Enable(gl::SCISSOR_TEST);
+    gl::Scissor(x, y, width, height);
+    gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+    gl::Clear(gl::COLOR_BUFFER_BIT);
+
+    gl::Scissor(
+        x,
+        y,
+        (width as f32 * glm::clamp(progress, 0.0, 1.0)) as i32,
+        height,
+    );
+    gl::ClearColor(0.9, 0.75, 0.2, 1.0);
+    gl::Clear(gl::COLOR_BUFFER_BIT);
+    gl::Disable(gl::SCISSOR_TEST);
+
+    gl::ClearColor(0.3, 0.3, 0.3, 1.0);
+}
+
+// Mira da camera em primeira pessoa, com duas linhas cruzadas no centro da tela, desenhada da
+// mesma forma que a barra de carregamento
+pub unsafe fn draw_crosshair(window_size: &glm::Vec2) {
+    let size = 16;
+    let thickness = 2;
+    let center_x = (window_size.x / 2.0) as i32;
+    let center_y = (window_size.y / 2.0) as i32;
+
+    gl::Enable(gl::SCISSOR_TEST);
+    gl::ClearColor(0.9, 0.9, 0.9, 1.0);
+    gl::Scissor(
+        center_x - size / 2,
+        center_y - thickness / 2,
+        size,
+        thickness,
+    );
+    gl::Clear(gl::COLOR_BUFFER_BIT);
+    gl::Scissor(
+        center_x - thickness / 2,
+        center_y - size / 2,
+        thickness,
+        size,
+    );
+    gl::Clear(gl::COLOR_BUFFER_BIT);
+    gl::Disable(gl::SCISSOR_TEST);
+
+    gl::ClearColor(0.3, 0.3, 0.3, 1.0);
+}
+
+// Seleciona o obj da fila de desenho atingido pelo raio que parte do cursor
+// Na camera em primeira pessoa o cursor fica escondido, então utiliza a mira no centro da tela
+pub fn pick_object(view: &View, game_state: &mut GameState) {
+    game_state.should_pick = false;
+
+    let cursor = if game_state.current_camera == 1 {
+        game_state.window_size / 2.0
+    } else {
+        game_state.cursor_position
+    };
+
+    let ray = match view.screen_ray(
+        cursor.x,
+        cursor.y,
+        game_state.window_size.x,
+        game_state.window_size.y,
+    ) {
+        Some(ray) => ray,
+        None => return,
+    };
+
+    // Com a curva de bezier ativa os objs são desenhados deslocados da posição na fila
+    let hit = if game_state.with_bezier {
+        let (curve, bezier_rotation) = bezier_motion(game_state.curr_x);
+        let displaced: Vec<SceneObject> = game_state
+            .draw_queue
+            .iter()
+            .map(|item| {
+                item.t_rot_quat(&bezier_rotation)
+                    .translate(curve.x, curve.y, curve.z)
+            })
+            .collect();
+        raycast_objects(displaced.as_slice(), &ray)
+    } else {
+        raycast_objects(game_state.draw_queue.as_slice(), &ray)
+    };
+
+    match hit {
+        Some((idx, hit)) => {
+            let obj = &game_state.draw_queue[idx];
+            let transform = obj.get_transform();
+
+            game_state.selected_obj = Some(idx);
+            println!("Obj selecionado: {}", idx);
+            println!("  Distancia: {:?}", hit.distance);
+            println!("  Ponto: {:?}", hit.point);
+            println!("  Normal: {:?}", hit.normal);
+            println!("  Obj simples atingido: {:?}", hit.object_index);
+            println!("  Posição: {:?}", transform.translation);
+            println!("  Escala: {:?}", transform.scale);
+            println!("  Rotação (euler): {:?}", transform.rotation.to_euler());
+            println!("  Cor: {:?}", obj.get_color());
+            println!("  Textura: {:?}", obj.get_texture_override());
+            println!("  Mapeamento de textura: {:?}", obj.get_texture_map_type());
+        }
+        None => game_state.selected_obj = None,
+    }
 }
 
 #[allow(dead_code)]
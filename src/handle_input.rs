@@ -3,6 +3,7 @@ use game_loop::GameState;
 use glutin::{DeviceEvent, Event, KeyboardInput, WindowEvent};
 use models::matrix::cross_product;
 use models::matrix::normalize_vector;
+use models::resources::TextureHandle;
 use models::scene_object::SceneObject;
 use world::lighting::Lighting;
 
@@ -20,12 +21,25 @@ pub fn handle_input(
     speed: &mut f32,
     main_obj: &mut SceneObject,
     plane: &mut SceneObject,
-    texture_pool: &Vec<&u32>,
+    texture_pool: &Vec<&TextureHandle>,
 ) {
     match event {
         Event::WindowEvent { event, .. } => match event {
             // Em caso de evento de fechamento de tela, seta controle do loop de eventos para encerrar
             WindowEvent::CloseRequested => game_state.should_break = true,
+
+            // Posição do cursor e tamanho da janela utilizados para selecionar objs
+            WindowEvent::CursorMoved { position, .. } => {
+                game_state.cursor_position = glm::vec2(position.x as f32, position.y as f32);
+            }
+            WindowEvent::Resized(size) => {
+                game_state.window_size = glm::vec2(size.width as f32, size.height as f32);
+            }
+            WindowEvent::MouseInput {
+                state: glutin::ElementState::Pressed,
+                button: glutin::MouseButton::Left,
+                ..
+            } => game_state.should_pick = true,
             WindowEvent::KeyboardInput {
                 input:
                     KeyboardInput {
@@ -95,6 +109,9 @@ pub fn handle_input(
                 (glutin::VirtualKeyCode::B, glutin::ElementState::Pressed) => {
                     game_state.with_bezier = !game_state.with_bezier;
                 }
+                (glutin::VirtualKeyCode::I, glutin::ElementState::Pressed) => {
+                    game_state.instanced_rendering = !game_state.instanced_rendering;
+                }
 
                 (glutin::VirtualKeyCode::Numpad7, glutin::ElementState::Pressed) => {
                     view.lighting.global = glm::vec3(
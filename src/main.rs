@@ -1,7 +1,13 @@
+extern crate base64;
+extern crate bevy_mikktspace;
+extern crate ddsfile;
 extern crate gl;
 extern crate glm;
+extern crate gltf;
 extern crate glutin;
 extern crate image;
+extern crate ktx2;
+extern crate png;
 extern crate rand;
 extern crate rayon;
 extern crate tobj;
@@ -30,7 +36,6 @@ fn main() {
 
     // Coloca janela no contexto atual
     let gl_window = unsafe { gl_window.make_current() }.unwrap();
-    gl_window.window().hide_cursor(true);
 
     // Carrega ponteiros para funções do openGL
     gl::load_with(|symbol| gl_window.get_proc_address(symbol) as *const _);
@@ -0,0 +1,497 @@
+use models::matrix::Transform;
+use models::quaternion::Quaternion;
+use models::skeleton::Skeleton;
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+// Interpolação entre keyframes de um canal
+// Step -> Valor do keyframe anterior, sem interpolação
+// Linear -> Interpolação linear de translações e escalas e esferica (slerp) de rotações
+pub enum Interpolation {
+    Step,
+    Linear,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Valores dos keyframes de um canal, um por tempo
+pub enum ChannelValues {
+    Translation(Vec<glm::Vec3>),
+    Rotation(Vec<Quaternion>),
+    Scale(Vec<glm::Vec3>),
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Keyframes de uma propriedade da transformação local de uma junta
+// joint -> Indice da junta em Skeleton::joints
+// times -> Tempo de cada keyframe em segundos, em ordem crescente
+pub struct Channel {
+    pub joint: usize,
+    pub interpolation: Interpolation,
+    pub times: Vec<f32>,
+    pub values: ChannelValues,
+}
+
+#[allow(dead_code)]
+impl Channel {
+    // Keyframes ao redor do tempo informado e fração entre eles
+    // Antes do primeiro e depois do ultimo keyframe o valor é constante
+    fn keyframes(&self, time: f32) -> (usize, usize, f32) {
+        let last = self.times.len() - 1;
+        if time <= self.times[0] {
+            return (0, 0, 0.0);
+        }
+        if time >= self.times[last] {
+            return (last, last, 0.0);
+        }
+
+        // Primeiro keyframe com tempo maior que o informado
+        let next = match self
+            .times
+            .binary_search_by(|t| t.partial_cmp(&time).unwrap_or(std::cmp::Ordering::Less))
+        {
+            Ok(exact) => return (exact, exact, 0.0),
+            Err(next) => next,
+        };
+        let previous = next - 1;
+
+        match self.interpolation {
+            Interpolation::Step => (previous, previous, 0.0),
+            Interpolation::Linear => {
+                let span = self.times[next] - self.times[previous];
+                let t = if span > 0.0 {
+                    (time - self.times[previous]) / span
+                } else {
+                    0.0
+                };
+                (previous, next, t)
+            }
+        }
+    }
+
+    // Escreve o valor do canal no tempo informado na transformação local da junta
+    pub fn apply(&self, time: f32, transform: &mut Transform) {
+        if self.times.len() == 0 {
+            return;
+        }
+        let (a, b, t) = self.keyframes(time);
+
+        match self.values {
+            ChannelValues::Translation(ref values) if b < values.len() => {
+                transform.translation = lerp_vec3(&values[a], &values[b], t);
+            }
+            ChannelValues::Rotation(ref values) if b < values.len() => {
+                transform.rotation = values[a].slerp(&values[b], t);
+            }
+            ChannelValues::Scale(ref values) if b < values.len() => {
+                transform.scale = lerp_vec3(&values[a], &values[b], t);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn lerp_vec3(a: &glm::Vec3, b: &glm::Vec3, t: f32) -> glm::Vec3 {
+    *a + (*b - *a) * t
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Animação de um esqueleto, como uma caminhada
+// duration -> Duração em segundos: tempo do ultimo keyframe entre todos os canais
+pub struct AnimationClip {
+    pub name: String,
+    pub duration: f32,
+    pub channels: Vec<Channel>,
+}
+
+#[allow(dead_code)]
+impl AnimationClip {
+    pub fn new(name: &str, channels: Vec<Channel>) -> Self {
+        let duration = channels
+            .iter()
+            .filter_map(|channel| channel.times.last())
+            .fold(0.0, |duration: f32, time| duration.max(*time));
+
+        AnimationClip {
+            name: name.to_string(),
+            duration,
+            channels,
+        }
+    }
+
+    // Pose local do esqueleto no tempo informado. Juntas sem canais ficam na pose de repouso
+    pub fn sample(&self, skeleton: &Skeleton, time: f32) -> Vec<Transform> {
+        let mut pose = skeleton.rest_pose();
+        for channel in self.channels.iter() {
+            if let Some(transform) = pose.get_mut(channel.joint) {
+                channel.apply(time, transform);
+            }
+        }
+        pose
+    }
+}
+
+// Mistura de duas poses junta a junta: weight 0 retorna a primeira e 1 a segunda
+#[allow(dead_code)]
+pub fn blend_poses(a: &[Transform], b: &[Transform], weight: f32) -> Vec<Transform> {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| {
+            Transform::new(
+                lerp_vec3(&a.translation, &b.translation, weight),
+                a.rotation.slerp(&b.rotation, weight),
+                lerp_vec3(&a.scale, &b.scale, weight),
+            )
+        })
+        .collect()
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone)]
+// Clip em reprodução
+// clip -> Indice em Skin::clips
+// time -> Tempo atual do clip em segundos
+// speed -> Multiplicador da velocidade de reprodução
+// weight -> Peso do clip na mistura das poses, em [0..1]
+// fade -> Variação do peso por segundo, usada nas transições entre clips
+// looping -> Reinicia o clip ao chegar no fim. Se falso, o clip para no ultimo keyframe
+pub struct ClipLayer {
+    pub clip: usize,
+    pub time: f32,
+    pub speed: f32,
+    pub weight: f32,
+    pub fade: f32,
+    pub looping: bool,
+}
+
+#[allow(dead_code)]
+impl ClipLayer {
+    pub fn new(clip: usize, weight: f32) -> Self {
+        ClipLayer {
+            clip,
+            time: 0.0,
+            speed: 1.0,
+            weight,
+            fade: 0.0,
+            looping: true,
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Estado de reprodução dos clips de um esqueleto
+// Os clips ativos são amostrados e misturados pelos pesos, normalizados pela soma
+pub struct Animator {
+    pub layers: Vec<ClipLayer>,
+}
+
+#[allow(dead_code)]
+impl Animator {
+    // Nenhum clip: o esqueleto fica na pose de repouso
+    pub fn new() -> Self {
+        Animator { layers: Vec::new() }
+    }
+
+    // Reproduz apenas o clip informado, em loop, desde o inicio
+    pub fn play(&self, clip: usize) -> Self {
+        Animator {
+            layers: vec![ClipLayer::new(clip, 1.0)],
+        }
+    }
+
+    // Reproduz apenas o clip informado, uma unica vez
+    pub fn play_once(&self, clip: usize) -> Self {
+        Animator {
+            layers: vec![ClipLayer {
+                looping: false,
+                ..ClipLayer::new(clip, 1.0)
+            }],
+        }
+    }
+
+    // Transição suave para o clip: o peso dele sobe até 1 em duration segundos, enquanto o
+    // dos demais desce até 0. Se o clip já estiver em reprodução, ele continua do tempo atual
+    pub fn cross_fade(&self, clip: usize, duration: f32) -> Self {
+        if duration <= 0.0 {
+            return self.play(clip);
+        }
+
+        let mut layers: Vec<ClipLayer> = self
+            .layers
+            .iter()
+            .filter(|layer| layer.clip != clip)
+            .map(|layer| ClipLayer {
+                fade: -1.0 / duration,
+                ..*layer
+            })
+            .collect();
+
+        let current = self.layers.iter().find(|layer| layer.clip == clip);
+        layers.push(ClipLayer {
+            fade: 1.0 / duration,
+            ..current.cloned().unwrap_or(ClipLayer::new(clip, 0.0))
+        });
+
+        Animator { layers }
+    }
+
+    // Mistura o clip aos demais com peso fixo (ex: aceno durante a caminhada)
+    // Um clip já em reprodução apenas tem o peso alterado
+    pub fn blend(&self, clip: usize, weight: f32) -> Self {
+        let weight = glm::clamp(weight, 0.0, 1.0);
+        let mut layers = self.layers.clone();
+
+        match layers.iter_mut().find(|layer| layer.clip == clip) {
+            Some(layer) => {
+                layer.weight = weight;
+                layer.fade = 0.0;
+            }
+            None => layers.push(ClipLayer::new(clip, weight)),
+        }
+        Animator { layers }
+    }
+
+    // Altera a velocidade de reprodução de todos os clips
+    pub fn with_speed(&self, speed: f32) -> Self {
+        Animator {
+            layers: self
+                .layers
+                .iter()
+                .map(|layer| ClipLayer { speed, ..*layer })
+                .collect(),
+        }
+    }
+
+    // Avança os clips em delta_time segundos e atualiza os pesos das transições
+    // Clips que saem de uma transição com peso 0 são descartados
+    pub fn update(&self, clips: &[AnimationClip], delta_time: f32) -> Self {
+        let layers = self
+            .layers
+            .iter()
+            .filter_map(|layer| {
+                let duration = clips.get(layer.clip)?.duration;
+
+                let mut time = layer.time + delta_time * layer.speed;
+                time = if duration <= 0.0 {
+                    0.0
+                } else if layer.looping {
+                    time.rem_euclid(duration)
+                } else {
+                    glm::clamp(time, 0.0, duration)
+                };
+
+                let weight = glm::clamp(layer.weight + layer.fade * delta_time, 0.0, 1.0);
+                if layer.fade < 0.0 && weight <= 0.0 {
+                    return None;
+                }
+
+                // Transição concluida
+                let fade = if layer.fade > 0.0 && weight >= 1.0 {
+                    0.0
+                } else {
+                    layer.fade
+                };
+
+                Some(ClipLayer {
+                    time,
+                    weight,
+                    fade,
+                    ..*layer
+                })
+            })
+            .collect();
+
+        Animator { layers }
+    }
+
+    // Pose local misturada dos clips ativos. Cada clip é interpolado com a mistura dos
+    // anteriores pela fração do seu peso no total acumulado, o que equivale a media ponderada
+    pub fn pose(&self, skeleton: &Skeleton, clips: &[AnimationClip]) -> Vec<Transform> {
+        let mut pose = skeleton.rest_pose();
+        let mut total_weight = 0.0;
+
+        for layer in self.layers.iter().filter(|layer| layer.weight > 0.0) {
+            let clip = match clips.get(layer.clip) {
+                Some(clip) => clip,
+                None => continue,
+            };
+            let sample = clip.sample(skeleton, layer.time);
+
+            total_weight = total_weight + layer.weight;
+            pose = blend_poses(&pose, &sample, layer.weight / total_weight);
+        }
+        pose
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::matrix::identity_matrix;
+    use models::skeleton::Joint;
+
+    fn translation_channel(interpolation: Interpolation) -> Channel {
+        Channel {
+            joint: 0,
+            interpolation,
+            times: vec![1.0, 2.0, 4.0],
+            values: ChannelValues::Translation(vec![
+                glm::vec3(0.0, 0.0, 0.0),
+                glm::vec3(2.0, 0.0, 0.0),
+                glm::vec3(2.0, 4.0, 0.0),
+            ]),
+        }
+    }
+
+    // Clip que mantém a junta 0 na posição informada durante 1 segundo
+    fn hold_clip(name: &str, position: glm::Vec3) -> AnimationClip {
+        AnimationClip::new(
+            name,
+            vec![Channel {
+                joint: 0,
+                interpolation: Interpolation::Linear,
+                times: vec![0.0, 1.0],
+                values: ChannelValues::Translation(vec![position, position]),
+            }],
+        )
+    }
+
+    fn single_joint() -> Skeleton {
+        Skeleton::new(vec![Joint {
+            name: "root".to_string(),
+            parent: None,
+            rest: Transform::identity(),
+            inverse_bind: identity_matrix(),
+        }])
+    }
+
+    fn clips() -> Vec<AnimationClip> {
+        vec![
+            hold_clip("a", glm::vec3(2.0, 0.0, 0.0)),
+            hold_clip("b", glm::vec3(0.0, 4.0, 0.0)),
+        ]
+    }
+
+    fn assert_vec3_near(a: glm::Vec3, b: glm::Vec3) {
+        assert!(glm::distance(a, b) < 1e-5, "{:?} diferente de {:?}", a, b);
+    }
+
+    // (clip, peso) das camadas do animator
+    fn weights(animator: &Animator) -> Vec<(usize, f32)> {
+        animator
+            .layers
+            .iter()
+            .map(|layer| (layer.clip, layer.weight))
+            .collect()
+    }
+
+    #[test]
+    fn keyframes_clamp_at_both_ends() {
+        for interpolation in [Interpolation::Step, Interpolation::Linear].iter() {
+            let channel = translation_channel(*interpolation);
+            assert_eq!(channel.keyframes(0.0), (0, 0, 0.0));
+            assert_eq!(channel.keyframes(1.0), (0, 0, 0.0));
+            assert_eq!(channel.keyframes(4.0), (2, 2, 0.0));
+            assert_eq!(channel.keyframes(9.0), (2, 2, 0.0));
+            assert_eq!(channel.keyframes(2.0), (1, 1, 0.0));
+        }
+    }
+
+    #[test]
+    fn step_and_linear_keyframes() {
+        let step = translation_channel(Interpolation::Step);
+        assert_eq!(step.keyframes(1.5), (0, 0, 0.0));
+        assert_eq!(step.keyframes(3.0), (1, 1, 0.0));
+
+        let linear = translation_channel(Interpolation::Linear);
+        assert_eq!(linear.keyframes(1.5), (0, 1, 0.5));
+        assert_eq!(linear.keyframes(3.0), (1, 2, 0.5));
+
+        let mut transform = Transform::identity();
+        step.apply(3.0, &mut transform);
+        assert_vec3_near(transform.translation, glm::vec3(2.0, 0.0, 0.0));
+        linear.apply(3.0, &mut transform);
+        assert_vec3_near(transform.translation, glm::vec3(2.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn cross_fade_ramps_weights_and_drops_faded_layer() {
+        let clips = clips();
+        let skeleton = single_joint();
+
+        let animator = Animator::new().play(0).cross_fade(1, 0.5);
+        assert_eq!(weights(&animator), vec![(0, 1.0), (1, 0.0)]);
+
+        let halfway = animator.update(&clips, 0.25);
+        assert_eq!(weights(&halfway), vec![(0, 0.5), (1, 0.5)]);
+        assert_vec3_near(
+            halfway.pose(&skeleton, &clips)[0].translation,
+            glm::vec3(1.0, 2.0, 0.0),
+        );
+
+        // O clip que sai é descartado e a transição do que entra é concluida
+        let done = halfway.update(&clips, 0.25);
+        assert_eq!(weights(&done), vec![(1, 1.0)]);
+        assert_eq!(done.layers[0].fade, 0.0);
+        assert_vec3_near(
+            done.pose(&skeleton, &clips)[0].translation,
+            glm::vec3(0.0, 4.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn cross_fade_keeps_time_of_playing_clip() {
+        let clips = clips();
+        let animator = Animator::new()
+            .play(0)
+            .blend(1, 0.5)
+            .update(&clips, 0.4)
+            .cross_fade(1, 1.0);
+
+        assert_eq!(weights(&animator), vec![(0, 1.0), (1, 0.5)]);
+        assert!((animator.layers[1].time - 0.4).abs() < 1e-6);
+        assert_eq!(animator.layers[0].fade, -1.0);
+        assert_eq!(animator.layers[1].fade, 1.0);
+
+        // Sem duração, a troca é imediata
+        assert_eq!(weights(&animator.cross_fade(0, 0.0)), vec![(0, 1.0)]);
+    }
+
+    #[test]
+    fn looping_and_play_once_times() {
+        let clips = clips();
+        let looping = Animator::new().play(0).update(&clips, 1.5);
+        assert!((looping.layers[0].time - 0.5).abs() < 1e-6);
+
+        let once = Animator::new().play_once(0).update(&clips, 1.5);
+        assert_eq!(once.layers[0].time, 1.0);
+    }
+
+    #[test]
+    fn pose_is_weighted_average() {
+        let clips = clips();
+        let skeleton = single_joint();
+
+        let animator = Animator::new().blend(0, 0.75).blend(1, 0.25);
+        assert_vec3_near(
+            animator.pose(&skeleton, &clips)[0].translation,
+            glm::vec3(1.5, 1.0, 0.0),
+        );
+
+        // Apenas a proporção entre os pesos importa
+        let animator = Animator::new().blend(0, 0.3).blend(1, 0.1);
+        assert_vec3_near(
+            animator.pose(&skeleton, &clips)[0].translation,
+            glm::vec3(1.5, 1.0, 0.0),
+        );
+
+        // Sem clips, pose de repouso
+        assert_vec3_near(
+            Animator::new().pose(&skeleton, &clips)[0].translation,
+            glm::vec3(0.0, 0.0, 0.0),
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use models::load_texture::decode_texture;
+use models::load_texture::decode_texture_from_memory;
+use models::load_texture::load_placeholder_texture;
+use models::load_texture::upload_decoded_texture;
+use models::load_texture::DecodedTexture;
+use models::load_texture::TextureError;
+use models::load_texture::TextureUsage;
+use models::material::Material;
+use models::mesh_cache::load_cached;
+use models::mesh_data::MeshData;
+use models::mesh_data::MeshError;
+use models::normals::NormalMode;
+use models::resources::cached_mesh;
+use models::resources::cached_texture;
+use models::resources::register_mesh;
+use models::resources::register_texture;
+use models::resources::GpuMesh;
+use models::resources::MeshHandle;
+use models::resources::ResourceManager;
+use models::resources::Texture;
+use models::resources::TextureHandle;
+use models::sampler::SamplerDesc;
+use rayon;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::mpsc::channel;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::sync::Weak;
+use std::time::Duration;
+use std::time::Instant;
+
+// Carregamento assincrono de assets: a leitura e o processamento dos arquivos (decodificação de
+// imagens, parse de obj, calculo de normais, tangentes e niveis de detalhe) rodam no pool do rayon,
+// e apenas o envio para a GPU acontece na thread do contexto OpenGL, em poll_assets.
+// Até o envio, os pedidos recebem recursos provisorios:
+// - Texturas recebem uma textura de 1 pixel, e a imagem é enviada depois para a mesma textura,
+//   então os handles já distribuidos passam a exibir a imagem sem nenhuma troca
+// - Malhas recebem uma caixa unitaria marcada com a chave do pedido (GpuMesh::pending), trocada
+//   pela malha carregada em ObjModel::resolve_assets
+
+// Asset decodificado em uma thread do rayon, aguardando o envio para a GPU
+enum DecodedAsset {
+    Mesh {
+        path: String,
+        normal_mode: NormalMode,
+        mesh: Result<MeshData, MeshError>,
+    },
+    Texture {
+        key: String,
+        image: Result<DecodedTexture, TextureError>,
+    },
+}
+
+#[allow(dead_code)]
+// Pedidos de carregamento da thread do contexto OpenGL
+// requested/finished -> Quantidade de assets pedidos e já enviados para a GPU (ou que falharam)
+// placeholders -> Malhas provisorias dos pedidos em andamento, por chave
+// textures -> Texturas provisorias dos pedidos em andamento, que recebem a imagem no envio
+// meshes -> Malhas carregadas, com a malha provisoria que substituem. São mantidas vivas pelo
+//           carregador enquanto a malha provisoria existir, pois objs clonados de objs provisorios
+//           (ex: pools de objs aleatorios) podem ser resolvidos a qualquer momento
+pub struct AssetLoader {
+    sender: Sender<DecodedAsset>,
+    receiver: Receiver<DecodedAsset>,
+    requested: usize,
+    finished: usize,
+    placeholders: HashMap<String, MeshHandle>,
+    textures: HashMap<String, TextureHandle>,
+    meshes: HashMap<String, (Weak<GpuMesh>, MeshHandle)>,
+}
+
+thread_local! {
+    static LOADER: RefCell<AssetLoader> = RefCell::new(AssetLoader::new());
+}
+
+#[allow(dead_code)]
+impl AssetLoader {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        AssetLoader {
+            sender,
+            receiver,
+            requested: 0,
+            finished: 0,
+            placeholders: HashMap::new(),
+            textures: HashMap::new(),
+            meshes: HashMap::new(),
+        }
+    }
+}
+
+// Pede o carregamento de uma malha, como resources::load_mesh, retornando imediatamente
+// Se a malha ainda não estiver carregada, o handle é de uma malha provisoria
+#[allow(dead_code)]
+pub fn request_mesh(path: &str, normal_mode: NormalMode) -> MeshHandle {
+    let source = path.to_string();
+    request_mesh_with(path, normal_mode, move || load_cached(&source, normal_mode))
+}
+
+// Malha gerada por uma função executada no rayon, compartilhada pela chave informada como em
+// resources::load_generated_mesh
+#[allow(dead_code)]
+pub fn request_generated_mesh<F>(key: &str, generate: F) -> MeshHandle
+where
+    F: FnOnce() -> Result<MeshData, MeshError> + Send + 'static,
+{
+    request_mesh_with(
+        &format!("generated:{}", key),
+        NormalMode::Imported,
+        generate,
+    )
+}
+
+fn request_mesh_with<F>(path: &str, normal_mode: NormalMode, load: F) -> MeshHandle
+where
+    F: FnOnce() -> Result<MeshData, MeshError> + Send + 'static,
+{
+    if let Some(mesh) = cached_mesh(path, normal_mode) {
+        return mesh;
+    }
+
+    LOADER.with(|loader| {
+        let mut loader = loader.borrow_mut();
+        let key = ResourceManager::mesh_key(path, normal_mode);
+        if let Some(placeholder) = loader.placeholders.get(&key) {
+            return placeholder.clone();
+        }
+
+        let placeholder = Arc::new(GpuMesh::placeholder(&key));
+        loader.placeholders.insert(key, placeholder.clone());
+        loader.requested = loader.requested + 1;
+
+        let sender = loader.sender.clone();
+        let path = path.to_string();
+        rayon::spawn(move || {
+            let mesh = load();
+            // O carregador só é descartado com a thread do contexto, quando não há mais quem receba
+            let _ = sender.send(DecodedAsset::Mesh {
+                path,
+                normal_mode,
+                mesh,
+            });
+        });
+        placeholder
+    })
+}
+
+// Pede o carregamento de uma textura, como resources::load_texture_resource, retornando
+// imediatamente. O handle é o mesmo antes e depois do carregamento
+#[allow(dead_code)]
+pub fn request_texture(path: &str) -> TextureHandle {
+    request_texture_with(path, path, TextureUsage::Color)
+}
+
+// Textura com os parametros de amostragem informados, ver
+// resources::load_texture_resource_with_sampler
+#[allow(dead_code)]
+pub fn request_texture_with_sampler(path: &str, sampler: &SamplerDesc) -> TextureHandle {
+    let texture = request_texture(path);
+    texture.set_sampler(sampler);
+    texture
+}
+
+// Normal maps são texturas lineares, ver resources::load_normal_map_resource
+#[allow(dead_code)]
+pub fn request_normal_map(path: &str) -> TextureHandle {
+    request_texture_with(&format!("{}#linear", path), path, TextureUsage::Data)
+}
+
+fn request_texture_with(key: &str, path: &str, usage: TextureUsage) -> TextureHandle {
+    // A textura provisoria é registrada no gerenciador, então pedidos repetidos recebem o
+    // mesmo handle
+    if let Some(texture) = cached_texture(key) {
+        return texture;
+    }
+
+    let path = path.to_string();
+    let texture = request_decoded_texture(Some(key), move || decode_texture(&path, usage));
+    register_texture(key, &texture);
+    texture
+}
+
+// Pede o carregamento de uma textura embutida em um modelo, como
+// resources::load_texture_resource_from_memory. Texturas embutidas não são compartilhadas, então
+// cada pedido cria uma textura
+#[allow(dead_code)]
+pub fn request_texture_from_memory(bytes: &[u8], usage: TextureUsage) -> TextureHandle {
+    let bytes = bytes.to_vec();
+    request_decoded_texture(None, move || decode_texture_from_memory(&bytes, usage))
+}
+
+// Cria a textura provisoria e decodifica a imagem no rayon. Sem chave (texturas não
+// compartilhadas), o pedido é identificado pela textura provisoria
+fn request_decoded_texture<F>(key: Option<&str>, decode: F) -> TextureHandle
+where
+    F: FnOnce() -> Result<DecodedTexture, TextureError> + Send + 'static,
+{
+    let id = unsafe { load_placeholder_texture() };
+    let texture = Arc::new(Texture::new(id, &SamplerDesc::new()));
+    let key = key.map_or(format!("embedded#{}", id), |key| key.to_string());
+
+    LOADER.with(|loader| {
+        let mut loader = loader.borrow_mut();
+        loader.textures.insert(key.clone(), texture.clone());
+        loader.requested = loader.requested + 1;
+
+        let sender = loader.sender.clone();
+        rayon::spawn(move || {
+            let image = decode();
+            let _ = sender.send(DecodedAsset::Texture { key, image });
+        });
+    });
+    texture
+}
+
+// Envia para a GPU os assets já decodificados, até esgotar o tempo informado (no minimo um
+// asset, se houver). Deve ser chamada a cada frame. Retorna a quantidade de assets enviados
+#[allow(dead_code)]
+pub fn poll_assets(budget: Duration) -> usize {
+    let start = Instant::now();
+    let mut uploaded = 0;
+
+    loop {
+        if uploaded > 0 && start.elapsed() >= budget {
+            break;
+        }
+        // O registro não fica emprestado durante o envio: materiais das malhas carregam texturas
+        let asset = match LOADER.with(|loader| loader.borrow().receiver.try_recv()) {
+            Ok(asset) => asset,
+            Err(_) => break,
+        };
+        upload_asset(asset);
+        uploaded = uploaded + 1;
+    }
+
+    // Malhas cujas malhas provisorias já foram todas resolvidas ou descartadas não são mais
+    // procuradas por resolve_mesh
+    LOADER.with(|loader| {
+        loader
+            .borrow_mut()
+            .meshes
+            .retain(|_, (placeholder, _)| placeholder.strong_count() > 0)
+    });
+    uploaded
+}
+
+fn upload_asset(asset: DecodedAsset) {
+    match asset {
+        DecodedAsset::Mesh {
+            path,
+            normal_mode,
+            mesh,
+        } => {
+            let key = ResourceManager::mesh_key(&path, normal_mode);
+            // Em caso de falha a malha provisoria continua sendo desenhada
+            let loaded = match mesh {
+                Ok(mesh) => {
+                    // As texturas dos materiais também são decodificadas no rayon
+                    let mesh = Arc::new(GpuMesh::upload_with_materials(
+                        Arc::new(mesh),
+                        Material::request,
+                    ));
+                    register_mesh(&path, normal_mode, &mesh);
+                    Some(mesh)
+                }
+                Err(error) => {
+                    println!("{}", error);
+                    None
+                }
+            };
+
+            LOADER.with(|loader| {
+                let mut loader = loader.borrow_mut();
+                let placeholder = loader.placeholders.remove(&key);
+                if let (Some(placeholder), Some(mesh)) = (placeholder, loaded) {
+                    loader
+                        .meshes
+                        .insert(key, (Arc::downgrade(&placeholder), mesh));
+                }
+                loader.finished = loader.finished + 1;
+            });
+        }
+        DecodedAsset::Texture { key, image } => {
+            let texture = LOADER.with(|loader| {
+                let mut loader = loader.borrow_mut();
+                loader.finished = loader.finished + 1;
+                loader.textures.remove(&key)
+            });
+
+            match (texture, image) {
+                (Some(texture), Ok(image)) => {
+                    unsafe { upload_decoded_texture(texture.id, &image) };
+                    texture.set_flip_uv(image.flip_uv());
+                }
+                (_, Err(error)) => println!("{}", error),
+                _ => {}
+            }
+        }
+    }
+}
+
+// Malha carregada que substitui a malha provisoria informada, se ela já estiver pronta
+#[allow(dead_code)]
+pub fn resolve_mesh(mesh: &MeshHandle) -> Option<MeshHandle> {
+    let key = mesh.pending.as_ref()?;
+    LOADER.with(|loader| {
+        loader
+            .borrow()
+            .meshes
+            .get(key)
+            .map(|(_, mesh)| mesh.clone())
+    })
+}
+
+// Fração dos assets pedidos já carregados, em [0..1]. 1 se nenhum asset foi pedido
+#[allow(dead_code)]
+pub fn loading_progress() -> f32 {
+    LOADER.with(|loader| {
+        let loader = loader.borrow();
+        if loader.requested == 0 {
+            1.0
+        } else {
+            loader.finished as f32 / loader.requested as f32
+        }
+    })
+}
+
+// Verdadeiro enquanto houver assets pedidos sem envio para a GPU
+#[allow(dead_code)]
+pub fn is_loading() -> bool {
+    LOADER.with(|loader| {
+        let loader = loader.borrow();
+        loader.finished < loader.requested
+    })
+}
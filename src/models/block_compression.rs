@@ -0,0 +1,775 @@
+use std::cell::Cell;
+
+use gl;
+use gl::types::GLenum;
+use models::load_texture::PixelFormat;
+use models::load_texture::TextureImage;
+use models::load_texture::TextureUsage;
+use models::sampler::has_extension;
+
+// Texturas comprimidas em blocos (BCn): cada bloco de 4x4 pixels ocupa 8 ou 16 bytes e é
+// descomprimido pela GPU na amostragem, ocupando de 4 a 8 vezes menos memoria que RGBA8
+// Quando o driver não aceita o formato, os blocos são descomprimidos na CPU (ver decompress)
+
+// Constantes das extensões GL_EXT_texture_compression_s3tc e GL_EXT_texture_sRGB, fora do
+// OpenGL 3.3 core
+static COMPRESSED_RGBA_S3TC_DXT1: GLenum = 0x83F1;
+static COMPRESSED_RGBA_S3TC_DXT5: GLenum = 0x83F3;
+static COMPRESSED_SRGB_ALPHA_S3TC_DXT1: GLenum = 0x8C4D;
+static COMPRESSED_SRGB_ALPHA_S3TC_DXT5: GLenum = 0x8C4F;
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+// Formatos de bloco suportados
+// Bc1 -> RGB com alpha de 1 bit, 8 bytes por bloco (DXT1)
+// Bc3 -> RGBA, com o alpha em um bloco separado, 16 bytes por bloco (DXT5)
+// Bc5 -> Dois canais (RG), 16 bytes por bloco. Usado em normal maps, o shader reconstroi o z
+// Bc7 -> RGBA de alta qualidade, 16 bytes por bloco
+pub enum BlockFormat {
+    Bc1,
+    Bc3,
+    Bc5,
+    Bc7,
+}
+
+impl BlockFormat {
+    pub fn block_bytes(&self) -> usize {
+        match *self {
+            BlockFormat::Bc1 => 8,
+            _ => 16,
+        }
+    }
+
+    // Tamanho em bytes de uma imagem do formato. Imagens menores que um bloco ocupam um bloco
+    pub fn image_bytes(&self, width: u32, height: u32) -> usize {
+        let blocks_x = ((width + 3) / 4).max(1) as usize;
+        let blocks_y = ((height + 3) / 4).max(1) as usize;
+        blocks_x * blocks_y * self.block_bytes()
+    }
+
+    // Formato interno do OpenGL, None se o driver não aceitar o formato
+    // Bc5 não tem variante sRGB e é sempre lido como dados
+    pub fn gl_internal_format(&self, usage: TextureUsage) -> Option<GLenum> {
+        let support = compression_support();
+        let color = usage == TextureUsage::Color;
+        match *self {
+            BlockFormat::Bc1 if color && support.s3tc_srgb => Some(COMPRESSED_SRGB_ALPHA_S3TC_DXT1),
+            BlockFormat::Bc1 if !color && support.s3tc => Some(COMPRESSED_RGBA_S3TC_DXT1),
+            BlockFormat::Bc3 if color && support.s3tc_srgb => Some(COMPRESSED_SRGB_ALPHA_S3TC_DXT5),
+            BlockFormat::Bc3 if !color && support.s3tc => Some(COMPRESSED_RGBA_S3TC_DXT5),
+            BlockFormat::Bc5 => Some(gl::COMPRESSED_RG_RGTC2),
+            BlockFormat::Bc7 if color && support.bptc => Some(gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM),
+            BlockFormat::Bc7 if !color && support.bptc => Some(gl::COMPRESSED_RGBA_BPTC_UNORM),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+// Familias de formatos comprimidos aceitas pelo driver. RGTC (Bc5) é core desde o OpenGL 3.0
+struct CompressionSupport {
+    s3tc: bool,
+    s3tc_srgb: bool,
+    bptc: bool,
+}
+
+thread_local! {
+    // Formatos aceitos pelo driver da thread do contexto OpenGL, consultados no primeiro envio
+    static COMPRESSION_SUPPORT: Cell<Option<CompressionSupport>> = Cell::new(None);
+}
+
+fn compression_support() -> CompressionSupport {
+    COMPRESSION_SUPPORT.with(|cached| {
+        if let Some(support) = cached.get() {
+            return support;
+        }
+        let support = unsafe {
+            let mut major = 0;
+            let mut minor = 0;
+            gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+            gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+
+            let s3tc = has_extension("GL_EXT_texture_compression_s3tc");
+            CompressionSupport {
+                s3tc,
+                s3tc_srgb: s3tc
+                    && (has_extension("GL_EXT_texture_sRGB")
+                        || has_extension("GL_EXT_texture_compression_s3tc_srgb")),
+                bptc: (major, minor) >= (4, 2) || has_extension("GL_ARB_texture_compression_bptc"),
+            }
+        };
+        cached.set(Some(support));
+        support
+    })
+}
+
+#[derive(Debug, Clone)]
+// Cadeia de mipmaps comprimida, do nivel 0 ao menor, lida de um container (ver texture_container.rs)
+// width/height -> Tamanho do nivel 0. Cada nivel tem a metade do tamanho do anterior
+pub struct CompressedLevels {
+    pub format: BlockFormat,
+    pub usage: TextureUsage,
+    pub width: u32,
+    pub height: u32,
+    pub levels: Vec<Vec<u8>>,
+}
+
+#[allow(dead_code)]
+impl CompressedLevels {
+    pub fn level_size(&self, level: usize) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+
+    // Descomprime todos os niveis, para drivers sem suporte ao formato
+    pub fn decompress(&self) -> Vec<TextureImage> {
+        (0..self.levels.len())
+            .map(|level| self.decompress_level(level))
+            .collect()
+    }
+
+    // Descomprime um nivel. Bc5 vira Rgb8 com azul 0, como na amostragem de RGTC pela GPU
+    pub fn decompress_level(&self, level: usize) -> TextureImage {
+        let (width, height) = self.level_size(level);
+        let (format, usage) = match self.format {
+            BlockFormat::Bc5 => (PixelFormat::Rgb8, TextureUsage::Data),
+            _ => (PixelFormat::Rgba8, self.usage),
+        };
+        let channels = format.channels();
+        let blocks_x = ((width + 3) / 4) as usize;
+        let mut pixels = vec![0; (width * height) as usize * channels];
+
+        for (id, block) in self.levels[level]
+            .chunks(self.format.block_bytes())
+            .enumerate()
+        {
+            let decoded = match self.format {
+                BlockFormat::Bc1 => decode_bc1(block, false),
+                BlockFormat::Bc3 => decode_bc3(block),
+                BlockFormat::Bc5 => decode_bc5(block),
+                BlockFormat::Bc7 => decode_bc7(block),
+            };
+            let (block_x, block_y) = ((id % blocks_x) * 4, (id / blocks_x) * 4);
+
+            // Blocos na borda de imagens com lados não multiplos de 4 são cortados
+            for (pixel, color) in decoded.iter().enumerate() {
+                let x = block_x + pixel % 4;
+                let y = block_y + pixel / 4;
+                if x < width as usize && y < height as usize {
+                    let offset = (y * width as usize + x) * channels;
+                    pixels[offset..offset + channels].copy_from_slice(&color[..channels]);
+                }
+            }
+        }
+
+        TextureImage {
+            width,
+            height,
+            format,
+            usage,
+            pixels,
+        }
+    }
+}
+
+// Bloco de cor do BC1: duas cores em 5:6:5 e 2 bits por pixel escolhendo entre elas e duas cores
+// intermediarias. Com a primeira cor menor ou igual à segunda, o bloco tem uma cor intermediaria
+// e preto transparente, exceto dentro do BC3 (four_colors), onde o bloco sempre tem 4 cores
+fn decode_bc1(block: &[u8], four_colors: bool) -> [[u8; 4]; 16] {
+    let color0 = u16::from_le_bytes([block[0], block[1]]);
+    let color1 = u16::from_le_bytes([block[2], block[3]]);
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+    let c0 = expand_565(color0);
+    let c1 = expand_565(color1);
+    let mix = |a: u8, b: u8, wa: u32, wb: u32| ((a as u32 * wa + b as u32 * wb) / (wa + wb)) as u8;
+
+    let mut palette = [c0, c1, [0; 4], [0; 4]];
+    if four_colors || color0 > color1 {
+        for channel in 0..3 {
+            palette[2][channel] = mix(c0[channel], c1[channel], 2, 1);
+            palette[3][channel] = mix(c0[channel], c1[channel], 1, 2);
+        }
+        palette[2][3] = 255;
+        palette[3][3] = 255;
+    } else {
+        for channel in 0..3 {
+            palette[2][channel] = mix(c0[channel], c1[channel], 1, 1);
+        }
+        palette[2][3] = 255;
+    }
+
+    let mut pixels = [[0; 4]; 16];
+    for (pixel, color) in pixels.iter_mut().enumerate() {
+        *color = palette[((indices >> (2 * pixel)) & 3) as usize];
+    }
+    pixels
+}
+
+fn expand_565(color: u16) -> [u8; 4] {
+    let r = ((color >> 11) & 31) as u8;
+    let g = ((color >> 5) & 63) as u8;
+    let b = (color & 31) as u8;
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+        255,
+    ]
+}
+
+// BC3: bloco de alpha no formato do BC4 seguido de um bloco de cor do BC1
+fn decode_bc3(block: &[u8]) -> [[u8; 4]; 16] {
+    let alpha = decode_bc4(&block[..8]);
+    let mut pixels = decode_bc1(&block[8..], true);
+    for (color, alpha) in pixels.iter_mut().zip(alpha.iter()) {
+        color[3] = *alpha;
+    }
+    pixels
+}
+
+// BC5: dois blocos do BC4, com o canal vermelho e o verde
+fn decode_bc5(block: &[u8]) -> [[u8; 4]; 16] {
+    let red = decode_bc4(&block[..8]);
+    let green = decode_bc4(&block[8..]);
+    let mut pixels = [[0, 0, 0, 255]; 16];
+    for (pixel, color) in pixels.iter_mut().enumerate() {
+        color[0] = red[pixel];
+        color[1] = green[pixel];
+    }
+    pixels
+}
+
+// Bloco de um canal: dois valores e 3 bits por pixel escolhendo entre 8 valores interpolados, ou
+// 6 interpolados mais 0 e 255 se o primeiro valor for menor ou igual ao segundo
+fn decode_bc4(block: &[u8]) -> [u8; 16] {
+    let value0 = block[0] as u32;
+    let value1 = block[1] as u32;
+    let mut palette = [value0, value1, 0, 0, 0, 0, 0, 255];
+    if value0 > value1 {
+        for id in 2..8 {
+            palette[id] = ((8 - id as u32) * value0 + (id as u32 - 1) * value1) / 7;
+        }
+    } else {
+        for id in 2..6 {
+            palette[id] = ((6 - id as u32) * value0 + (id as u32 - 1) * value1) / 5;
+        }
+    }
+
+    let mut indices = 0u64;
+    for (id, byte) in block[2..8].iter().enumerate() {
+        indices |= (*byte as u64) << (8 * id);
+    }
+    let mut values = [0; 16];
+    for (pixel, value) in values.iter_mut().enumerate() {
+        *value = palette[((indices >> (3 * pixel)) & 7) as usize] as u8;
+    }
+    values
+}
+
+// Leitura de campos de bits de um bloco, do bit menos significativo do primeiro byte em diante
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn read(&mut self, count: usize) -> u32 {
+        let mut value = 0;
+        for bit in 0..count {
+            let byte = self.bytes[self.position / 8];
+            value |= (((byte >> (self.position % 8)) & 1) as u32) << bit;
+            self.position = self.position + 1;
+        }
+        value
+    }
+}
+
+// Layout dos 8 modos do BC7, escolhidos pelo primeiro bit 1 do bloco
+// subsets -> Quantidade de regiões do bloco, cada uma com o seu par de cores
+// partition_bits -> Bits do indice da tabela de regiões
+// rotation_bits/index_selection_bits -> Troca do alpha com um canal e escolha dos indices (modos 4 e 5)
+// color_bits/alpha_bits -> Precisão das cores, sem contar os p-bits. Sem alpha, o alpha é 255
+// endpoint_pbits/shared_pbits -> Bit menos significativo de cada cor, ou compartilhado pelo par
+// index_bits/index2_bits -> Bits dos indices de interpolação, e do segundo conjunto de indices
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: usize,
+    rotation_bits: usize,
+    index_selection_bits: usize,
+    color_bits: usize,
+    alpha_bits: usize,
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: usize,
+    index2_bits: usize,
+}
+
+static BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode {
+        subsets: 3,
+        partition_bits: 4,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 4,
+        alpha_bits: 0,
+        endpoint_pbits: true,
+        shared_pbits: false,
+        index_bits: 3,
+        index2_bits: 0,
+    },
+    Bc7Mode {
+        subsets: 2,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 6,
+        alpha_bits: 0,
+        endpoint_pbits: false,
+        shared_pbits: true,
+        index_bits: 3,
+        index2_bits: 0,
+    },
+    Bc7Mode {
+        subsets: 3,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 5,
+        alpha_bits: 0,
+        endpoint_pbits: false,
+        shared_pbits: false,
+        index_bits: 2,
+        index2_bits: 0,
+    },
+    Bc7Mode {
+        subsets: 2,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 7,
+        alpha_bits: 0,
+        endpoint_pbits: true,
+        shared_pbits: false,
+        index_bits: 2,
+        index2_bits: 0,
+    },
+    Bc7Mode {
+        subsets: 1,
+        partition_bits: 0,
+        rotation_bits: 2,
+        index_selection_bits: 1,
+        color_bits: 5,
+        alpha_bits: 6,
+        endpoint_pbits: false,
+        shared_pbits: false,
+        index_bits: 2,
+        index2_bits: 3,
+    },
+    Bc7Mode {
+        subsets: 1,
+        partition_bits: 0,
+        rotation_bits: 2,
+        index_selection_bits: 0,
+        color_bits: 7,
+        alpha_bits: 8,
+        endpoint_pbits: false,
+        shared_pbits: false,
+        index_bits: 2,
+        index2_bits: 2,
+    },
+    Bc7Mode {
+        subsets: 1,
+        partition_bits: 0,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 7,
+        alpha_bits: 7,
+        endpoint_pbits: true,
+        shared_pbits: false,
+        index_bits: 4,
+        index2_bits: 0,
+    },
+    Bc7Mode {
+        subsets: 2,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 5,
+        alpha_bits: 5,
+        endpoint_pbits: true,
+        shared_pbits: false,
+        index_bits: 2,
+        index2_bits: 0,
+    },
+];
+
+// Pesos de interpolação entre as duas cores de uma região, em 64 avos, por bits de indice
+static BC7_WEIGHTS2: [u32; 4] = [0, 21, 43, 64];
+static BC7_WEIGHTS3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+static BC7_WEIGHTS4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+fn bc7_weight(bits: usize, index: u32) -> u32 {
+    match bits {
+        2 => BC7_WEIGHTS2[index as usize],
+        3 => BC7_WEIGHTS3[index as usize],
+        _ => BC7_WEIGHTS4[index as usize],
+    }
+}
+
+// Decodifica um bloco do BC7. Blocos sem modo (primeiro byte 0) são pretos transparentes
+fn decode_bc7(block: &[u8]) -> [[u8; 4]; 16] {
+    let mode_id = match (0..8).find(|&mode| block[0] & (1 << mode) != 0) {
+        Some(mode) => mode,
+        None => return [[0; 4]; 16],
+    };
+    let mode = &BC7_MODES[mode_id];
+    let mut bits = BitReader {
+        bytes: block,
+        position: mode_id + 1,
+    };
+
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let index_selection = bits.read(mode.index_selection_bits);
+
+    // Cores dos extremos de cada região, canal a canal: todos os vermelhos, depois os verdes...
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..4 {
+        let channel_bits = if channel < 3 {
+            mode.color_bits
+        } else {
+            mode.alpha_bits
+        };
+        for endpoint in endpoints.iter_mut().take(endpoint_count) {
+            endpoint[channel] = bits.read(channel_bits);
+        }
+    }
+
+    let mut color_bits = mode.color_bits;
+    let mut alpha_bits = mode.alpha_bits;
+    if mode.endpoint_pbits || mode.shared_pbits {
+        let pbits: Vec<u32> = if mode.endpoint_pbits {
+            (0..endpoint_count).map(|_| bits.read(1)).collect()
+        } else {
+            (0..mode.subsets)
+                .flat_map(|_| {
+                    let pbit = bits.read(1);
+                    vec![pbit, pbit]
+                })
+                .collect()
+        };
+        for (endpoint, pbit) in endpoints.iter_mut().zip(pbits.iter()) {
+            for value in endpoint.iter_mut() {
+                *value = (*value << 1) | pbit;
+            }
+        }
+        color_bits = color_bits + 1;
+        if alpha_bits > 0 {
+            alpha_bits = alpha_bits + 1;
+        }
+    }
+
+    // Expande as cores para 8 bits repetindo os bits mais significativos nos menos significativos
+    for endpoint in endpoints.iter_mut().take(endpoint_count) {
+        for channel in 0..4 {
+            let channel_bits = if channel < 3 { color_bits } else { alpha_bits };
+            endpoint[channel] = if channel_bits == 0 {
+                255
+            } else {
+                let value = endpoint[channel] << (8 - channel_bits);
+                value | (value >> channel_bits)
+            };
+        }
+    }
+
+    let subset_of = |pixel: usize| match mode.subsets {
+        1 => 0,
+        2 => ((BC7_PARTITIONS2[partition] >> pixel) & 1) as usize,
+        _ => BC7_PARTITIONS3[partition][pixel] as usize,
+    };
+    // O primeiro pixel de cada região (anchor) tem um bit de indice a menos, implicito 0
+    let is_anchor = |pixel: usize| {
+        pixel == 0
+            || match mode.subsets {
+                2 => pixel == BC7_ANCHORS2[partition],
+                3 => {
+                    pixel == BC7_ANCHORS3_SECOND[partition]
+                        || pixel == BC7_ANCHORS3_THIRD[partition]
+                }
+                _ => false,
+            }
+    };
+
+    let mut indices = [0; 16];
+    for (pixel, index) in indices.iter_mut().enumerate() {
+        let anchor_bits = if is_anchor(pixel) { 1 } else { 0 };
+        *index = bits.read(mode.index_bits - anchor_bits);
+    }
+    let mut indices2 = [0; 16];
+    if mode.index2_bits > 0 {
+        for (pixel, index) in indices2.iter_mut().enumerate() {
+            let anchor_bits = if pixel == 0 { 1 } else { 0 };
+            *index = bits.read(mode.index2_bits - anchor_bits);
+        }
+    }
+
+    let mut pixels = [[0; 4]; 16];
+    for (pixel, color) in pixels.iter_mut().enumerate() {
+        let subset = subset_of(pixel);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+
+        // Nos modos 4 e 5 cor e alpha usam conjuntos de indices diferentes
+        let primary = (mode.index_bits, indices[pixel]);
+        let secondary = (mode.index2_bits, indices2[pixel]);
+        let ((color_index_bits, color_index), (alpha_index_bits, alpha_index)) =
+            if mode.index2_bits == 0 {
+                (primary, primary)
+            } else if index_selection == 0 {
+                (primary, secondary)
+            } else {
+                (secondary, primary)
+            };
+
+        let color_weight = bc7_weight(color_index_bits, color_index);
+        let alpha_weight = bc7_weight(alpha_index_bits, alpha_index);
+        for channel in 0..4 {
+            let weight = if channel < 3 {
+                color_weight
+            } else {
+                alpha_weight
+            };
+            color[channel] = (((64 - weight) * e0[channel] + weight * e1[channel] + 32) >> 6) as u8;
+        }
+
+        match rotation {
+            1 => color.swap(0, 3),
+            2 => color.swap(1, 3),
+            3 => color.swap(2, 3),
+            _ => {}
+        }
+    }
+    pixels
+}
+
+// Tabelas de regiões do BC7 (2 e 3 regiões), e os anchors das regiões além da primeira
+// Com 2 regiões cada bit da mascara indica a região do pixel, do pixel 0 no bit menos significativo
+static BC7_PARTITIONS2: [u16; 64] = [
+    0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80, 0xC800, 0xFFEC, 0xFE80, 0xE800,
+    0xFFE8, 0xFF00, 0xFFF0, 0xF000, 0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE,
+    0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C, 0xAAAA, 0xF0F0, 0x5A5A, 0x33CC,
+    0x3C3C, 0x55AA, 0x9696, 0xA55A, 0x73CE, 0x13C8, 0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660,
+    0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C, 0x9336, 0x9CC6, 0x817E, 0xE718,
+    0xCCF0, 0x0FCC, 0x7744, 0xEE22,
+];
+static BC7_ANCHORS2: [usize; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+static BC7_PARTITIONS3: [[u8; 16]; 64] = [
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 2, 0, 0, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2],
+    [0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0, 2, 2, 2, 0],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2],
+    [0, 1, 1, 1, 0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0],
+    [0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2, 0, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 0, 0, 1, 1, 0, 0, 2, 2, 1, 0, 2, 2, 1, 0],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0],
+    [0, 0, 1, 2, 0, 0, 1, 2, 1, 1, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1, 0, 1, 1, 0],
+    [0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1],
+    [0, 0, 2, 2, 1, 1, 0, 2, 1, 1, 0, 2, 0, 0, 2, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2],
+    [0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 0, 0, 2, 0, 0, 0, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 2, 0, 0, 2, 2, 0, 2, 2, 2],
+    [0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0],
+    [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0],
+    [0, 1, 2, 0, 2, 0, 1, 2, 1, 2, 0, 1, 0, 1, 2, 0],
+    [0, 0, 1, 1, 2, 2, 0, 0, 1, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 0, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 1, 1],
+    [0, 2, 2, 0, 1, 2, 2, 1, 0, 2, 2, 0, 1, 2, 2, 1],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 1, 0, 1],
+    [0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 2, 2, 2, 0, 1, 1, 1],
+    [0, 0, 0, 2, 1, 1, 1, 2, 0, 0, 0, 2, 1, 1, 1, 2],
+    [0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2],
+    [0, 0, 0, 2, 1, 1, 1, 2, 1, 1, 1, 2, 0, 0, 0, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2],
+    [0, 0, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2],
+    [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1],
+    [0, 2, 2, 2, 1, 2, 2, 2, 0, 2, 2, 2, 1, 2, 2, 2],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 1, 2, 0, 1, 1, 2, 2, 0, 1, 2, 2, 2, 0],
+];
+static BC7_ANCHORS3_SECOND: [usize; 64] = [
+    3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3, 3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5,
+    15, 15, 8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15, 3, 15, 5, 5, 5, 8, 5, 10, 5,
+    10, 8, 13, 15, 12, 3, 3,
+];
+static BC7_ANCHORS3_THIRD: [usize; 64] = [
+    15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8, 15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6,
+    10, 15, 15, 10, 8, 15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8, 15, 3, 15, 15, 15,
+    15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Escreve campos de bits na ordem lida por BitReader
+    struct BitWriter {
+        bytes: Vec<u8>,
+        position: usize,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: u32, count: usize) {
+            for bit in 0..count {
+                if (value >> bit) & 1 == 1 {
+                    self.bytes[self.position / 8] |= 1 << (self.position % 8);
+                }
+                self.position = self.position + 1;
+            }
+        }
+    }
+
+    fn bc1_block(color0: u16, color1: u16, indices: &[u32; 16]) -> Vec<u8> {
+        let mut block = Vec::new();
+        block.extend_from_slice(&color0.to_le_bytes());
+        block.extend_from_slice(&color1.to_le_bytes());
+        let indices = indices
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (pixel, index)| bits | (index << (2 * pixel)));
+        block.extend_from_slice(&indices.to_le_bytes());
+        block
+    }
+
+    static RED: u16 = 0xF800;
+    static BLUE: u16 = 0x001F;
+
+    #[test]
+    fn bc1_four_colors() {
+        let mut indices = [0; 16];
+        indices[1] = 1;
+        indices[2] = 2;
+        indices[3] = 3;
+        let pixels = decode_bc1(&bc1_block(RED, BLUE, &indices), false);
+        assert_eq!(pixels[0], [255, 0, 0, 255]);
+        assert_eq!(pixels[1], [0, 0, 255, 255]);
+        assert_eq!(pixels[2], [170, 0, 85, 255]);
+        assert_eq!(pixels[3], [85, 0, 170, 255]);
+    }
+
+    #[test]
+    fn bc1_three_colors_and_alpha() {
+        // Primeira cor menor que a segunda: uma cor intermediaria e preto transparente
+        let mut indices = [0; 16];
+        indices[1] = 1;
+        indices[2] = 2;
+        indices[3] = 3;
+        let block = bc1_block(BLUE, RED, &indices);
+        let pixels = decode_bc1(&block, false);
+        assert_eq!(pixels[0], [0, 0, 255, 255]);
+        assert_eq!(pixels[1], [255, 0, 0, 255]);
+        assert_eq!(pixels[2], [127, 0, 127, 255]);
+        assert_eq!(pixels[3], [0, 0, 0, 0]);
+
+        // Dentro do BC3 o mesmo bloco tem 4 cores opacas
+        let pixels = decode_bc1(&block, true);
+        assert_eq!(pixels[2], [85, 0, 170, 255]);
+        assert_eq!(pixels[3], [170, 0, 85, 255]);
+    }
+
+    #[test]
+    fn bc7_mode6() {
+        // Modo 6: uma região, cores e alpha de 7 bits, p-bit por cor e indices de 4 bits
+        let mut bits = BitWriter {
+            bytes: vec![0; 16],
+            position: 0,
+        };
+        bits.write(1 << 6, 7);
+        for &(e0, e1) in [(0, 127), (127, 0), (0, 0), (127, 127)].iter() {
+            bits.write(e0, 7);
+            bits.write(e1, 7);
+        }
+        bits.write(0, 1);
+        bits.write(1, 1);
+        // O indice do pixel 0 tem um bit a menos
+        bits.write(0, 3);
+        for pixel in 1..16 {
+            bits.write(pixel, 4);
+        }
+        assert_eq!(bits.position, 128);
+
+        // Extremos com o p-bit: (0, 254, 0, 254) e (255, 1, 1, 255)
+        let pixels = decode_bc7(&bits.bytes);
+        assert_eq!(pixels[0], [0, 254, 0, 254]);
+        assert_eq!(pixels[8], [135, 120, 1, 255]);
+        assert_eq!(pixels[15], [255, 1, 1, 255]);
+    }
+
+    #[test]
+    fn bc7_without_mode_is_transparent() {
+        assert_eq!(decode_bc7(&[0; 16]), [[0; 4]; 16]);
+    }
+
+    #[test]
+    fn decompress_crops_partial_blocks() {
+        // 6x2 pixels: dois blocos, o segundo com apenas 2 colunas e 2 linhas usadas
+        let mut blocks = bc1_block(RED, BLUE, &[0; 16]);
+        blocks.extend(bc1_block(RED, BLUE, &[1; 16]));
+        let compressed = CompressedLevels {
+            format: BlockFormat::Bc1,
+            usage: TextureUsage::Color,
+            width: 6,
+            height: 2,
+            levels: vec![blocks],
+        };
+        assert_eq!(BlockFormat::Bc1.image_bytes(6, 2), 16);
+
+        let image = compressed.decompress_level(0);
+        assert_eq!((image.width, image.height), (6, 2));
+        assert_eq!(image.format, PixelFormat::Rgba8);
+        for (pixel, color) in image.pixels.chunks(4).enumerate() {
+            let expected = if pixel % 6 < 4 {
+                [255, 0, 0, 255]
+            } else {
+                [0, 0, 255, 255]
+            };
+            assert_eq!(color, &expected[..], "pixel {}", pixel);
+        }
+    }
+}
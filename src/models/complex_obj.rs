@@ -3,6 +3,7 @@ use super::matrix::GLMatrix;
 use super::matrix::MatrixTransform;
 use super::obj_model::ObjModel;
 use super::scene_object::SceneObject;
+use world::frustum::Frustum;
 
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
@@ -18,7 +19,7 @@ impl ComplexObj {
         new_children.append(&mut vec![(*child).clone()]);
 
         ComplexObj {
-            root: self.root,
+            root: self.root.clone(),
             children: new_children,
         }
     }
@@ -35,7 +36,7 @@ impl MatrixTransform for ComplexObj {
 
     fn from_matrix(&self, matrix: &GLMatrix) -> Self {
         ComplexObj {
-            root: *self.root.clone().update_matrix(matrix),
+            root: self.root.from_matrix(matrix),
             children: Box::new((&*self.children).to_vec()),
         }
     }
@@ -71,4 +72,31 @@ impl Draw for ComplexObj {
 
         self
     }
+
+    fn draw_in_frustum(&self, program: &u32, frustum: &Frustum) -> usize {
+        let mut culled = self.root.draw_in_frustum(program, frustum);
+
+        self.children.iter().for_each(|item| {
+            culled = culled
+                + match item {
+                    SceneObject::ObjModel(obj_model) => obj_model
+                        .from_matrix(&GLMatrix {
+                            matrix: obj_model.model.matrix * self.root.model.matrix,
+                        })
+                        .draw_in_frustum(program, frustum),
+                    SceneObject::CompositeObj(composite_obj) => composite_obj
+                        .from_matrix(&GLMatrix {
+                            matrix: self.root.model.matrix * composite_obj.root.model.matrix,
+                        })
+                        .draw_in_frustum(program, frustum),
+                    SceneObject::ComplexObj(complex_obj) => complex_obj
+                        .from_matrix(&GLMatrix {
+                            matrix: self.root.model.matrix * complex_obj.root.model.matrix,
+                        })
+                        .draw_in_frustum(program, frustum),
+                };
+        });
+
+        culled
+    }
 }
@@ -2,6 +2,7 @@ use super::draw::Draw;
 use super::matrix::GLMatrix;
 use super::matrix::MatrixTransform;
 use super::obj_model::ObjModel;
+use world::frustum::Frustum;
 
 #[derive(Clone, Debug)]
 pub struct CompositeObj {
@@ -13,10 +14,10 @@ pub struct CompositeObj {
 impl CompositeObj {
     pub fn add_children(&self, child: &ObjModel) -> Self {
         let mut new_children = self.children.clone();
-        new_children.append(&mut vec![*child]);
+        new_children.append(&mut vec![child.clone()]);
 
         CompositeObj {
-            root: self.root,
+            root: self.root.clone(),
             children: new_children,
         }
     }
@@ -33,7 +34,7 @@ impl MatrixTransform for CompositeObj {
 
     fn from_matrix(&self, matrix: &GLMatrix) -> Self {
         CompositeObj {
-            root: *self.root.clone().update_matrix(matrix),
+            root: self.root.from_matrix(matrix),
             children: self.children.clone(),
         }
     }
@@ -53,4 +54,19 @@ impl Draw for CompositeObj {
 
         self
     }
+
+    fn draw_in_frustum(&self, program: &u32, frustum: &Frustum) -> usize {
+        let mut culled = self.root.draw_in_frustum(program, frustum);
+
+        self.children.iter().for_each(|item| {
+            culled = culled
+                + item
+                    .from_matrix(&GLMatrix {
+                        matrix: self.root.model.matrix * item.model.matrix,
+                    })
+                    .draw_in_frustum(program, frustum);
+        });
+
+        culled
+    }
 }
@@ -1,3 +1,9 @@
+use world::frustum::Frustum;
+
 pub trait Draw {
     fn draw(&self, program: &u32) -> &Self;
+
+    // Desenha apenas os objs simples que intersectam o frustum
+    // Retorna o numero de objs simples descartados
+    fn draw_in_frustum(&self, program: &u32, frustum: &Frustum) -> usize;
 }
@@ -0,0 +1,763 @@
+use base64;
+use gltf;
+use gltf::animation::util::ReadOutputs;
+use models::animation::AnimationClip;
+use models::animation::Channel;
+use models::animation::ChannelValues;
+use models::animation::Interpolation;
+use models::complex_obj::ComplexObj;
+use models::composite_obj::CompositeObj;
+use models::material::MaterialData;
+use models::material::TextureSource;
+use models::matrix::identity_matrix;
+use models::matrix::GLMatrix;
+use models::matrix::Transform;
+use models::mesh_data::MeshData;
+use models::mesh_data::MeshError;
+use models::normals::NormalMesh;
+use models::normals::NormalMode;
+use models::obj_model::ObjModel;
+use models::quaternion::Quaternion;
+use models::scene_object::SceneObject;
+use models::simplify::generate_lods;
+use models::skeleton::Joint;
+use models::skeleton::Skeleton;
+use models::skeleton::Skin;
+use models::skeleton::MAX_JOINTS;
+use models::tangents::generate_tangents;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Nó de uma cena glTF carregado na CPU
+// transform -> Transformação local, relativa ao nó pai
+// mesh -> Malha do nó, com um submesh por primitiva. None para nós apenas de transformação
+// skin -> Esqueleto e animações que deformam a malha, se o nó tiver um skin
+pub struct GltfNode {
+    pub name: String,
+    pub transform: Transform,
+    pub mesh: Option<MeshData>,
+    pub skin: Option<Skin>,
+    pub children: Vec<GltfNode>,
+}
+
+// Esqueleto de um skin do arquivo, com as animações das suas juntas
+// root_parent -> Nó pai das juntas raiz, cuja matriz global posiciona o esqueleto na cena
+struct GltfSkin {
+    skeleton: Skeleton,
+    clips: Arc<Vec<AnimationClip>>,
+    root_parent: Option<usize>,
+}
+
+impl GltfSkin {
+    // Skin de um nó com malha. O glTF ignora a matriz do nó da malha e usa a global das juntas;
+    // como o obj é desenhado com a matriz do nó, ela é desfeita na raiz do esqueleto
+    fn bind(&self, mesh_global: &GLMatrix, globals: &HashMap<usize, GLMatrix>) -> Skin {
+        let root_global = self
+            .root_parent
+            .and_then(|parent| globals.get(&parent).cloned())
+            .unwrap_or(identity_matrix());
+        let inverse_mesh = mesh_global.inverse().unwrap_or(identity_matrix());
+
+        let mut skeleton = self.skeleton.clone();
+        skeleton.root = GLMatrix {
+            matrix: inverse_mesh.matrix * root_global.matrix,
+        };
+        Skin::new(Arc::new(skeleton), self.clips.clone())
+    }
+}
+
+// Carrega os nós raiz da cena padrão de um arquivo .gltf ou .glb
+// Buffers e imagens podem estar no arquivo .glb, em data URIs ou em arquivos externos
+#[allow(dead_code)]
+pub fn load_gltf(path: &str, normal_mode: NormalMode) -> Result<Vec<GltfNode>, MeshError> {
+    let load_error = |message: String| MeshError::Load {
+        path: path.to_string(),
+        message,
+    };
+
+    let gltf = gltf::Gltf::open(path).map_err(|error| load_error(error.to_string()))?;
+    let base_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+
+    let mut buffers: Vec<Vec<u8>> = Vec::new();
+    for buffer in gltf.buffers() {
+        let data = match buffer.source() {
+            gltf::buffer::Source::Bin => gltf
+                .blob
+                .clone()
+                .ok_or_else(|| load_error("Buffer binario do .glb ausente".to_string()))?,
+            gltf::buffer::Source::Uri(uri) => read_uri(base_dir, uri).map_err(&load_error)?,
+        };
+        buffers.push(data);
+    }
+
+    let materials: Vec<MaterialData> = gltf
+        .materials()
+        .map(|material| material_data(&material, &buffers, base_dir))
+        .collect();
+
+    let scene = gltf
+        .default_scene()
+        .or_else(|| gltf.scenes().next())
+        .ok_or_else(|| load_error("Arquivo sem cenas".to_string()))?;
+
+    // Pai e matriz global de cada nó da cena, usados para montar e posicionar os esqueletos
+    let mut parents: HashMap<usize, usize> = HashMap::new();
+    let mut globals: HashMap<usize, GLMatrix> = HashMap::new();
+    for node in scene.nodes() {
+        collect_globals(&node, &identity_matrix(), &mut parents, &mut globals);
+    }
+
+    let skins: Vec<Option<GltfSkin>> = gltf
+        .skins()
+        .map(|skin| load_skin(&skin, &gltf.document, &buffers, &parents))
+        .collect();
+
+    let mut roots = Vec::new();
+    for node in scene.nodes() {
+        roots.push(load_node(
+            &node,
+            &buffers,
+            &materials,
+            normal_mode,
+            &skins,
+            &globals,
+        )?);
+    }
+    Ok(roots)
+}
+
+// Carrega um arquivo glTF como obj da cena, enviando as malhas para a GPU
+// A hierarquia de nós é mantida: cada nó vira um obj simples com sua matriz local,
+// e nós com filhos viram CompositeObj ou ComplexObj
+#[allow(dead_code)]
+pub fn load_gltf_scene(path: &str, normal_mode: NormalMode) -> Result<SceneObject, MeshError> {
+    let mut roots: Vec<SceneObject> = load_gltf(path, normal_mode)?
+        .iter()
+        .map(node_to_scene_object)
+        .collect();
+
+    if roots.len() == 1 {
+        return Ok(roots.remove(0));
+    }
+
+    // Varios nós raiz ficam abaixo de uma raiz sem geometria
+    Ok(SceneObject::ComplexObj(ComplexObj {
+        root: ObjModel::empty(),
+        children: Box::new(roots.iter().map(wrap_leaf).collect()),
+    }))
+}
+
+fn node_to_scene_object(node: &GltfNode) -> SceneObject {
+    let mut root = match node.mesh {
+        Some(ref mesh) => ObjModel::from_mesh_data(mesh.clone()),
+        None => ObjModel::empty(),
+    };
+    if let Some(ref skin) = node.skin {
+        root = root.with_skin(skin);
+    }
+    root.model = node.transform.to_matrix();
+
+    let children: Vec<SceneObject> = node.children.iter().map(node_to_scene_object).collect();
+
+    if children.len() == 0 {
+        return SceneObject::ObjModel(root);
+    }
+
+    let all_leaves = children.iter().all(|child| match child {
+        SceneObject::ObjModel(_) => true,
+        _ => false,
+    });
+
+    if all_leaves {
+        SceneObject::CompositeObj(CompositeObj {
+            root,
+            children: children
+                .iter()
+                .filter_map(|child| match child {
+                    SceneObject::ObjModel(obj) => Some(obj.clone()),
+                    _ => None,
+                })
+                .collect(),
+        })
+    } else {
+        SceneObject::ComplexObj(ComplexObj {
+            root,
+            children: Box::new(children.iter().map(wrap_leaf).collect()),
+        })
+    }
+}
+
+// ComplexObj compõe filhos simples como filho * raiz, enquanto glTF espera raiz * filho
+// Objs simples são colocados em um CompositeObj sem filhos, que é composto como raiz * filho
+fn wrap_leaf(child: &SceneObject) -> SceneObject {
+    match child {
+        SceneObject::ObjModel(obj) => SceneObject::CompositeObj(CompositeObj {
+            root: obj.clone(),
+            children: Vec::new(),
+        }),
+        _ => child.clone(),
+    }
+}
+
+fn load_node(
+    node: &gltf::Node,
+    buffers: &Vec<Vec<u8>>,
+    materials: &Vec<MaterialData>,
+    normal_mode: NormalMode,
+    skins: &Vec<Option<GltfSkin>>,
+    globals: &HashMap<usize, GLMatrix>,
+) -> Result<GltfNode, MeshError> {
+    let mesh = match node.mesh() {
+        Some(mesh) => Some(load_mesh(&mesh, buffers, materials, normal_mode)?),
+        None => None,
+    };
+
+    let skin = match (node.skin(), mesh.as_ref()) {
+        (Some(skin), Some(_)) => skins[skin.index()].as_ref().map(|gltf_skin| {
+            let mesh_global = globals
+                .get(&node.index())
+                .cloned()
+                .unwrap_or(identity_matrix());
+            gltf_skin.bind(&mesh_global, globals)
+        }),
+        _ => None,
+    };
+
+    let mut children = Vec::new();
+    for child in node.children() {
+        children.push(load_node(
+            &child,
+            buffers,
+            materials,
+            normal_mode,
+            skins,
+            globals,
+        )?);
+    }
+
+    Ok(GltfNode {
+        name: node.name().unwrap_or("").to_string(),
+        transform: node_transform(node),
+        mesh,
+        skin,
+        children,
+    })
+}
+
+fn node_transform(node: &gltf::Node) -> Transform {
+    let (translation, rotation, scale) = node.transform().decomposed();
+    Transform::new(
+        glm::vec3(translation[0], translation[1], translation[2]),
+        Quaternion::new(rotation[0], rotation[1], rotation[2], rotation[3]),
+        glm::vec3(scale[0], scale[1], scale[2]),
+    )
+}
+
+// Matriz do glTF, em ordem de colunas
+fn column_major_matrix(m: [[f32; 4]; 4]) -> GLMatrix {
+    GLMatrix::new([
+        m[0][0], m[1][0], m[2][0], m[3][0], // LINHA 1
+        m[0][1], m[1][1], m[2][1], m[3][1], // LINHA 2
+        m[0][2], m[1][2], m[2][2], m[3][2], // LINHA 3
+        m[0][3], m[1][3], m[2][3], m[3][3],
+    ])
+}
+
+fn collect_globals(
+    node: &gltf::Node,
+    parent_global: &GLMatrix,
+    parents: &mut HashMap<usize, usize>,
+    globals: &mut HashMap<usize, GLMatrix>,
+) {
+    let global = GLMatrix {
+        matrix: parent_global.matrix * column_major_matrix(node.transform().matrix()).matrix,
+    };
+    for child in node.children() {
+        parents.insert(child.index(), node.index());
+        collect_globals(&child, &global, parents, globals);
+    }
+    globals.insert(node.index(), global);
+}
+
+// Monta o esqueleto de um skin e os clips das animações que movem as suas juntas
+// O pai de cada junta é o ancestral mais proximo que também é junta. Canais de nós que não são
+// juntas do skin e de pesos de morph targets são ignorados
+fn load_skin(
+    skin: &gltf::Skin,
+    document: &gltf::Document,
+    buffers: &Vec<Vec<u8>>,
+    parents: &HashMap<usize, usize>,
+) -> Option<GltfSkin> {
+    let joint_nodes: Vec<gltf::Node> = skin.joints().collect();
+    if joint_nodes.len() > MAX_JOINTS {
+        println!(
+            "Skin {} com {} juntas ignorado, maximo de {}",
+            skin.name().unwrap_or(""),
+            joint_nodes.len(),
+            MAX_JOINTS
+        );
+        return None;
+    }
+
+    let joint_ids: HashMap<usize, usize> = joint_nodes
+        .iter()
+        .enumerate()
+        .map(|(joint, node)| (node.index(), joint))
+        .collect();
+    let parent_joint = |node: usize| -> Option<usize> {
+        let mut ancestor = parents.get(&node);
+        while let Some(parent) = ancestor {
+            if let Some(joint) = joint_ids.get(parent) {
+                return Some(*joint);
+            }
+            ancestor = parents.get(parent);
+        }
+        None
+    };
+
+    let reader = skin.reader(|buffer| buffers.get(buffer.index()).map(|b| b.as_slice()));
+    let inverse_binds: Vec<GLMatrix> = reader
+        .read_inverse_bind_matrices()
+        .map(|matrices| matrices.map(column_major_matrix).collect())
+        .unwrap_or(Vec::new());
+
+    let joints: Vec<Joint> = joint_nodes
+        .iter()
+        .enumerate()
+        .map(|(joint, node)| Joint {
+            name: node.name().unwrap_or("").to_string(),
+            parent: parent_joint(node.index()),
+            rest: node_transform(node),
+            inverse_bind: inverse_binds
+                .get(joint)
+                .cloned()
+                .unwrap_or(identity_matrix()),
+        })
+        .collect();
+
+    let root_parent = joint_nodes
+        .iter()
+        .find(|node| parent_joint(node.index()).is_none())
+        .and_then(|node| parents.get(&node.index()).cloned());
+
+    let mut clips = Vec::new();
+    for animation in document.animations() {
+        let channels: Vec<Channel> = animation
+            .channels()
+            .filter_map(|channel| {
+                let joint = *joint_ids.get(&channel.target().node().index())?;
+                load_channel(&channel, joint, buffers)
+            })
+            .collect();
+
+        if channels.len() > 0 {
+            let name = match animation.name() {
+                Some(name) => name.to_string(),
+                None => format!("animation{}", animation.index()),
+            };
+            clips.push(AnimationClip::new(&name, channels));
+        }
+    }
+
+    Some(GltfSkin {
+        skeleton: Skeleton::new(joints),
+        clips: Arc::new(clips),
+        root_parent,
+    })
+}
+
+fn load_channel(
+    channel: &gltf::animation::Channel,
+    joint: usize,
+    buffers: &Vec<Vec<u8>>,
+) -> Option<Channel> {
+    let reader = channel.reader(|buffer| buffers.get(buffer.index()).map(|b| b.as_slice()));
+    let times: Vec<f32> = reader.read_inputs()?.collect();
+
+    // Splines cubicas guardam tangentes de entrada e saida ao redor de cada valor
+    // Apenas os valores são utilizados, com interpolação linear
+    let (interpolation, stride, offset) = match channel.sampler().interpolation() {
+        gltf::animation::Interpolation::Step => (Interpolation::Step, 1, 0),
+        gltf::animation::Interpolation::Linear => (Interpolation::Linear, 1, 0),
+        gltf::animation::Interpolation::CubicSpline => (Interpolation::Linear, 3, 1),
+    };
+
+    let values = match reader.read_outputs()? {
+        ReadOutputs::Translations(translations) => ChannelValues::Translation(
+            translations
+                .skip(offset)
+                .step_by(stride)
+                .map(|t| glm::vec3(t[0], t[1], t[2]))
+                .collect(),
+        ),
+        ReadOutputs::Rotations(rotations) => ChannelValues::Rotation(
+            rotations
+                .into_f32()
+                .skip(offset)
+                .step_by(stride)
+                .map(|r| Quaternion::new(r[0], r[1], r[2], r[3]))
+                .collect(),
+        ),
+        ReadOutputs::Scales(scales) => ChannelValues::Scale(
+            scales
+                .skip(offset)
+                .step_by(stride)
+                .map(|s| glm::vec3(s[0], s[1], s[2]))
+                .collect(),
+        ),
+        ReadOutputs::MorphTargetWeights(_) => return None,
+    };
+
+    Some(Channel {
+        joint,
+        interpolation,
+        times,
+        values,
+    })
+}
+
+// Cada primitiva de triangulos vira um submesh. Apenas os materiais utilizados pela malha
+// são copiados, com indices remapeados
+fn load_mesh(
+    mesh: &gltf::Mesh,
+    buffers: &Vec<Vec<u8>>,
+    materials: &Vec<MaterialData>,
+    normal_mode: NormalMode,
+) -> Result<MeshData, MeshError> {
+    let mut mesh_data = MeshData::new();
+    let mut material_ids: HashMap<usize, usize> = HashMap::new();
+    let mesh_name = mesh.name().unwrap_or("mesh");
+
+    for primitive in mesh.primitives() {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            println!(
+                "Primitiva {:?} ignorada em {}, apenas triangulos são suportados",
+                primitive.mode(),
+                mesh_name
+            );
+            continue;
+        }
+
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|b| b.as_slice()));
+
+        let positions: Vec<f32> = match reader.read_positions() {
+            Some(positions) => positions.flat_map(|p| p.to_vec()).collect(),
+            None => continue,
+        };
+        let normals: Vec<f32> = reader
+            .read_normals()
+            .map(|normals| normals.flat_map(|n| n.to_vec()).collect())
+            .unwrap_or(Vec::new());
+
+        // Origem das coordenadas de textura do glTF é o canto superior esquerdo,
+        // V é invertido para seguir a convenção do obj
+        let texcoords: Vec<f32> = reader
+            .read_tex_coords(0)
+            .map(|texcoords| {
+                texcoords
+                    .into_f32()
+                    .flat_map(|t| vec![t[0], 1.0 - t[1]])
+                    .collect()
+            })
+            .unwrap_or(Vec::new());
+
+        // Juntas e pesos das malhas com esqueleto. Os pesos são normalizados: o glTF tolera
+        // pequenos desvios na soma
+        let (joints, weights): (Vec<u16>, Vec<f32>) =
+            match (reader.read_joints(0), reader.read_weights(0)) {
+                (Some(joints), Some(weights)) => (
+                    joints.into_u16().flat_map(|j| j.to_vec()).collect(),
+                    weights
+                        .into_f32()
+                        .flat_map(|w| {
+                            let sum = w[0] + w[1] + w[2] + w[3];
+                            let scale = if sum > 0.0 { 1.0 / sum } else { 0.0 };
+                            vec![w[0] * scale, w[1] * scale, w[2] * scale, w[3] * scale]
+                        })
+                        .collect(),
+                ),
+                _ => (Vec::new(), Vec::new()),
+            };
+
+        // Primitivas sem indices desenham os vertices em ordem
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..(positions.len() / 3) as u32).collect(),
+        };
+
+        let normal_mesh = NormalMesh {
+            positions,
+            normals,
+            texcoords,
+            colors: Vec::new(),
+            joints,
+            weights,
+            indices,
+        }
+        .with_normal_mode(normal_mode);
+
+        let material = primitive.material().index().map(|index| {
+            let next_id = mesh_data.materials.len();
+            let id = *material_ids.entry(index).or_insert(next_id);
+            if id == next_id {
+                mesh_data.materials.push(materials[index].clone());
+            }
+            id
+        });
+
+        let name = format!("{}.{}", mesh_name, primitive.index());
+        mesh_data.push_mesh(&name, &normal_mesh, material);
+    }
+
+    // Tangentes do arquivo são ignoradas: o glTF exige MikkTSpace, calculado para a malha toda
+    generate_tangents(&mut mesh_data);
+    generate_lods(&mut mesh_data);
+    mesh_data.validate()?;
+    Ok(mesh_data)
+}
+
+// Converte o material PBR para os parametros de Phong utilizados pelos shaders
+// Apenas a cor base e as texturas de cor base e de normais são importadas
+fn material_data(
+    material: &gltf::Material,
+    buffers: &Vec<Vec<u8>>,
+    base_dir: &Path,
+) -> MaterialData {
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, _] = pbr.base_color_factor();
+
+    let texture_source = |image: gltf::image::Image| -> Option<TextureSource> {
+        match image.source() {
+            gltf::image::Source::View { view, .. } => {
+                let buffer = buffers.get(view.buffer().index())?;
+                let bytes = buffer.get(view.offset()..view.offset() + view.length())?;
+                Some(TextureSource::Encoded(bytes.to_vec()))
+            }
+            gltf::image::Source::Uri { uri, .. } => {
+                if uri.starts_with("data:") {
+                    read_uri(base_dir, uri).ok().map(TextureSource::Encoded)
+                } else {
+                    Some(TextureSource::File(base_dir.join(decode_uri_path(uri))))
+                }
+            }
+        }
+    };
+
+    MaterialData {
+        name: material.name().unwrap_or("").to_string(),
+        ambient: glm::vec3(0.0, 0.0, 0.0),
+        diffuse: glm::vec3(r, g, b),
+        specular: glm::vec3(0.0, 0.0, 0.0),
+        shininess: 0.0,
+        diffuse_texture: pbr
+            .base_color_texture()
+            .and_then(|info| texture_source(info.texture().source())),
+        bump_texture: material
+            .normal_texture()
+            .and_then(|normal| texture_source(normal.texture().source())),
+    }
+}
+
+// Le o conteudo de uma URI de buffer ou imagem: data URI em base64 ou arquivo relativo
+fn read_uri(base_dir: &Path, uri: &str) -> Result<Vec<u8>, String> {
+    if uri.starts_with("data:") {
+        let data = uri
+            .splitn(2, ";base64,")
+            .nth(1)
+            .ok_or_else(|| "Data URI sem base64".to_string())?;
+        base64::decode(data).map_err(|error| error.to_string())
+    } else {
+        let path = base_dir.join(decode_uri_path(uri));
+        fs::read(&path).map_err(|error| format!("{:?}: {}", path, error))
+    }
+}
+
+// URIs relativas podem ter caracteres codificados (ex: espaço como %20)
+fn decode_uri_path(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = String::from_utf8_lossy(&bytes[i + 1..i + 3]);
+            if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // Cena: root (1, 0, 0) -> body (0, 2, 0), com malha e skin
+    //                      -> hip (0, 0, 3) -> knee (0, 1, 0), juntas do skin
+    // A malha tem duas primitivas, a primeira com indices e material "external" e a segunda sem
+    // indices e com material "embedded". Buffer e textura em data URIs, normal map em arquivo
+    fn skinned() -> GltfNode {
+        let mut roots =
+            load_gltf("src/data/objs/skinned.gltf", NormalMode::Smooth).expect("glTF invalido");
+        assert_eq!(roots.len(), 1);
+        roots.remove(0)
+    }
+
+    fn assert_vec3_near(a: glm::Vec3, b: glm::Vec3) {
+        assert!(glm::distance(a, b) < 1e-5, "{:?} diferente de {:?}", a, b);
+    }
+
+    fn assert_matrix_near(a: &GLMatrix, b: &GLMatrix) {
+        for (x, y) in a.to_points().iter().zip(b.to_points().iter()) {
+            assert!(
+                (x - y).abs() < 1e-5,
+                "{:?} diferente de {:?}",
+                a.to_points(),
+                b.to_points()
+            );
+        }
+    }
+
+    #[test]
+    fn node_hierarchy_and_transforms() {
+        let root = skinned();
+        assert_eq!(root.name, "root");
+        assert!(root.mesh.is_none());
+        assert_vec3_near(root.transform.translation, glm::vec3(1.0, 0.0, 0.0));
+
+        let names: Vec<&str> = root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["body", "hip"]);
+
+        // Transformações dos nós são locais ao pai
+        let body = &root.children[0];
+        assert_vec3_near(body.transform.translation, glm::vec3(0.0, 2.0, 0.0));
+        assert!(body.children.is_empty());
+
+        let hip = &root.children[1];
+        assert_vec3_near(hip.transform.translation, glm::vec3(0.0, 0.0, 3.0));
+        assert!(hip.mesh.is_none() && hip.skin.is_none());
+        assert_eq!(hip.children.len(), 1);
+        assert_vec3_near(
+            hip.children[0].transform.translation,
+            glm::vec3(0.0, 1.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn primitives_become_submeshes() {
+        let root = skinned();
+        let mesh = root.children[0].mesh.as_ref().expect("malha ausente");
+
+        let ranges: Vec<(&str, usize, usize, Option<usize>)> = mesh
+            .ranges
+            .iter()
+            .map(|r| (r.name.as_str(), r.index_offset, r.index_count, r.material))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![("body.0", 0, 3, Some(0)), ("body.1", 3, 3, Some(1))]
+        );
+        assert_eq!(mesh.vertex_count(), 6);
+
+        // Indices do arquivo na primeira primitiva, ordem dos vertices na segunda
+        assert_eq!(mesh.indices, vec![0, 2, 1, 3, 4, 5]);
+
+        // Materiais na ordem de uso pela malha
+        let materials: Vec<&str> = mesh.materials.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(materials, vec!["external", "embedded"]);
+    }
+
+    #[test]
+    fn embedded_and_file_textures() {
+        let root = skinned();
+        let mesh = root.children[0].mesh.as_ref().expect("malha ausente");
+
+        let external = &mesh.materials[0];
+        assert!(external.diffuse_texture.is_none());
+        match external.bump_texture {
+            Some(TextureSource::File(ref path)) => {
+                assert_eq!(path, &PathBuf::from("src/data/objs/normal map.png"))
+            }
+            ref other => panic!("resultado inesperado {:?}", other),
+        }
+
+        let embedded = &mesh.materials[1];
+        assert_vec3_near(embedded.diffuse, glm::vec3(1.0, 0.0, 0.0));
+        match embedded.diffuse_texture {
+            Some(TextureSource::Encoded(ref bytes)) => assert_eq!(bytes.as_slice(), b"png"),
+            ref other => panic!("resultado inesperado {:?}", other),
+        }
+    }
+
+    #[test]
+    fn joints_and_normalized_weights() {
+        let root = skinned();
+        let mesh = root.children[0].mesh.as_ref().expect("malha ausente");
+        assert!(mesh.is_skinned());
+
+        assert_eq!(&mesh.joints[..12], &[0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0]);
+        let expected = [0.5, 0.5, 0.0, 0.0, 0.25, 0.75, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0];
+        for (weight, expected) in mesh.weights[..12].iter().zip(expected.iter()) {
+            assert!((weight - expected).abs() < 1e-6, "{:?}", mesh.weights);
+        }
+    }
+
+    #[test]
+    fn skin_binding() {
+        let root = skinned();
+        let skin = root.children[0].skin.as_ref().expect("skin ausente");
+        let skeleton = &skin.skeleton;
+
+        let joints: Vec<(&str, Option<usize>)> = skeleton
+            .joints
+            .iter()
+            .map(|joint| (joint.name.as_str(), joint.parent))
+            .collect();
+        assert_eq!(joints, vec![("hip", None), ("knee", Some(0))]);
+
+        // Raiz do esqueleto: inversa da global do body (1, 2, 0) * global do root (1, 0, 0)
+        assert_matrix_near(&skeleton.root, &identity_matrix().translate(0.0, -2.0, 0.0));
+
+        // Na pose de ligação, a matriz global do body desfaz a deformação das juntas
+        let body_global = identity_matrix().translate(1.0, 2.0, 0.0);
+        for matrix in skeleton.skin_matrices(&skeleton.rest_pose()) {
+            let world = GLMatrix {
+                matrix: body_global.matrix * matrix.matrix,
+            };
+            assert_matrix_near(&world, &identity_matrix());
+        }
+
+        // Canal de translação do knee, com interpolação em degraus
+        assert_eq!(skin.clip_names(), vec!["bend"]);
+        let channel = &skin.clips[0].channels[0];
+        assert_eq!(channel.joint, 1);
+        assert_eq!(channel.interpolation, Interpolation::Step);
+        assert_eq!(channel.times, vec![0.0, 1.0]);
+        match channel.values {
+            ChannelValues::Translation(ref values) => {
+                assert_vec3_near(values[1], glm::vec3(0.0, 2.0, 0.0))
+            }
+            ref other => panic!("resultado inesperado {:?}", other),
+        }
+    }
+
+    #[test]
+    fn uri_paths_are_decoded() {
+        assert_eq!(decode_uri_path("normal%20map.png"), "normal map.png");
+        assert_eq!(decode_uri_path("a%2"), "a%2");
+        assert_eq!(
+            read_uri(Path::new(""), "data:text/plain;base64,YWJj"),
+            Ok(b"abc".to_vec())
+        );
+    }
+}
@@ -0,0 +1,365 @@
+use gl::types::GLsizeiptr;
+use gl::types::GLuint;
+use models::draw::Draw;
+use models::matrix::identity_matrix;
+use models::obj_model::ObjModel;
+use models::resources::MeshHandle;
+use models::resources::TextureHandle;
+use models::scene_object::SceneObject;
+use models::texture_units::flip_uv;
+use models::texture_units::TextureUnits;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::ffi::CString;
+use std::mem;
+use std::sync::Arc;
+use world::frustum::Frustum;
+
+// Primeiro location dos atributos por instancia no vertex shader (5 a 13)
+static INSTANCE_LOCATION: GLuint = 5;
+// Quantidade de vec4 por instancia: linhas da model (3), colunas da matriz de normais (3), cor, especular
+// e ambiente. A ultima linha da model é sempre 0 0 0 1 e não é enviada
+static INSTANCE_VEC4S: usize = 9;
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+// Dados de uma instancia, no layout dos atributos instance_* do vertex shader
+// model -> Tres primeiras linhas da matriz model
+// normal_matrix -> Colunas da matriz de normais (xyz) e fonte de luz (w de cada coluna)
+// color -> Cor (rgb) e q de phong (w)
+// specular -> Refletancia especular (rgb) e tipo de mapeamento de textura (w)
+// ambient -> Refletancia ambiente (rgb)
+pub struct InstanceData {
+    pub model: [f32; 12],
+    pub normal_matrix: [f32; 12],
+    pub color: [f32; 4],
+    pub specular: [f32; 4],
+    pub ambient: [f32; 4],
+}
+
+#[allow(dead_code)]
+impl InstanceData {
+    // Valores de desenho de um submesh do obj, como em ObjModel::draw
+    pub fn from_obj(
+        obj: &ObjModel,
+        color: glm::Vec3,
+        specular: glm::Vec3,
+        ambient: glm::Vec3,
+        phong_q: f32,
+    ) -> Self {
+        let light = obj.lighting_source_override;
+        // Calculada uma vez por instancia, como no desenho sem instancias
+        let normal_matrix = obj.model.normal_matrix().unwrap_or(identity_matrix());
+
+        InstanceData {
+            model: model_rows(&obj.model.matrix),
+            normal_matrix: normal_columns(&normal_matrix.matrix, [light.x, light.y, light.z]),
+            color: [color.x, color.y, color.z, phong_q],
+            specular: [
+                specular.x,
+                specular.y,
+                specular.z,
+                obj.texture_map_type as f32,
+            ],
+            ambient: [ambient.x, ambient.y, ambient.z, 0.0],
+        }
+    }
+}
+
+// Tres primeiras linhas da matriz, montadas de volta com transpose no shader
+fn model_rows(matrix: &glm::Mat4) -> [f32; 12] {
+    let mut array = [0.0; 12];
+    for row in 0..3 {
+        for column in 0..4 {
+            array[4 * row + column] = matrix[column][row];
+        }
+    }
+    array
+}
+
+// Parte 3x3 da matriz em ordem de colunas, com um valor extra no w de cada coluna
+fn normal_columns(matrix: &glm::Mat4, extra: [f32; 3]) -> [f32; 12] {
+    let mut array = [0.0; 12];
+    for column in 0..3 {
+        for row in 0..3 {
+            array[4 * column + row] = matrix[column][row];
+        }
+        array[4 * column + 3] = extra[column];
+    }
+    array
+}
+
+#[allow(dead_code)]
+// Instancias de um submesh desenhadas com o mesmo shader, as mesmas texturas e o mesmo nivel de
+// detalhe em uma unica chamada
+// Textura e normal map são parte do grupo: o sampler do shader não pode ser escolhido por instancia
+pub struct InstanceBatch {
+    pub program: u32,
+    pub gpu_mesh: MeshHandle,
+    pub submesh: usize,
+    pub lod: usize,
+    pub texture: Option<TextureHandle>,
+    pub normal_map: Option<TextureHandle>,
+    pub instances: Vec<InstanceData>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone)]
+// Uniforms do desenho instanciado de um programa, buscados uma unica vez
+struct InstanceUniforms {
+    instanced: i32,
+    texture: i32,
+    normal_map: i32,
+    has_normal_map: i32,
+    flip_texture_uv: i32,
+    flip_normal_map_uv: i32,
+    skinned: i32,
+    bbox_min: i32,
+    bbox_max: i32,
+}
+
+#[allow(dead_code)]
+// Desenho instanciado de objs repetidos
+// Os objs da frame são agrupados por shader, malha, submesh, nivel de detalhe e texturas, e os dados de cada
+// instancia são enviados em um unico buffer. Cada grupo é desenhado com um glDrawElementsInstanced
+pub struct InstancedRenderer {
+    instance_vbo: u32,
+    batches: Vec<InstanceBatch>,
+    batch_ids: HashMap<(u32, usize, usize, usize, u32, u32), usize>,
+    uniforms: HashMap<u32, InstanceUniforms>,
+}
+
+#[allow(dead_code)]
+impl InstancedRenderer {
+    pub fn new() -> Self {
+        let mut instance_vbo = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut instance_vbo);
+        }
+        InstancedRenderer {
+            instance_vbo,
+            batches: Vec::new(),
+            batch_ids: HashMap::new(),
+            uniforms: HashMap::new(),
+        }
+    }
+
+    // Adiciona os submeshes do obj nos grupos do programa
+    // Objs com esqueleto tem pose propria e são desenhados imediatamente, sem instanciamento
+    pub fn push(&mut self, obj: &ObjModel, program: &u32) {
+        if obj.skin.is_some() {
+            obj.draw(program);
+            return;
+        }
+        let lod = obj.select_lod();
+        for (submesh_id, submesh) in obj.submeshes.iter().enumerate() {
+            let (color, texture, specular, ambient, phong_q) = obj.resolve_material(submesh);
+            let normal_map = obj.resolve_normal_map(submesh);
+            let texture_id = |texture: &Option<TextureHandle>| texture.as_ref().map_or(0, |t| t.id);
+            let key = (
+                *program,
+                Arc::as_ptr(&obj.gpu_mesh) as usize,
+                submesh_id,
+                lod,
+                texture_id(&texture),
+                texture_id(&normal_map),
+            );
+
+            let next_id = self.batches.len();
+            let id = *self.batch_ids.entry(key).or_insert(next_id);
+            if id == next_id {
+                self.batches.push(InstanceBatch {
+                    program: *program,
+                    gpu_mesh: obj.gpu_mesh.clone(),
+                    submesh: submesh_id,
+                    lod,
+                    texture,
+                    normal_map,
+                    instances: Vec::new(),
+                });
+            }
+            self.batches[id].instances.push(InstanceData::from_obj(
+                obj, color, specular, ambient, phong_q,
+            ));
+        }
+    }
+
+    // Adiciona todos os objs simples que compõe o obj
+    pub fn push_scene_object(&mut self, obj: &SceneObject, program: &u32) {
+        for obj_model in obj.world_obj_models().iter() {
+            self.push(obj_model, program);
+        }
+    }
+
+    // Adiciona apenas os objs simples que intersectam o frustum, como Draw::draw_in_frustum
+    // Retorna o numero de objs simples descartados
+    pub fn push_in_frustum(
+        &mut self,
+        obj: &SceneObject,
+        program: &u32,
+        frustum: &Frustum,
+    ) -> usize {
+        let mut culled = 0;
+        for obj_model in obj.world_obj_models().iter() {
+            let (world_min, world_max) = obj_model.world_bbox();
+            if frustum.intersects_bbox(&world_min, &world_max) {
+                self.push(obj_model, program);
+            } else {
+                culled = culled + 1;
+            }
+        }
+        culled
+    }
+
+    // Quantidade de instancias aguardando desenho
+    pub fn instance_count(&self) -> usize {
+        self.batches.iter().map(|batch| batch.instances.len()).sum()
+    }
+
+    // Desenha e descarta os grupos. Retorna o numero de chamadas de desenho
+    pub fn draw(&mut self) -> usize {
+        if self.batches.len() == 0 {
+            return 0;
+        }
+
+        // Grupos do mesmo programa e da mesma malha ficam em sequencia
+        self.batches.sort_by_key(|batch| {
+            (
+                batch.program,
+                Arc::as_ptr(&batch.gpu_mesh) as usize,
+                batch.submesh,
+                batch.lod,
+            )
+        });
+
+        // Todas as instancias da frame são enviadas de uma vez
+        let instances: Vec<InstanceData> = self
+            .batches
+            .iter()
+            .flat_map(|batch| batch.instances.iter().cloned())
+            .collect();
+        let stride = mem::size_of::<InstanceData>();
+
+        let programs: Vec<u32> = self.batches.iter().map(|batch| batch.program).collect();
+        for program in programs.iter() {
+            self.load_uniforms(*program);
+        }
+
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (instances.len() * stride) as GLsizeiptr,
+                instances.as_ptr() as *const c_void,
+                gl::STREAM_DRAW,
+            );
+
+            let mut first_instance = 0;
+            for batch in self.batches.iter() {
+                let uniforms = self.uniforms[&batch.program];
+                let mesh = &batch.gpu_mesh;
+                let (index_offset, index_count) = mesh.submesh_range(batch.lod, batch.submesh);
+
+                gl::UseProgram(batch.program);
+                gl::Uniform1i(uniforms.instanced, 1);
+                let mut texture_units = TextureUnits::new();
+                texture_units.bind_uniform(uniforms.texture, batch.texture.as_ref());
+                let has_normal_map =
+                    texture_units.bind_uniform(uniforms.normal_map, batch.normal_map.as_ref());
+                gl::Uniform1i(uniforms.has_normal_map, has_normal_map as i32);
+                gl::Uniform1i(
+                    uniforms.flip_texture_uv,
+                    flip_uv(batch.texture.as_ref()) as i32,
+                );
+                gl::Uniform1i(
+                    uniforms.flip_normal_map_uv,
+                    flip_uv(batch.normal_map.as_ref()) as i32,
+                );
+                gl::Uniform1i(uniforms.skinned, 0);
+                gl::Uniform4f(
+                    uniforms.bbox_min,
+                    mesh.mesh.bbox_min.x,
+                    mesh.mesh.bbox_min.y,
+                    mesh.mesh.bbox_min.z,
+                    1.0,
+                );
+                gl::Uniform4f(
+                    uniforms.bbox_max,
+                    mesh.mesh.bbox_max.x,
+                    mesh.mesh.bbox_max.y,
+                    mesh.mesh.bbox_max.z,
+                    1.0,
+                );
+
+                // Atributos por instancia apontam para o trecho do grupo no buffer
+                gl::BindVertexArray(mesh.vao);
+                gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+                for i in 0..INSTANCE_VEC4S {
+                    let location = INSTANCE_LOCATION + i as GLuint;
+                    let offset = first_instance * stride + i * 4 * mem::size_of::<f32>();
+                    gl::VertexAttribPointer(
+                        location,
+                        4,
+                        gl::FLOAT,
+                        gl::FALSE,
+                        stride as i32,
+                        offset as *const c_void,
+                    );
+                    gl::VertexAttribDivisor(location, 1);
+                    gl::EnableVertexAttribArray(location);
+                }
+
+                gl::DrawElementsInstanced(
+                    gl::TRIANGLES,
+                    index_count as i32,
+                    gl::UNSIGNED_INT,
+                    (index_offset * mem::size_of::<u32>()) as *const c_void,
+                    batch.instances.len() as i32,
+                );
+
+                // A VAO volta a ser usada no desenho simples, sem os atributos por instancia
+                for i in 0..INSTANCE_VEC4S {
+                    gl::DisableVertexAttribArray(INSTANCE_LOCATION + i as GLuint);
+                }
+                gl::Uniform1i(uniforms.instanced, 0);
+
+                first_instance = first_instance + batch.instances.len();
+            }
+
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+
+        let draw_calls = self.batches.len();
+        self.batches.clear();
+        self.batch_ids.clear();
+        draw_calls
+    }
+
+    fn load_uniforms(&mut self, program: u32) {
+        self.uniforms.entry(program).or_insert_with(|| unsafe {
+            let location =
+                |name: &str| gl::GetUniformLocation(program, CString::new(name).unwrap().as_ptr());
+            InstanceUniforms {
+                instanced: location("instanced"),
+                texture: location("texture_overide"),
+                normal_map: location("normal_map"),
+                has_normal_map: location("has_normal_map"),
+                flip_texture_uv: location("flip_texture_uv"),
+                flip_normal_map_uv: location("flip_normal_map_uv"),
+                skinned: location("skinned"),
+                bbox_min: location("bbox_min"),
+                bbox_max: location("bbox_max"),
+            }
+        });
+    }
+}
+
+impl Drop for InstancedRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.instance_vbo);
+        }
+    }
+}
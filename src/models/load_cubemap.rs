@@ -0,0 +1,304 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use gl;
+use models::load_texture::decode_texture_rows;
+use models::load_texture::upload_image;
+use models::load_texture::PixelFormat;
+use models::load_texture::TextureError;
+use models::load_texture::TextureImage;
+use models::load_texture::TextureUsage;
+use models::resources::cached_texture;
+use models::resources::register_texture;
+use models::resources::Texture;
+use models::resources::TextureHandle;
+use models::sampler::SamplerDesc;
+
+// Cubemaps: texturas de 6 faces quadradas amostradas por direção (ex: skybox, ver skybox.rs)
+// As faces seguem a ordem dos alvos do OpenGL: +X, -X, +Y, -Y, +Z, -Z, com a primeira linha
+// de cada imagem sendo a linha de cima do arquivo. Cubemaps são texturas de cor (sRGB)
+
+// Decodifica as 6 faces de arquivos separados, na ordem dos alvos do OpenGL
+// As faces devem ser quadradas e ter o mesmo tamanho e formato
+pub fn decode_cubemap_faces(paths: &[&str; 6]) -> Result<Vec<TextureImage>, TextureError> {
+    let faces = paths
+        .iter()
+        .map(|path| decode_texture_rows(path, TextureUsage::Color))
+        .collect::<Result<Vec<TextureImage>, TextureError>>()?;
+
+    let first = &faces[0];
+    for (face, path) in faces.iter().zip(paths.iter()) {
+        if face.width != face.height || face.width != first.width || face.format != first.format {
+            return Err(TextureError::Decode {
+                path: path.to_string(),
+                message: format!(
+                    "face de cubemap {}x{} {:?}, esperado {}x{} {:?}",
+                    face.width, face.height, face.format, first.width, first.width, first.format
+                ),
+            });
+        }
+    }
+    Ok(faces)
+}
+
+// Decodifica uma imagem equirretangular (longitude em x, latitude em y) e a converte nas 6 faces
+// O centro da imagem fica na direção -Z, e cada face tem um quarto da largura da imagem
+pub fn decode_equirectangular(path: &str) -> Result<Vec<TextureImage>, TextureError> {
+    let image = decode_texture_rows(path, TextureUsage::Color)?;
+    let face_size = (image.width / 4).max(1);
+    Ok((0..6)
+        .map(|face| equirectangular_face(&image, face, face_size))
+        .collect())
+}
+
+// Gera uma face do cubemap amostrando a imagem equirretangular na direção de cada pixel
+fn equirectangular_face(image: &TextureImage, face: usize, face_size: u32) -> TextureImage {
+    let channels = image.format.channels();
+    let mut pixels =
+        Vec::with_capacity((face_size * face_size) as usize * image.format.bytes_per_pixel());
+
+    for y in 0..face_size {
+        for x in 0..face_size {
+            let sc = 2.0 * (x as f32 + 0.5) / face_size as f32 - 1.0;
+            let tc = 2.0 * (y as f32 + 0.5) / face_size as f32 - 1.0;
+            let direction = glm::normalize(face_direction(face, sc, tc));
+
+            // Longitude a partir de -Z, no sentido de +X, e latitude a partir do horizonte
+            let longitude = direction.x.atan2(-direction.z);
+            let latitude = glm::clamp(direction.y, -1.0, 1.0).asin();
+            let u = 0.5 + longitude / (2.0 * PI);
+            let v = 0.5 - latitude / PI;
+
+            for channel in 0..channels {
+                let value = sample_bilinear(image, u, v, channel);
+                write_channel(&mut pixels, image.format, value);
+            }
+        }
+    }
+
+    TextureImage {
+        width: face_size,
+        height: face_size,
+        format: image.format,
+        usage: image.usage,
+        pixels,
+    }
+}
+
+// Direção do pixel de coordenadas (sc, tc) em [-1..1] da face, invertendo a escolha de face e
+// coordenadas da especificação do OpenGL (tabela 8.19 da especificação 3.3)
+fn face_direction(face: usize, sc: f32, tc: f32) -> glm::Vec3 {
+    match face {
+        0 => glm::vec3(1.0, -tc, -sc),
+        1 => glm::vec3(-1.0, -tc, sc),
+        2 => glm::vec3(sc, 1.0, tc),
+        3 => glm::vec3(sc, -1.0, -tc),
+        4 => glm::vec3(sc, -tc, 1.0),
+        _ => glm::vec3(-sc, -tc, -1.0),
+    }
+}
+
+// Valor de um canal em (u, v), interpolado entre os 4 pixels mais proximos
+// u se repete na horizontal (a longitude dá a volta na imagem), v é limitado às bordas
+fn sample_bilinear(image: &TextureImage, u: f32, v: f32, channel: usize) -> f32 {
+    let width = image.width as i64;
+    let height = image.height as i64;
+    let x = u * width as f32 - 0.5;
+    let y = v * height as f32 - 0.5;
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let tx = x - x0;
+    let ty = y - y0;
+
+    let pixel = |x: i64, y: i64| {
+        let x = ((x % width) + width) % width;
+        let y = y.max(0).min(height - 1);
+        read_channel(image, (y * width + x) as usize, channel)
+    };
+
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let top = pixel(x0, y0) * (1.0 - tx) + pixel(x0 + 1, y0) * tx;
+    let bottom = pixel(x0, y0 + 1) * (1.0 - tx) + pixel(x0 + 1, y0 + 1) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
+
+// Valor em [0..1] de um canal do pixel de indice informado
+fn read_channel(image: &TextureImage, pixel: usize, channel: usize) -> f32 {
+    let bytes_per_channel = image.format.bytes_per_channel();
+    let offset = (pixel * image.format.channels() + channel) * bytes_per_channel;
+    if bytes_per_channel == 1 {
+        image.pixels[offset] as f32 / 255.0
+    } else {
+        u16::from_ne_bytes([image.pixels[offset], image.pixels[offset + 1]]) as f32 / 65535.0
+    }
+}
+
+fn write_channel(pixels: &mut Vec<u8>, format: PixelFormat, value: f32) {
+    let value = glm::clamp(value, 0.0, 1.0);
+    if format.bytes_per_channel() == 1 {
+        pixels.push((value * 255.0).round() as u8);
+    } else {
+        pixels.extend_from_slice(&((value * 65535.0).round() as u16).to_ne_bytes());
+    }
+}
+
+// Cria o cubemap na GPU com as 6 faces decodificadas. Retorna a textura criada
+pub unsafe fn upload_cubemap(faces: &[TextureImage]) -> u32 {
+    let mut texture_id = 0;
+    gl::GenTextures(1, &mut texture_id);
+
+    gl::ActiveTexture(gl::TEXTURE0);
+    gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture_id);
+    for (face, image) in faces.iter().enumerate() {
+        upload_image(gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as u32, 0, image);
+    }
+    gl::TexParameteriv(
+        gl::TEXTURE_CUBE_MAP,
+        gl::TEXTURE_SWIZZLE_RGBA,
+        faces[0].gl_swizzle().as_ptr(),
+    );
+    gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+
+    // Filtra entre faces vizinhas nas arestas, evitando costuras visiveis
+    gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+
+    texture_id
+}
+
+// Carrega um cubemap de 6 arquivos, na ordem +X, -X, +Y, -Y, +Z, -Z
+pub unsafe fn load_cubemap(paths: &[&str; 6]) -> Result<u32, TextureError> {
+    Ok(upload_cubemap(&decode_cubemap_faces(paths)?))
+}
+
+// Carrega um cubemap convertendo uma imagem equirretangular
+pub unsafe fn load_equirectangular_cubemap(path: &str) -> Result<u32, TextureError> {
+    Ok(upload_cubemap(&decode_equirectangular(path)?))
+}
+
+// Carrega um cubemap de 6 arquivos uma unica vez pelos caminhos das faces
+#[allow(dead_code)]
+pub fn load_cubemap_resource(paths: &[&str; 6]) -> Result<TextureHandle, TextureError> {
+    let key = format!("{}#cubemap", paths.join("|"));
+    load_cubemap_resource_with(&key, || unsafe { load_cubemap(paths) })
+}
+
+// Carrega um cubemap equirretangular uma unica vez por caminho
+#[allow(dead_code)]
+pub fn load_equirectangular_resource(path: &str) -> Result<TextureHandle, TextureError> {
+    load_cubemap_resource_with(&format!("{}#cubemap", path), || unsafe {
+        load_equirectangular_cubemap(path)
+    })
+}
+
+fn load_cubemap_resource_with<F>(key: &str, load: F) -> Result<TextureHandle, TextureError>
+where
+    F: FnOnce() -> Result<u32, TextureError>,
+{
+    if let Some(texture) = cached_texture(key) {
+        return Ok(texture);
+    }
+    let texture = Arc::new(Texture::cubemap(load()?, &SamplerDesc::new()));
+    register_texture(key, &texture);
+    Ok(texture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cor do octante: canal cheio para coordenada positiva (r = x, g = y, b = z)
+    fn octant_color(x: f32, y: f32, z: f32) -> [u8; 3] {
+        let channel = |value: f32| if value > 0.0 { 255 } else { 0 };
+        [channel(x), channel(y), channel(z)]
+    }
+
+    // Imagem equirretangular 16x8 com uma cor por octante. Da esquerda para a direita, cada quarto
+    // da largura cobre as longitudes de +Z a -X, de -X a -Z, de -Z a +X e de +X a +Z. A metade de
+    // cima é y > 0
+    fn octant_image() -> TextureImage {
+        let mut pixels = Vec::new();
+        for row in 0..8 {
+            for column in 0..16 {
+                let y = if row < 4 { 1.0 } else { -1.0 };
+                let (x, z) = match column / 4 {
+                    0 => (-1.0, 1.0),
+                    1 => (-1.0, -1.0),
+                    2 => (1.0, -1.0),
+                    _ => (1.0, 1.0),
+                };
+                pixels.extend_from_slice(&octant_color(x, y, z));
+            }
+        }
+        TextureImage {
+            width: 16,
+            height: 8,
+            format: PixelFormat::Rgb8,
+            usage: TextureUsage::Color,
+            pixels,
+        }
+    }
+
+    fn pixel(face: &TextureImage, x: usize, y: usize) -> [u8; 3] {
+        let offset = 3 * (y * face.width as usize + x);
+        [
+            face.pixels[offset],
+            face.pixels[offset + 1],
+            face.pixels[offset + 2],
+        ]
+    }
+
+    #[test]
+    fn face_centers_follow_gl_targets() {
+        let axes = [
+            glm::vec3(1.0, 0.0, 0.0),
+            glm::vec3(-1.0, 0.0, 0.0),
+            glm::vec3(0.0, 1.0, 0.0),
+            glm::vec3(0.0, -1.0, 0.0),
+            glm::vec3(0.0, 0.0, 1.0),
+            glm::vec3(0.0, 0.0, -1.0),
+        ];
+        for (face, axis) in axes.iter().enumerate() {
+            assert_eq!(face_direction(face, 0.0, 0.0), *axis, "face {}", face);
+        }
+    }
+
+    #[test]
+    fn equirectangular_faces_sample_octants() {
+        let image = octant_image();
+        let faces: Vec<TextureImage> = (0..6)
+            .map(|face| equirectangular_face(&image, face, 4))
+            .collect();
+        for face in faces.iter() {
+            assert_eq!((face.width, face.height), (4, 4));
+            assert_eq!(face.format, PixelFormat::Rgb8);
+        }
+
+        // 4 pixels centrais de cada face, (x, y) da face e octante esperado, pela tabela de
+        // faces do OpenGL: em +X s cresce para -Z, em -Z para -X e em +Y para +X, com t
+        // crescendo para -Y nas faces laterais e para +Z em +Y
+        let expected = [
+            (0, 1, 1, octant_color(1.0, 1.0, 1.0)),
+            (0, 2, 1, octant_color(1.0, 1.0, -1.0)),
+            (0, 1, 2, octant_color(1.0, -1.0, 1.0)),
+            (0, 2, 2, octant_color(1.0, -1.0, -1.0)),
+            (5, 1, 1, octant_color(1.0, 1.0, -1.0)),
+            (5, 2, 1, octant_color(-1.0, 1.0, -1.0)),
+            (5, 1, 2, octant_color(1.0, -1.0, -1.0)),
+            (5, 2, 2, octant_color(-1.0, -1.0, -1.0)),
+            (2, 1, 1, octant_color(-1.0, 1.0, -1.0)),
+            (2, 2, 1, octant_color(1.0, 1.0, -1.0)),
+            (2, 1, 2, octant_color(-1.0, 1.0, 1.0)),
+            (2, 2, 2, octant_color(1.0, 1.0, 1.0)),
+        ];
+        for &(face, x, y, color) in expected.iter() {
+            assert_eq!(
+                pixel(&faces[face], x, y),
+                color,
+                "face {} pixel ({}, {})",
+                face,
+                x,
+                y
+            );
+        }
+    }
+}
@@ -1,77 +1,728 @@
 #![allow(dead_code)]
 
+use std::fmt;
+use std::fs;
+use std::io::Cursor;
 use std::os::raw::c_void;
-use std::path::Path;
 
 use gl;
 use image;
 use image::DynamicImage;
-use image::GenericImage;
-use rayon::iter::IntoParallelRefIterator;
-use rayon::iter::ParallelIterator;
+use models::block_compression::CompressedLevels;
+use models::texture_container::decode_container;
+use models::texture_container::is_container;
+use png;
+use png::HasParameters;
 
-// A função de carregamento de imagems da implementação original da biblioteca images é muito lenta,
-// Reimplementamos a mesma sem deferênciação dos valores e com paralelismo
-unsafe fn image_to_bytes(image: &DynamicImage) -> Vec<&u8> {
-    match *image {
-        DynamicImage::ImageLuma8(ref a) => a.par_iter().collect(),
-        DynamicImage::ImageLumaA8(ref a) => a.par_iter().collect(),
-        DynamicImage::ImageRgb8(ref a) => a.par_iter().collect(),
-        DynamicImage::ImageRgba8(ref a) => a.par_iter().collect(),
+#[derive(Debug)]
+// Erro ao carregar uma textura
+// path -> Caminho do arquivo, ou a origem da imagem embutida em um modelo
+pub enum TextureError {
+    // Falha ao ler o arquivo
+    Io { path: String, message: String },
+    // Arquivo corrompido ou em formato de imagem não reconhecido
+    Decode { path: String, message: String },
+    // Formato de pixel sem textura correspondente (ex: canais de 32 bits, ou cubemaps em KTX2)
+    UnsupportedFormat { path: String, format: String },
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TextureError::Io {
+                ref path,
+                ref message,
+            } => write!(f, "Falha ao ler textura {}: {}", path, message),
+            TextureError::Decode {
+                ref path,
+                ref message,
+            } => write!(f, "Falha ao decodificar textura {}: {}", path, message),
+            TextureError::UnsupportedFormat {
+                ref path,
+                ref format,
+            } => write!(
+                f,
+                "Formato de pixel não suportado na textura {}: {}",
+                path, format
+            ),
+        }
     }
 }
 
-pub unsafe fn load_texture(path: &str) -> (u32, u32) {
-    // Le arquivo de imagem
-    let img = image::open(&Path::new(&path))
-        .expect("Falha ao carregar textura")
-        .rotate180();
+#[derive(Debug, Copy, Clone, PartialEq)]
+// Uso da textura, que define se os valores são convertidos de sRGB para linear na amostragem
+// Color -> Cores (ex: textura difusa), armazenadas em sRGB
+// Data -> Valores que não são cores (ex: normal maps), lidos sem conversão
+pub enum TextureUsage {
+    Color,
+    Data,
+}
 
-    let data = image_to_bytes(&img);
+#[derive(Debug, Copy, Clone, PartialEq)]
+// Canais (L luminancia, A alpha) e bits por canal dos pixels de uma imagem
+pub enum PixelFormat {
+    L8,
+    La8,
+    Rgb8,
+    Rgba8,
+    L16,
+    La16,
+    Rgb16,
+    Rgba16,
+}
 
-    // let data2 = img.flipv().pixels().map(|pixel|pixel)
-    let mut texture_id = 0;
-    let mut sampler_id = 0;
+impl PixelFormat {
+    pub fn channels(&self) -> usize {
+        match *self {
+            PixelFormat::L8 | PixelFormat::L16 => 1,
+            PixelFormat::La8 | PixelFormat::La16 => 2,
+            PixelFormat::Rgb8 | PixelFormat::Rgb16 => 3,
+            PixelFormat::Rgba8 | PixelFormat::Rgba16 => 4,
+        }
+    }
+
+    pub fn bytes_per_channel(&self) -> usize {
+        match *self {
+            PixelFormat::L8 | PixelFormat::La8 | PixelFormat::Rgb8 | PixelFormat::Rgba8 => 1,
+            _ => 2,
+        }
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        self.channels() * self.bytes_per_channel()
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.channels() % 2 == 0
+    }
+}
+
+#[derive(Debug, Clone)]
+// Imagem decodificada e pronta para o envio à GPU, com os pixels em um buffer continuo, linha a linha
+// Canais de 16 bits ficam na ordem de bytes da CPU, como esperado pelo OpenGL
+pub struct TextureImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: PixelFormat,
+    pub usage: TextureUsage,
+    pub pixels: Vec<u8>,
+}
+
+impl TextureImage {
+    // Gira a imagem em 180 graus, orientação esperada pelos mapeamentos de textura dos shaders
+    fn rotate180(self) -> Self {
+        let bytes_per_pixel = self.format.bytes_per_pixel();
+        let pixels = self
+            .pixels
+            .chunks(bytes_per_pixel)
+            .rev()
+            .flat_map(|pixel| pixel.iter().cloned())
+            .collect();
+        TextureImage { pixels, ..self }
+    }
+
+    // Ajusta os pixels ao uso da textura. O OpenGL 3.3 só tem formatos sRGB de 8 bits com 3 ou 4
+    // canais, então cores em luminancia de 8 bits são expandidas para RGB e cores de 16 bits
+    // são convertidas para linear na CPU, mantendo a precisão
+    pub fn prepare(self, usage: TextureUsage) -> Self {
+        let image = TextureImage { usage, ..self };
+        if usage == TextureUsage::Data {
+            return image;
+        }
+
+        match image.format {
+            PixelFormat::L8 | PixelFormat::La8 => image.expand_luminance(),
+            PixelFormat::Rgb8 | PixelFormat::Rgba8 => image,
+            _ => image.linearize16(),
+        }
+    }
+
+    // L8 -> Rgb8 e La8 -> Rgba8, repetindo a luminancia nos 3 canais
+    fn expand_luminance(self) -> Self {
+        let channels = self.format.channels();
+        let pixels = self
+            .pixels
+            .chunks(channels)
+            .flat_map(|pixel| {
+                let mut rgba = vec![pixel[0], pixel[0], pixel[0]];
+                rgba.extend_from_slice(&pixel[1..]);
+                rgba
+            })
+            .collect();
+        let format = if self.format.has_alpha() {
+            PixelFormat::Rgba8
+        } else {
+            PixelFormat::Rgb8
+        };
+        TextureImage {
+            format,
+            pixels,
+            ..self
+        }
+    }
+
+    // Converte os canais de cor de 16 bits de sRGB para linear. O alpha já é linear
+    fn linearize16(self) -> Self {
+        let channels = self.format.channels();
+        let has_alpha = self.format.has_alpha();
+        let mut pixels = self.pixels;
+
+        for (id, value) in pixels.chunks_mut(2).enumerate() {
+            if has_alpha && id % channels == channels - 1 {
+                continue;
+            }
+            let srgb = u16::from_ne_bytes([value[0], value[1]]) as f32 / 65535.0;
+            let linear = if srgb <= 0.04045 {
+                srgb / 12.92
+            } else {
+                ((srgb + 0.055) / 1.055).powf(2.4)
+            };
+            let linear = (linear * 65535.0).round() as u16;
+            value.copy_from_slice(&linear.to_ne_bytes());
+        }
+        TextureImage { pixels, ..self }
+    }
+
+    // Formato interno, formato e tipo dos pixels no OpenGL
+    pub fn gl_formats(&self) -> (gl::types::GLenum, gl::types::GLenum, gl::types::GLenum) {
+        let color = self.usage == TextureUsage::Color;
+        match self.format {
+            PixelFormat::L8 => (gl::R8, gl::RED, gl::UNSIGNED_BYTE),
+            PixelFormat::La8 => (gl::RG8, gl::RG, gl::UNSIGNED_BYTE),
+            PixelFormat::Rgb8 if color => (gl::SRGB8, gl::RGB, gl::UNSIGNED_BYTE),
+            PixelFormat::Rgb8 => (gl::RGB8, gl::RGB, gl::UNSIGNED_BYTE),
+            PixelFormat::Rgba8 if color => (gl::SRGB8_ALPHA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            PixelFormat::Rgba8 => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            PixelFormat::L16 => (gl::R16, gl::RED, gl::UNSIGNED_SHORT),
+            PixelFormat::La16 => (gl::RG16, gl::RG, gl::UNSIGNED_SHORT),
+            PixelFormat::Rgb16 => (gl::RGB16, gl::RGB, gl::UNSIGNED_SHORT),
+            PixelFormat::Rgba16 => (gl::RGBA16, gl::RGBA, gl::UNSIGNED_SHORT),
+        }
+    }
+
+    // Canais lidos pelos shaders. Imagens em luminancia repetem o canal R em RGB, com o alpha
+    // no canal G
+    pub fn gl_swizzle(&self) -> [gl::types::GLint; 4] {
+        let swizzle = match self.format.channels() {
+            1 => [gl::RED, gl::RED, gl::RED, gl::ONE],
+            2 => [gl::RED, gl::RED, gl::RED, gl::GREEN],
+            _ => [gl::RED, gl::GREEN, gl::BLUE, gl::ALPHA],
+        };
+        [
+            swizzle[0] as i32,
+            swizzle[1] as i32,
+            swizzle[2] as i32,
+            swizzle[3] as i32,
+        ]
+    }
+}
+
+#[derive(Debug, Clone)]
+// Textura decodificada e pronta para o envio à GPU
+// Image -> Imagem (png, jpg...) girada em 180 graus, sem mipmaps, gerados na GPU após o envio
+// Levels -> Cadeia de mipmaps já calculada de um container (ver texture_container.rs), do nivel 0
+//           ao menor. Com um unico nivel os mipmaps são gerados na GPU, como em Image
+// Compressed -> Cadeia de mipmaps comprimida em blocos (ver block_compression.rs)
+pub enum DecodedTexture {
+    Image(TextureImage),
+    Levels(Vec<TextureImage>),
+    Compressed(CompressedLevels),
+}
+
+impl DecodedTexture {
+    // Niveis de containers ficam na orientação do arquivo: girar blocos comprimidos exigiria
+    // descomprimi-los. Os shaders amostram essas texturas com U e V invertidos, o que equivale
+    // ao giro de 180 graus das demais imagens (ver Texture::flip_uv)
+    pub fn flip_uv(&self) -> bool {
+        match *self {
+            DecodedTexture::Image(_) => false,
+            DecodedTexture::Levels(_) | DecodedTexture::Compressed(_) => true,
+        }
+    }
+}
+
+// Decodifica a imagem de uma textura sem acessar o OpenGL, podendo rodar fora da thread do
+// contexto (ver asset_loader.rs). Aceita imagens (png, jpg...) e containers KTX2 e DDS
+pub fn decode_texture(path: &str, usage: TextureUsage) -> Result<DecodedTexture, TextureError> {
+    let bytes = fs::read(path).map_err(|error| TextureError::Io {
+        path: path.to_string(),
+        message: error.to_string(),
+    })?;
+    decode_bytes(path, &bytes, usage)
+}
+
+// Decodifica a imagem sem girar: a primeira linha do buffer é a linha de cima do arquivo, como
+// esperado pelas faces de cubemaps (ver load_cubemap.rs)
+pub fn decode_texture_rows(path: &str, usage: TextureUsage) -> Result<TextureImage, TextureError> {
+    let bytes = fs::read(path).map_err(|error| TextureError::Io {
+        path: path.to_string(),
+        message: error.to_string(),
+    })?;
+    decode_image(path, &bytes, usage)
+}
+
+// Decodifica um arquivo de imagem já lido para a memoria (png, jpg, KTX2, DDS...)
+// Usado por texturas embutidas em modelos, como no formato glTF
+pub fn decode_texture_from_memory(
+    bytes: &[u8],
+    usage: TextureUsage,
+) -> Result<DecodedTexture, TextureError> {
+    decode_bytes("<imagem embutida>", bytes, usage)
+}
+
+// Imagens são giradas para a orientação dos shaders, containers ficam na orientação do arquivo
+// (ver DecodedTexture::flip_uv)
+fn decode_bytes(
+    path: &str,
+    bytes: &[u8],
+    usage: TextureUsage,
+) -> Result<DecodedTexture, TextureError> {
+    if is_container(bytes) {
+        decode_container(path, bytes, usage)
+    } else {
+        Ok(DecodedTexture::Image(
+            decode_image(path, bytes, usage)?.rotate180(),
+        ))
+    }
+}
+
+fn decode_image(
+    path: &str,
+    bytes: &[u8],
+    usage: TextureUsage,
+) -> Result<TextureImage, TextureError> {
+    let decode_error = |error: image::ImageError| match error {
+        image::ImageError::UnsupportedColor(color) => TextureError::UnsupportedFormat {
+            path: path.to_string(),
+            format: format!("{:?}", color),
+        },
+        error => TextureError::Decode {
+            path: path.to_string(),
+            message: error.to_string(),
+        },
+    };
+
+    // O decodificador de png da biblioteca image reduz canais de 16 bits para 8
+    let format = image::guess_format(bytes).map_err(decode_error)?;
+    let image = if format == image::ImageFormat::PNG {
+        decode_png(path, bytes)?
+    } else {
+        from_dynamic_image(
+            image::load_from_memory_with_format(bytes, format).map_err(decode_error)?,
+        )
+    };
+
+    Ok(image.prepare(usage))
+}
+
+// Decodifica um png mantendo canais de 16 bits. Paletas e profundidades menores que 8 bits são
+// expandidas para 8 bits por canal
+fn decode_png(path: &str, bytes: &[u8]) -> Result<TextureImage, TextureError> {
+    let decode_error = |error: png::DecodingError| TextureError::Decode {
+        path: path.to_string(),
+        message: error.to_string(),
+    };
+
+    let mut decoder = png::Decoder::new(Cursor::new(bytes));
+    decoder.set(png::Transformations::EXPAND);
+    let (info, mut reader) = decoder.read_info().map_err(decode_error)?;
+
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut pixels).map_err(decode_error)?;
+
+    // Com a expansão, profundidades menores que 8 bits viram 8 bits, mas o png reporta 8 bits
+    // também para imagens de 16 bits, que não são alteradas
+    let (color_type, _) = reader.output_color_type();
+    let sixteen_bits = reader.info().bit_depth as u8 == 16;
+    let format = match (color_type, sixteen_bits) {
+        (png::ColorType::Grayscale, false) => PixelFormat::L8,
+        (png::ColorType::GrayscaleAlpha, false) => PixelFormat::La8,
+        (png::ColorType::RGB, false) => PixelFormat::Rgb8,
+        (png::ColorType::RGBA, false) => PixelFormat::Rgba8,
+        (png::ColorType::Grayscale, true) => PixelFormat::L16,
+        (png::ColorType::GrayscaleAlpha, true) => PixelFormat::La16,
+        (png::ColorType::RGB, true) => PixelFormat::Rgb16,
+        (png::ColorType::RGBA, true) => PixelFormat::Rgba16,
+        (color_type, _) => {
+            return Err(TextureError::UnsupportedFormat {
+                path: path.to_string(),
+                format: format!("{:?} {:?}", color_type, reader.info().bit_depth),
+            })
+        }
+    };
+
+    let expected = (info.width * info.height) as usize * format.bytes_per_pixel();
+    if pixels.len() != expected {
+        return Err(TextureError::Decode {
+            path: path.to_string(),
+            message: format!("{} bytes de pixels, esperado {}", pixels.len(), expected),
+        });
+    }
 
+    // Canais de 16 bits do png são big endian
+    if format.bytes_per_channel() == 2 {
+        for value in pixels.chunks_mut(2) {
+            let native = u16::from_be_bytes([value[0], value[1]]).to_ne_bytes();
+            value.copy_from_slice(&native);
+        }
+    }
+
+    Ok(TextureImage {
+        width: info.width,
+        height: info.height,
+        format,
+        usage: TextureUsage::Data,
+        pixels,
+    })
+}
+
+fn from_dynamic_image(image: DynamicImage) -> TextureImage {
+    let (width, height, format, pixels) = match image {
+        DynamicImage::ImageLuma8(image) => (
+            image.width(),
+            image.height(),
+            PixelFormat::L8,
+            image.into_raw(),
+        ),
+        DynamicImage::ImageLumaA8(image) => (
+            image.width(),
+            image.height(),
+            PixelFormat::La8,
+            image.into_raw(),
+        ),
+        DynamicImage::ImageRgb8(image) => (
+            image.width(),
+            image.height(),
+            PixelFormat::Rgb8,
+            image.into_raw(),
+        ),
+        DynamicImage::ImageRgba8(image) => (
+            image.width(),
+            image.height(),
+            PixelFormat::Rgba8,
+            image.into_raw(),
+        ),
+    };
+
+    TextureImage {
+        width,
+        height,
+        format,
+        usage: TextureUsage::Data,
+        pixels,
+    }
+}
+
+// Textura de 1 pixel cinza, exibida enquanto a imagem real é carregada
+// A imagem é enviada depois para a mesma textura com upload_decoded_texture
+pub unsafe fn load_placeholder_texture() -> u32 {
+    upload_texture(&DecodedTexture::Image(TextureImage {
+        width: 1,
+        height: 1,
+        format: PixelFormat::Rgb8,
+        usage: TextureUsage::Color,
+        pixels: vec![128, 128, 128],
+    }))
+}
+
+// Cria uma textura com a imagem decodificada (ver decode_texture). Texturas de cor passam pela
+// conversão de sRGB para linear na amostragem, texturas de dados (ex: normal maps) não
+// Retorna a textura criada, os parametros de amostragem ficam no sampler (ver sampler.rs)
+pub unsafe fn upload_texture(texture: &DecodedTexture) -> u32 {
+    let mut texture_id = 0;
     gl::GenTextures(1, &mut texture_id);
-    gl::GenSamplers(1, &mut sampler_id);
+    upload_decoded_texture(texture_id, texture);
+    texture_id
+}
 
-    // Veja slide 100 do documento "Aula_20_e_21_Mapeamento_de_Texturas.pdf"
-    gl::SamplerParameteri(sampler_id, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
-    gl::SamplerParameteri(sampler_id, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+// Envia uma textura decodificada (ver decode_texture) para uma textura existente, substituindo
+// a imagem anterior. Os handles da textura continuam validos
+pub unsafe fn upload_decoded_texture(texture_id: u32, texture: &DecodedTexture) {
+    // Agora enviamos a imagem lida do disco para a GPU. O envio usa a primeira unidade, as
+    // unidades do desenho são ligadas de novo a cada desenho (ver TextureUnits)
+    gl::ActiveTexture(gl::TEXTURE0);
+    gl::BindTexture(gl::TEXTURE_2D, texture_id);
 
-    // Parâmetros de amostragem da textura.
-    gl::SamplerParameteri(
-        sampler_id,
-        gl::TEXTURE_MIN_FILTER,
-        gl::LINEAR_MIPMAP_LINEAR as i32,
-    );
+    // Niveis usados na amostragem. A textura pode ter recebido antes uma cadeia de outro tamanho
+    let level_count = match *texture {
+        DecodedTexture::Image(ref image) => {
+            upload_image(gl::TEXTURE_2D, 0, image);
+            set_swizzle(image.gl_swizzle());
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+            None
+        }
+        DecodedTexture::Levels(ref images) => {
+            for (level, image) in images.iter().enumerate() {
+                upload_image(gl::TEXTURE_2D, level as i32, image);
+            }
+            set_swizzle(images[0].gl_swizzle());
+            if images.len() == 1 {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+                None
+            } else {
+                Some(images.len())
+            }
+        }
+        DecodedTexture::Compressed(ref compressed) => {
+            upload_compressed(compressed);
+            Some(compressed.levels.len())
+        }
+    };
 
-    gl::SamplerParameteri(sampler_id, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+    // Sem cadeia pré-calculada vale o padrão do OpenGL, que usa todos os niveis gerados
+    let max_level = level_count.map_or(1000, |count| count as i32 - 1);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_BASE_LEVEL, 0);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, max_level);
+}
+
+// Envia os niveis comprimidos sem descompressão se o driver aceitar o formato, senão descomprime
+// os niveis na CPU
+unsafe fn upload_compressed(compressed: &CompressedLevels) {
+    let internal_format = match compressed.format.gl_internal_format(compressed.usage) {
+        Some(internal_format) => internal_format,
+        None => {
+            let images = compressed.decompress();
+            for (level, image) in images.iter().enumerate() {
+                upload_image(gl::TEXTURE_2D, level as i32, image);
+            }
+            set_swizzle(images[0].gl_swizzle());
+            return;
+        }
+    };
+
+    for (level, blocks) in compressed.levels.iter().enumerate() {
+        let (width, height) = compressed.level_size(level);
+        gl::CompressedTexImage2D(
+            gl::TEXTURE_2D,
+            level as i32,
+            internal_format,
+            width as i32,
+            height as i32,
+            0,
+            blocks.len() as i32,
+            blocks.as_ptr() as *const c_void,
+        );
+    }
+    set_swizzle([
+        gl::RED as i32,
+        gl::GREEN as i32,
+        gl::BLUE as i32,
+        gl::ALPHA as i32,
+    ]);
+}
+
+// A textura pode ter recebido antes uma imagem com outro numero de canais
+unsafe fn set_swizzle(swizzle: [gl::types::GLint; 4]) {
+    gl::TexParameteriv(gl::TEXTURE_2D, gl::TEXTURE_SWIZZLE_RGBA, swizzle.as_ptr());
+}
 
+// Envia a imagem para um nivel do alvo informado (ex: uma face de cubemap) da textura ligada
+pub unsafe fn upload_image(target: gl::types::GLenum, level: i32, image: &TextureImage) {
+    let (internal_format, format, pixel_type) = image.gl_formats();
+
+    // Linhas sem alinhamento, o buffer não tem espaços entre elas
     gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
     gl::PixelStorei(gl::UNPACK_ROW_LENGTH, 0);
     gl::PixelStorei(gl::UNPACK_SKIP_PIXELS, 0);
     gl::PixelStorei(gl::UNPACK_SKIP_ROWS, 0);
 
-    // Agora enviamos a imagem lida do disco para a GPU
-    gl::ActiveTexture(gl::TEXTURE0 + texture_id);
-    gl::BindTexture(gl::TEXTURE_2D, texture_id);
-
     gl::TexImage2D(
-        gl::TEXTURE_2D,
+        target,
+        level,
+        internal_format as i32,
+        image.width as i32,
+        image.height as i32,
         0,
-        gl::SRGB8 as i32,
-        img.width() as i32,
-        img.height() as i32,
-        0,
-        gl::RGB,
-        gl::UNSIGNED_BYTE,
-        data[0] as *const u8 as *const c_void,
+        format,
+        pixel_type,
+        image.pixels.as_ptr() as *const c_void,
     );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    gl::GenerateMipmap(gl::TEXTURE_2D);
-    gl::BindSampler(texture_id, sampler_id);
+    // Codifica um png na memoria. Os pixels são dados linha a linha, de cima para baixo, com
+    // canais de 16 bits em big endian
+    fn encode_png(
+        width: u32,
+        height: u32,
+        color: png::ColorType,
+        depth: png::BitDepth,
+        pixels: &[u8],
+    ) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, width, height);
+            encoder.set(color).set(depth);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(pixels).unwrap();
+        }
+        bytes
+    }
+
+    fn decoded_image(bytes: &[u8], usage: TextureUsage) -> TextureImage {
+        match decode_texture_from_memory(bytes, usage) {
+            Ok(DecodedTexture::Image(image)) => image,
+            other => panic!("resultado inesperado {:?}", other),
+        }
+    }
+
+    fn channels16(image: &TextureImage) -> Vec<u16> {
+        image
+            .pixels
+            .chunks(2)
+            .map(|value| u16::from_ne_bytes([value[0], value[1]]))
+            .collect()
+    }
+
+    // Valor linear esperado de um canal sRGB de 16 bits
+    fn linear16(srgb: u16) -> f32 {
+        let srgb = srgb as f32 / 65535.0;
+        ((srgb + 0.055) / 1.055).powf(2.4) * 65535.0
+    }
+
+    #[test]
+    fn luminance_expands_only_for_color() {
+        let l8 = encode_png(
+            2,
+            1,
+            png::ColorType::Grayscale,
+            png::BitDepth::Eight,
+            &[10, 200],
+        );
+
+        // Imagens são giradas em 180 graus
+        let data = decoded_image(&l8, TextureUsage::Data);
+        assert_eq!(data.format, PixelFormat::L8);
+        assert_eq!(data.pixels, vec![200, 10]);
+        assert_eq!(data.gl_formats(), (gl::R8, gl::RED, gl::UNSIGNED_BYTE));
+
+        let color = decoded_image(&l8, TextureUsage::Color);
+        assert_eq!(color.format, PixelFormat::Rgb8);
+        assert_eq!(color.pixels, vec![200, 200, 200, 10, 10, 10]);
+        assert_eq!(color.gl_formats(), (gl::SRGB8, gl::RGB, gl::UNSIGNED_BYTE));
+
+        let la8 = encode_png(
+            2,
+            1,
+            png::ColorType::GrayscaleAlpha,
+            png::BitDepth::Eight,
+            &[10, 20, 30, 40],
+        );
+        let data = decoded_image(&la8, TextureUsage::Data);
+        assert_eq!(data.format, PixelFormat::La8);
+        assert_eq!(data.pixels, vec![30, 40, 10, 20]);
+
+        let color = decoded_image(&la8, TextureUsage::Color);
+        assert_eq!(color.format, PixelFormat::Rgba8);
+        assert_eq!(color.pixels, vec![30, 30, 30, 40, 10, 10, 10, 20]);
+        assert_eq!(
+            color.gl_formats(),
+            (gl::SRGB8_ALPHA8, gl::RGBA, gl::UNSIGNED_BYTE)
+        );
+    }
+
+    #[test]
+    fn rgba8_orientation() {
+        // Linha de cima: 1, 2. Linha de baixo: 3, 4
+        let pixels: Vec<u8> = (1..5).flat_map(|p| vec![p, p, p, 255]).collect();
+        let bytes = encode_png(2, 2, png::ColorType::RGBA, png::BitDepth::Eight, &pixels);
 
-    (texture_id, sampler_id)
+        let rows = decode_image("rgba.png", &bytes, TextureUsage::Color).unwrap();
+        assert_eq!((rows.width, rows.height), (2, 2));
+        assert_eq!(rows.format, PixelFormat::Rgba8);
+        assert_eq!(rows.pixels, pixels);
+
+        let rotated = decoded_image(&bytes, TextureUsage::Color);
+        let first_channels: Vec<u8> = rotated.pixels.chunks(4).map(|p| p[0]).collect();
+        assert_eq!(first_channels, vec![4, 3, 2, 1]);
+
+        // Apenas cores são amostradas como sRGB
+        assert_eq!(
+            rotated.gl_formats(),
+            (gl::SRGB8_ALPHA8, gl::RGBA, gl::UNSIGNED_BYTE)
+        );
+        let data = decoded_image(&bytes, TextureUsage::Data);
+        assert_eq!(data.gl_formats(), (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE));
+    }
+
+    #[test]
+    fn rgb16_big_endian_to_native() {
+        // Pixel de cima (0x1234, 0, 0xffff), pixel de baixo (0x8000, 1, 0x00ff)
+        let bytes = encode_png(
+            1,
+            2,
+            png::ColorType::RGB,
+            png::BitDepth::Sixteen,
+            &[0x12, 0x34, 0, 0, 0xff, 0xff, 0x80, 0, 0, 1, 0, 0xff],
+        );
+
+        let data = decode_image("rgb16.png", &bytes, TextureUsage::Data).unwrap();
+        assert_eq!(data.format, PixelFormat::Rgb16);
+        assert_eq!(
+            channels16(&data),
+            vec![0x1234, 0, 0xffff, 0x8000, 1, 0x00ff]
+        );
+        assert_eq!(data.gl_formats(), (gl::RGB16, gl::RGB, gl::UNSIGNED_SHORT));
+
+        // Cores de 16 bits são convertidas para linear na CPU e continuam com 16 bits
+        let color = decoded_image(&bytes, TextureUsage::Color);
+        assert_eq!(color.format, PixelFormat::Rgb16);
+        assert_eq!(color.gl_formats(), (gl::RGB16, gl::RGB, gl::UNSIGNED_SHORT));
+        // Valores escuros seguem o trecho linear da curva sRGB
+        let expected = vec![
+            linear16(0x8000).round() as u16,
+            0,
+            (255.0f32 / 12.92).round() as u16,
+            linear16(0x1234).round() as u16,
+            0,
+            0xffff,
+        ];
+        assert_eq!(channels16(&color), expected);
+    }
+
+    #[test]
+    fn linearize16_skips_alpha() {
+        let values: [u16; 4] = [0x8000, 0x8000, 0x8000, 0x8000];
+        let image = TextureImage {
+            width: 2,
+            height: 1,
+            format: PixelFormat::La16,
+            usage: TextureUsage::Data,
+            pixels: values
+                .iter()
+                .flat_map(|v| v.to_ne_bytes().to_vec())
+                .collect(),
+        };
+
+        let linear = channels16(&image.clone().prepare(TextureUsage::Color));
+        let expected = linear16(0x8000).round() as u16;
+        assert_eq!(linear, vec![expected, 0x8000, expected, 0x8000]);
+
+        // Dados não são alterados
+        let data = image.prepare(TextureUsage::Data);
+        assert_eq!(data.usage, TextureUsage::Data);
+        assert_eq!(channels16(&data), values.to_vec());
+    }
+
+    #[test]
+    fn missing_file_is_io_error() {
+        let path = "src/data/textures/nao_existe.png";
+        for result in [
+            decode_texture(path, TextureUsage::Color).map(|_| ()),
+            decode_texture_rows(path, TextureUsage::Color).map(|_| ()),
+        ]
+        .iter()
+        {
+            match result {
+                Err(TextureError::Io {
+                    path: ref error_path,
+                    ..
+                }) => assert_eq!(error_path, path),
+                other => panic!("resultado inesperado {:?}", other),
+            }
+        }
+    }
 }
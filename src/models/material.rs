@@ -0,0 +1,202 @@
+use models::asset_loader::request_normal_map;
+use models::asset_loader::request_texture;
+use models::asset_loader::request_texture_from_memory;
+use models::load_texture::TextureUsage;
+use models::resources::load_normal_map_resource;
+use models::resources::load_normal_map_resource_from_memory;
+use models::resources::load_texture_resource;
+use models::resources::load_texture_resource_from_memory;
+use models::resources::TextureHandle;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use tobj;
+
+#[allow(dead_code)]
+#[derive(Clone)]
+// Origem de uma textura de material
+// File -> Caminho já resolvido em relação ao diretorio do arquivo do modelo
+// Encoded -> Imagem (png, jpg...) embutida no arquivo do modelo
+pub enum TextureSource {
+    File(PathBuf),
+    Encoded(Vec<u8>),
+}
+
+// Não imprime os bytes de imagens embutidas
+impl fmt::Debug for TextureSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TextureSource::File(ref path) => write!(f, "File({:?})", path),
+            TextureSource::Encoded(ref bytes) => write!(f, "Encoded({} bytes)", bytes.len()),
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Material lido do arquivo, sem recursos na GPU
+pub struct MaterialData {
+    pub name: String,
+    pub ambient: glm::Vec3,
+    pub diffuse: glm::Vec3,
+    pub specular: glm::Vec3,
+    pub shininess: f32,
+    pub diffuse_texture: Option<TextureSource>,
+    pub bump_texture: Option<TextureSource>,
+}
+
+#[allow(dead_code)]
+impl MaterialData {
+    // Converte um material lido pelo tobj
+    // Caminhos de textura no .mtl são relativos ao diretorio do arquivo obj
+    pub fn from_tobj(material: &tobj::Material, base_dir: &Path) -> Self {
+        let [ka_r, ka_g, ka_b] = material.ambient;
+        let [kd_r, kd_g, kd_b] = material.diffuse;
+        let [ks_r, ks_g, ks_b] = material.specular;
+
+        // O tobj não reconhece map_Bump, que fica nos parametros desconhecidos
+        let bump_path = ["map_Bump", "map_bump", "bump", "norm"]
+            .iter()
+            .filter_map(|key| material.unknown_param.get(*key))
+            .next()
+            .map(|value| value.as_str())
+            .unwrap_or(&material.normal_texture);
+
+        MaterialData {
+            name: material.name.clone(),
+            ambient: glm::vec3(ka_r, ka_g, ka_b),
+            diffuse: glm::vec3(kd_r, kd_g, kd_b),
+            specular: glm::vec3(ks_r, ks_g, ks_b),
+            shininess: material.shininess,
+            diffuse_texture: material_texture_path(base_dir, &material.diffuse_texture),
+            bump_texture: material_texture_path(base_dir, bump_path),
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Material importado de um arquivo .mtl
+// ambient -> Ka, refletancia ambiente
+// diffuse -> Kd, cor difusa do obj
+// specular -> Ks, refletancia especular
+// shininess -> Ns, expoente q de phong
+// diffuse_texture -> map_Kd, textura já carregada na GPU
+// bump_texture -> map_Bump/bump/norm, normal map em espaço tangente já carregado na GPU
+pub struct Material {
+    pub name: String,
+    pub ambient: glm::Vec3,
+    pub diffuse: glm::Vec3,
+    pub specular: glm::Vec3,
+    pub shininess: f32,
+    pub diffuse_texture: Option<TextureHandle>,
+    pub bump_texture: Option<TextureHandle>,
+}
+
+#[allow(dead_code)]
+impl Material {
+    // Material vazio: todos os valores zerados não sobreescrevem os padrões do shader
+    pub fn new(name: &str) -> Self {
+        Material {
+            name: name.to_string(),
+            ambient: glm::vec3(0.0, 0.0, 0.0),
+            diffuse: glm::vec3(0.0, 0.0, 0.0),
+            specular: glm::vec3(0.0, 0.0, 0.0),
+            shininess: 0.0,
+            diffuse_texture: None,
+            bump_texture: None,
+        }
+    }
+
+    // Carrega as texturas do material na GPU. Texturas de arquivo já carregadas são compartilhadas
+    pub fn from_data(data: &MaterialData) -> Self {
+        Material {
+            name: data.name.clone(),
+            ambient: data.ambient,
+            diffuse: data.diffuse,
+            specular: data.specular,
+            shininess: data.shininess,
+            diffuse_texture: load_material_texture(&data.diffuse_texture, false),
+            bump_texture: load_material_texture(&data.bump_texture, true),
+        }
+    }
+
+    // Como from_data, pedindo as texturas ao carregador assincrono (ver asset_loader.rs): as
+    // imagens são decodificadas no rayon e o material exibe texturas provisorias até o envio
+    pub fn request(data: &MaterialData) -> Self {
+        Material {
+            name: data.name.clone(),
+            ambient: data.ambient,
+            diffuse: data.diffuse,
+            specular: data.specular,
+            shininess: data.shininess,
+            diffuse_texture: request_material_texture(&data.diffuse_texture, false),
+            bump_texture: request_material_texture(&data.bump_texture, true),
+        }
+    }
+}
+
+// Caminho de uma textura referenciada no .mtl
+// Opções do map (ex: "-bm 0.5 bump.png") são ignoradas, utilizando o ultimo valor como arquivo
+fn material_texture_path(base_dir: &Path, texture: &str) -> Option<TextureSource> {
+    texture
+        .split_whitespace()
+        .last()
+        .map(|file_name| TextureSource::File(base_dir.join(file_name)))
+}
+
+// Pede uma textura do material ao carregador assincrono. Retorna None se o arquivo não existir,
+// falhas na decodificação são informadas no envio
+fn request_material_texture(
+    source: &Option<TextureSource>,
+    normal_map: bool,
+) -> Option<TextureHandle> {
+    let usage = if normal_map {
+        TextureUsage::Data
+    } else {
+        TextureUsage::Color
+    };
+    match *source {
+        Some(TextureSource::File(ref path)) if !path.is_file() => {
+            println!("Textura do material não encontrada: {:?}", path);
+            None
+        }
+        Some(TextureSource::File(ref path)) if normal_map => {
+            Some(request_normal_map(&path.to_string_lossy()))
+        }
+        Some(TextureSource::File(ref path)) => Some(request_texture(&path.to_string_lossy())),
+        Some(TextureSource::Encoded(ref bytes)) => Some(request_texture_from_memory(bytes, usage)),
+        None => None,
+    }
+}
+
+// Carrega uma textura do material. Retorna None se não existir ou não puder ser carregada
+// normal_map -> Carrega a textura como dado linear, sem conversão de sRGB
+fn load_material_texture(
+    source: &Option<TextureSource>,
+    normal_map: bool,
+) -> Option<TextureHandle> {
+    let texture = match *source {
+        Some(TextureSource::File(ref path)) if !path.is_file() => {
+            println!("Textura do material não encontrada: {:?}", path);
+            return None;
+        }
+        Some(TextureSource::File(ref path)) if normal_map => {
+            load_normal_map_resource(&path.to_string_lossy())
+        }
+        Some(TextureSource::File(ref path)) => load_texture_resource(&path.to_string_lossy()),
+        Some(TextureSource::Encoded(ref bytes)) if normal_map => {
+            load_normal_map_resource_from_memory(bytes)
+        }
+        Some(TextureSource::Encoded(ref bytes)) => load_texture_resource_from_memory(bytes),
+        None => return None,
+    };
+
+    match texture {
+        Ok(texture) => Some(texture),
+        Err(error) => {
+            println!("{}", error);
+            None
+        }
+    }
+}
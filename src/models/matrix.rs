@@ -4,6 +4,7 @@ use glm::sin;
 use glm::sqrt;
 use glm::tan;
 use glm::Vector4;
+use models::quaternion::Quaternion;
 
 #[allow(dead_code)]
 #[derive(Copy, Debug)]
@@ -42,6 +43,33 @@ pub const fn points_to_mat4(points: &[f32; 16]) -> glm::Mat4 {
     }
 }
 
+// Inverso de points_to_mat4: retorna os valores da matriz em row major
+pub fn mat4_to_points(matrix: &glm::Mat4) -> [f32; 16] {
+    [
+        matrix.c0.x, // LINHA 1
+        matrix.c1.x,
+        matrix.c2.x,
+        matrix.c3.x,
+        matrix.c0.y, // LINHA 2
+        matrix.c1.y,
+        matrix.c2.y,
+        matrix.c3.y,
+        matrix.c0.z, // LINHA 3
+        matrix.c1.z,
+        matrix.c2.z,
+        matrix.c3.z,
+        matrix.c0.w, // LINHA 4
+        matrix.c1.w,
+        matrix.c2.w,
+        matrix.c3.w,
+    ]
+}
+
+// Matrizes com determinante menor que esse valor, relativo ao produto das normas das colunas,
+// são consideradas singulares. O produto é o maior determinante possivel com essas colunas
+// (desigualdade de Hadamard), então escalas uniformes pequenas não tornam a matriz singular
+pub const SINGULAR_EPSILON: f32 = 1e-6;
+
 #[allow(dead_code)]
 pub const fn identity_matrix() -> GLMatrix {
     GLMatrix::new([
@@ -299,6 +327,39 @@ pub fn camera_view_matrix(
     ])
 }
 
+#[allow(dead_code)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+// Transformação decomposta em translação, rotação e escala (TRS)
+// A matriz equivalente aplica a escala, depois a rotação e por fim a translação
+pub struct Transform {
+    pub translation: glm::Vec3,
+    pub rotation: Quaternion,
+    pub scale: glm::Vec3,
+}
+
+#[allow(dead_code)]
+impl Transform {
+    pub fn new(translation: glm::Vec3, rotation: Quaternion, scale: glm::Vec3) -> Self {
+        Transform {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn identity() -> Self {
+        Transform::new(
+            glm::vec3(0.0, 0.0, 0.0),
+            Quaternion::identity(),
+            glm::vec3(1.0, 1.0, 1.0),
+        )
+    }
+
+    pub fn to_matrix(&self) -> GLMatrix {
+        GLMatrix::compose(self)
+    }
+}
+
 // Implementação ads funcões de transformação de matriz
 #[allow(dead_code)]
 impl GLMatrix {
@@ -343,6 +404,12 @@ impl GLMatrix {
         }
     }
 
+    pub fn rotate_quat(&self, rotation: &Quaternion) -> Self {
+        GLMatrix {
+            matrix: rotation.to_matrix().matrix * self.matrix,
+        }
+    }
+
     pub fn scale(&self, x: f32, y: f32, z: f32) -> Self {
         let scaling_matrix = scaling_matrix(x, y, z);
         GLMatrix {
@@ -353,6 +420,148 @@ impl GLMatrix {
     pub fn update(&mut self, matrix: &GLMatrix) {
         self.matrix = matrix.matrix;
     }
+
+    // Retorna os valores da matriz em row major
+    pub fn to_points(&self) -> [f32; 16] {
+        mat4_to_points(&self.matrix)
+    }
+
+    pub fn transpose(&self) -> Self {
+        let m = self.to_points();
+        GLMatrix::new([
+            m[0], m[4], m[8], m[12], // LINHA 1
+            m[1], m[5], m[9], m[13], // LINHA 2
+            m[2], m[6], m[10], m[14], // LINHA 3
+            m[3], m[7], m[11], m[15], // LINHA 4
+        ])
+    }
+
+    // Determinantes 2x2 das duas linhas superiores (s) e inferiores (c) da matriz,
+    // reutilizados no calculo do determinante e da inversa por cofatores
+    fn sub_determinants(m: &[f32; 16]) -> ([f32; 6], [f32; 6]) {
+        let s = [
+            m[0] * m[5] - m[4] * m[1],
+            m[0] * m[6] - m[4] * m[2],
+            m[0] * m[7] - m[4] * m[3],
+            m[1] * m[6] - m[5] * m[2],
+            m[1] * m[7] - m[5] * m[3],
+            m[2] * m[7] - m[6] * m[3],
+        ];
+        let c = [
+            m[8] * m[13] - m[12] * m[9],
+            m[8] * m[14] - m[12] * m[10],
+            m[8] * m[15] - m[12] * m[11],
+            m[9] * m[14] - m[13] * m[10],
+            m[9] * m[15] - m[13] * m[11],
+            m[10] * m[15] - m[14] * m[11],
+        ];
+        (s, c)
+    }
+
+    // Produto das normas das 4 colunas, limite superior do modulo do determinante
+    fn column_norms_product(&self) -> f32 {
+        let m = self.matrix;
+        [m.c0, m.c1, m.c2, m.c3]
+            .iter()
+            .map(|column| glm::length(*column))
+            .product()
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let (s, c) = GLMatrix::sub_determinants(&self.to_points());
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    // Inversa pela matriz adjunta. Retorna None se a matriz for singular (ex: escala 0)
+    pub fn inverse(&self) -> Option<Self> {
+        let m = self.to_points();
+        let (s, c) = GLMatrix::sub_determinants(&m);
+
+        let det = s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+        if abs(det) <= SINGULAR_EPSILON * self.column_norms_product() {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        Some(GLMatrix::new([
+            (m[5] * c[5] - m[6] * c[4] + m[7] * c[3]) * inv_det, // LINHA 1
+            (-m[1] * c[5] + m[2] * c[4] - m[3] * c[3]) * inv_det,
+            (m[13] * s[5] - m[14] * s[4] + m[15] * s[3]) * inv_det,
+            (-m[9] * s[5] + m[10] * s[4] - m[11] * s[3]) * inv_det,
+            (-m[4] * c[5] + m[6] * c[2] - m[7] * c[1]) * inv_det, // LINHA 2
+            (m[0] * c[5] - m[2] * c[2] + m[3] * c[1]) * inv_det,
+            (-m[12] * s[5] + m[14] * s[2] - m[15] * s[1]) * inv_det,
+            (m[8] * s[5] - m[10] * s[2] + m[11] * s[1]) * inv_det,
+            (m[4] * c[4] - m[5] * c[2] + m[7] * c[0]) * inv_det, // LINHA 3
+            (-m[0] * c[4] + m[1] * c[2] - m[3] * c[0]) * inv_det,
+            (m[12] * s[4] - m[13] * s[2] + m[15] * s[0]) * inv_det,
+            (-m[8] * s[4] + m[9] * s[2] - m[11] * s[0]) * inv_det,
+            (-m[4] * c[3] + m[5] * c[1] - m[6] * c[0]) * inv_det, // LINHA 4
+            (m[0] * c[3] - m[1] * c[1] + m[2] * c[0]) * inv_det,
+            (-m[12] * s[3] + m[13] * s[1] - m[14] * s[0]) * inv_det,
+            (m[8] * s[3] - m[9] * s[1] + m[10] * s[0]) * inv_det,
+        ]))
+    }
+
+    // Decompõe a matriz em translação, rotação e escala. Assume uma matriz afim sem
+    // cisalhamento. Reflexões (determinante negativo) são representadas com escala x negativa
+    pub fn decompose(&self) -> Transform {
+        let m = self.matrix;
+        let translation = glm::vec3(m.c3.x, m.c3.y, m.c3.z);
+
+        let mut scale = glm::vec3(
+            norm(glm::vec4(m.c0.x, m.c0.y, m.c0.z, 0.0)),
+            norm(glm::vec4(m.c1.x, m.c1.y, m.c1.z, 0.0)),
+            norm(glm::vec4(m.c2.x, m.c2.y, m.c2.z, 0.0)),
+        );
+        if self.determinant() < 0.0 {
+            scale.x = -scale.x;
+        }
+
+        // Remove a escala de cada coluna para obter a matriz de rotação pura
+        let mut rotation = m;
+        for (column, axis_scale) in [scale.x, scale.y, scale.z].iter().enumerate() {
+            if *axis_scale != 0.0 {
+                rotation[column] = rotation[column] / *axis_scale;
+            }
+        }
+        rotation.c3 = Vector4 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        };
+
+        Transform {
+            translation,
+            rotation: Quaternion::from_rotation_matrix(&GLMatrix { matrix: rotation }),
+            scale,
+        }
+    }
+
+    // Inverso de decompose
+    pub fn compose(transform: &Transform) -> Self {
+        let s = transform.scale;
+        let t = transform.translation;
+        scaling_matrix(s.x, s.y, s.z)
+            .rotate_quat(&transform.rotation)
+            .translate(t.x, t.y, t.z)
+    }
+
+    // Matriz para transformação de normais: inversa da transposta da matriz model.
+    // A translação é descartada, pois normais são vetores (w = 0)
+    pub fn normal_matrix(&self) -> Option<Self> {
+        let mut linear = self.matrix;
+        linear.c3 = Vector4 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        };
+        GLMatrix { matrix: linear }
+            .inverse()
+            .map(|inverse| inverse.transpose())
+    }
 }
 
 impl Clone for GLMatrix {
@@ -377,6 +586,16 @@ pub trait MatrixTransform: Sized {
     fn update_matrix(&mut self, matrix: &GLMatrix) -> &mut Self;
     fn from_matrix(&self, matrix: &GLMatrix) -> Self;
 
+    // Translação, rotação e escala atuais do obj
+    fn get_transform(&self) -> Transform {
+        self.get_matrix().decompose()
+    }
+
+    // Reconstroi a matriz do obj a partir de uma transformação TRS
+    fn with_transform(&self, transform: &Transform) -> Self {
+        self.from_matrix(&transform.to_matrix())
+    }
+
     fn translate(&self, x: f32, y: f32, z: f32) -> Self {
         self.from_matrix(&self.get_matrix().translate(x, y, z))
     }
@@ -397,6 +616,10 @@ pub trait MatrixTransform: Sized {
         self.from_matrix(&self.get_matrix().rotate(angle, axis))
     }
 
+    fn rotate_quat(&self, rotation: &Quaternion) -> Self {
+        self.from_matrix(&self.get_matrix().rotate_quat(rotation))
+    }
+
     fn scale(&self, x: f32, y: f32, z: f32) -> Self {
         self.from_matrix(&self.get_matrix().scale(x, y, z))
     }
@@ -454,4 +677,170 @@ pub trait MatrixTransform: Sized {
         new_self = new_self.translate(translation_vec.x, translation_vec.y, translation_vec.z);
         new_self
     }
+
+    // Translate para 0, rotaciona pelo quaternion e translate de volta
+    fn t_rot_quat(&self, rotation: &Quaternion) -> Self {
+        let translation_vec = self.get_matrix().matrix.c3;
+        let mut new_self =
+            self.translate(-translation_vec.x, -translation_vec.y, -translation_vec.z);
+
+        new_self = new_self.rotate_quat(rotation);
+        new_self = new_self.translate(translation_vec.x, translation_vec.y, translation_vec.z);
+        new_self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_matrix_near(a: &GLMatrix, b: &GLMatrix, tolerance: f32) {
+        for (x, y) in a.to_points().iter().zip(b.to_points().iter()) {
+            assert!(
+                abs(x - y) < tolerance,
+                "{:?} diferente de {:?}",
+                a.to_points(),
+                b.to_points()
+            );
+        }
+    }
+
+    // Escala não uniforme, rotação em um eixo inclinado e translação
+    fn model_matrix() -> GLMatrix {
+        identity_matrix()
+            .scale(2.0, 0.5, 4.0)
+            .rotate(0.7, glm::vec4(1.0, 1.0, 0.0, 0.0))
+            .translate(1.0, -2.0, 3.0)
+    }
+
+    #[test]
+    fn determinant_is_product_of_scales() {
+        assert!(abs(scaling_matrix(2.0, 0.5, 4.0).determinant() - 4.0) < 1e-5);
+        assert!(abs(model_matrix().determinant() - 4.0) < 1e-4);
+        assert!(abs(scaling_matrix(-1.0, 1.0, 1.0).determinant() + 1.0) < 1e-6);
+        assert_eq!(scaling_matrix(1.0, 0.0, 1.0).determinant(), 0.0);
+    }
+
+    #[test]
+    fn inverse_undoes_matrix() {
+        let model = model_matrix();
+        let inverse = model.inverse().unwrap();
+        let product = GLMatrix {
+            matrix: inverse.matrix * model.matrix,
+        };
+        assert_matrix_near(&product, &identity_matrix(), 1e-5);
+
+        let product = GLMatrix {
+            matrix: model.matrix * inverse.matrix,
+        };
+        assert_matrix_near(&product, &identity_matrix(), 1e-5);
+
+        assert!(scaling_matrix(1.0, 0.0, 1.0).inverse().is_none());
+        // Colunas quase paralelas
+        let flattened = GLMatrix::new([
+            1.0, 1.0, 0.0, 0.0, // LINHA 1
+            0.0, 1e-8, 0.0, 0.0, // LINHA 2
+            0.0, 0.0, 1.0, 0.0, // LINHA 3
+            0.0, 0.0, 0.0, 1.0,
+        ]);
+        assert!(flattened.inverse().is_none());
+    }
+
+    #[test]
+    fn inverse_of_small_uniform_scale() {
+        // Determinante 1e-9, mas a matriz é bem condicionada
+        let model = scaling_matrix(0.001, 0.001, 0.001).translate(5.0, 0.0, -2.0);
+        let inverse = model.inverse().unwrap();
+        let expected = translating_matrix(-5.0, 0.0, 2.0).scale(1000.0, 1000.0, 1000.0);
+        assert_matrix_near(&inverse, &expected, 1e-2);
+
+        let normal_matrix = model.normal_matrix().unwrap();
+        assert_matrix_near(
+            &normal_matrix,
+            &scaling_matrix(1000.0, 1000.0, 1000.0),
+            1e-2,
+        );
+    }
+
+    #[test]
+    fn normal_matrix_keeps_normals_perpendicular() {
+        let model = model_matrix();
+        let normal_matrix = model.normal_matrix().unwrap();
+
+        // Normal e tangente de uma superficie inclinada, perpendiculares no espaço local
+        let normal = glm::vec4(1.0, 1.0, 0.0, 0.0);
+        let tangent = glm::vec4(1.0, -1.0, 1.0, 0.0);
+        assert_eq!(dot_product(normal, tangent), 0.0);
+
+        let world_normal = normal_matrix.matrix * normal;
+        let world_tangent = model.matrix * tangent;
+        assert!(abs(dot_product(world_normal, world_tangent)) < 1e-5);
+        assert_eq!(world_normal.w, 0.0);
+
+        // Sem rotação, a matriz das normais é a escala inversa
+        let normal_matrix = scaling_matrix(2.0, 0.5, 4.0)
+            .translate(7.0, 0.0, 0.0)
+            .normal_matrix()
+            .unwrap();
+        assert_matrix_near(&normal_matrix, &scaling_matrix(0.5, 2.0, 0.25), 1e-6);
+    }
+
+    fn assert_vec3_near(a: glm::Vec3, b: glm::Vec3) {
+        assert!(glm::distance(a, b) < 1e-4, "{:?} diferente de {:?}", a, b);
+    }
+
+    // q e -q representam a mesma rotação
+    fn assert_same_rotation(a: &Quaternion, b: &Quaternion) {
+        assert!(
+            abs(abs(a.dot(b)) - 1.0) < 1e-4,
+            "{:?} diferente de {:?}",
+            a,
+            b
+        );
+    }
+
+    fn transform(scale: glm::Vec3) -> Transform {
+        Transform::new(
+            glm::vec3(1.0, -2.0, 3.0),
+            Quaternion::from_axis_angle(glm::vec4(1.0, 1.0, 0.0, 0.0), 0.7),
+            scale,
+        )
+    }
+
+    #[test]
+    fn decompose_undoes_compose() {
+        let expected = transform(glm::vec3(2.0, 0.5, 4.0));
+        let model = GLMatrix::compose(&expected);
+        let decomposed = model.decompose();
+        assert_vec3_near(decomposed.translation, expected.translation);
+        assert_vec3_near(decomposed.scale, expected.scale);
+        assert_same_rotation(&decomposed.rotation, &expected.rotation);
+        assert_matrix_near(&GLMatrix::compose(&decomposed), &model, 1e-5);
+
+        // Matriz montada pelas operações dos objs
+        let decomposed = model_matrix().decompose();
+        assert_vec3_near(decomposed.scale, glm::vec3(2.0, 0.5, 4.0));
+        assert_matrix_near(&GLMatrix::compose(&decomposed), &model_matrix(), 1e-5);
+    }
+
+    #[test]
+    fn decompose_mirrored_matrix() {
+        // Reflexão no eixo x: o sinal fica na escala x e a rotação continua sendo uma rotação
+        let expected = transform(glm::vec3(-2.0, 0.5, 4.0));
+        let model = GLMatrix::compose(&expected);
+        assert!(model.determinant() < 0.0);
+        let decomposed = model.decompose();
+        assert_vec3_near(decomposed.scale, expected.scale);
+        assert_same_rotation(&decomposed.rotation, &expected.rotation);
+        assert!(abs(decomposed.rotation.to_matrix().determinant() - 1.0) < 1e-4);
+
+        // Reflexão em outro eixo: a escala negativa passa para x, com a rotação compensando,
+        // e a matriz recomposta é a mesma
+        let model = GLMatrix::compose(&transform(glm::vec3(2.0, -0.5, 4.0)));
+        let decomposed = model.decompose();
+        assert!(decomposed.scale.x < 0.0 && decomposed.scale.y > 0.0 && decomposed.scale.z > 0.0);
+        assert_vec3_near(glm::abs(decomposed.scale), glm::vec3(2.0, 0.5, 4.0));
+        assert!(abs(decomposed.rotation.to_matrix().determinant() - 1.0) < 1e-4);
+        assert_matrix_near(&GLMatrix::compose(&decomposed), &model, 1e-5);
+    }
 }
@@ -0,0 +1,479 @@
+use models::material::MaterialData;
+use models::material::TextureSource;
+use models::mesh_data::MeshData;
+use models::mesh_data::MeshError;
+use models::mesh_data::MeshLod;
+use models::mesh_data::MeshRange;
+use models::normals::NormalMode;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+// Identificação do arquivo de cache
+static CACHE_MAGIC: &[u8; 4] = b"MSHC";
+// Deve ser incrementada sempre que o formato abaixo ou o processamento das malhas mudar
+static CACHE_VERSION: u32 = 3;
+// Diretorio criado ao lado de cada arquivo de origem
+static CACHE_DIR: &str = ".mesh_cache";
+
+// Carrega a malha do cache binario, se ele existir e corresponder ao arquivo de origem
+// Caso contrario carrega o arquivo com MeshData::load e reescreve o cache
+//
+// Formato do cache (little endian):
+// "MSHC", versão (u32), chave (u64), bbox min e max (6 f32),
+// positions, normals, texcoords, colors e tangents (u32 quantidade + f32), indices (u32 quantidade + u32),
+// ranges (u32 quantidade + nome, offset, quantidade, material), materiais e
+// niveis de detalhe (u32 quantidade + erro, indices e u32 offset e quantidade por range)
+#[allow(dead_code)]
+pub fn load_cached(path: &str, normal_mode: NormalMode) -> Result<MeshData, MeshError> {
+    let key = source_key(path, normal_mode);
+    let cache = cache_path(path, normal_mode);
+
+    if let (Some(key), Some(cache)) = (key, cache.as_ref()) {
+        if let Ok(bytes) = fs::read(cache) {
+            if let Some(mesh_data) = read_cache(&bytes, key) {
+                if mesh_data.validate().is_ok() {
+                    return Ok(mesh_data);
+                }
+            }
+        }
+    }
+
+    let mesh_data = MeshData::load(path, normal_mode)?;
+
+    // Falhas ao escrever o cache não impedem o carregamento
+    if let (Some(key), Some(cache)) = (key, cache) {
+        if let Err(error) = write_cache(&cache, key, &mesh_data) {
+            println!("Não foi possivel escrever o cache {:?}: {}", cache, error);
+        }
+    }
+    Ok(mesh_data)
+}
+
+// Caminho do cache: <diretorio do arquivo>/.mesh_cache/<nome do arquivo>.<modo de normais>.bin
+// Cada modo de normais tem o seu arquivo, para que carregar o mesmo arquivo em modos diferentes
+// não sobrescreva o cache do outro modo
+#[allow(dead_code)]
+pub fn cache_path(path: &str, normal_mode: NormalMode) -> Option<PathBuf> {
+    let source = Path::new(path);
+    let file_name = source.file_name()?;
+    let dir = source.parent().unwrap_or(Path::new("")).join(CACHE_DIR);
+
+    let mut name = file_name.to_os_string();
+    name.push(match normal_mode {
+        NormalMode::Imported => ".imported.bin".to_string(),
+        NormalMode::Flat => ".flat.bin".to_string(),
+        NormalMode::Smooth => ".smooth.bin".to_string(),
+        // Bits do angulo, para que angulos diferentes nunca compartilhem o arquivo
+        NormalMode::Crease(angle) => format!(".crease-{:08x}.bin", angle.to_bits()),
+    });
+    Some(dir.join(name))
+}
+
+// Hash FNV-1a de 64 bits. O hash padrão do Rust pode mudar entre versões do compilador,
+// o que invalidaria os caches
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Chave do cache: hash do arquivo de origem, dos .mtl referenciados por um obj,
+// do modo de normais e da versão do formato. None se o arquivo não puder ser lido
+fn source_key(path: &str, normal_mode: NormalMode) -> Option<u64> {
+    let source = fs::read(path).ok()?;
+
+    let mut hash = fnv1a(0xcbf29ce484222325, &CACHE_VERSION.to_le_bytes());
+    hash = match normal_mode {
+        NormalMode::Imported => fnv1a(hash, &[0]),
+        NormalMode::Flat => fnv1a(hash, &[1]),
+        NormalMode::Smooth => fnv1a(hash, &[2]),
+        NormalMode::Crease(angle) => fnv1a(fnv1a(hash, &[3]), &angle.to_bits().to_le_bytes()),
+    };
+    hash = fnv1a(hash, &source);
+
+    // Materiais fazem parte da malha carregada, alterações no .mtl também invalidam o cache
+    if path.to_lowercase().ends_with(".obj") {
+        let base_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        for line in String::from_utf8_lossy(&source).lines() {
+            let mut tokens = line.split_whitespace();
+            if tokens.next() == Some("mtllib") {
+                let mtl: Vec<&str> = tokens.collect();
+                let mtl = fs::read(base_dir.join(mtl.join(" "))).unwrap_or(Vec::new());
+                hash = fnv1a(hash, &mtl);
+            }
+        }
+    }
+    Some(hash)
+}
+
+fn write_cache(cache: &Path, key: u64, mesh_data: &MeshData) -> Result<(), String> {
+    let mut writer = CacheWriter { bytes: Vec::new() };
+    writer.bytes.extend_from_slice(CACHE_MAGIC);
+    writer.u32(CACHE_VERSION);
+    writer.u64(key);
+    writer.vec3(&mesh_data.bbox_min);
+    writer.vec3(&mesh_data.bbox_max);
+    writer.f32s(&mesh_data.positions);
+    writer.f32s(&mesh_data.normals);
+    writer.f32s(&mesh_data.texcoords);
+    writer.f32s(&mesh_data.colors);
+    writer.f32s(&mesh_data.tangents);
+
+    writer.u32(mesh_data.indices.len() as u32);
+    for index in mesh_data.indices.iter() {
+        writer.u32(*index);
+    }
+
+    writer.u32(mesh_data.ranges.len() as u32);
+    for range in mesh_data.ranges.iter() {
+        writer.string(&range.name);
+        writer.u32(range.index_offset as u32);
+        writer.u32(range.index_count as u32);
+        // u32::MAX para submesh sem material
+        writer.u32(
+            range
+                .material
+                .map_or(std::u32::MAX, |material| material as u32),
+        );
+    }
+
+    writer.u32(mesh_data.materials.len() as u32);
+    for material in mesh_data.materials.iter() {
+        writer.string(&material.name);
+        writer.vec3(&material.ambient);
+        writer.vec3(&material.diffuse);
+        writer.vec3(&material.specular);
+        writer.f32(material.shininess);
+        writer.texture(&material.diffuse_texture);
+        writer.texture(&material.bump_texture);
+    }
+
+    writer.u32(mesh_data.lods.len() as u32);
+    for lod in mesh_data.lods.iter() {
+        writer.f32(lod.error);
+        writer.u32(lod.indices.len() as u32);
+        for index in lod.indices.iter() {
+            writer.u32(*index);
+        }
+        for &(offset, count) in lod.ranges.iter() {
+            writer.u32(offset as u32);
+            writer.u32(count as u32);
+        }
+    }
+
+    let dir = cache.parent().unwrap_or(Path::new(""));
+    fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+
+    // Escreve em um arquivo temporario e renomeia, para que um cache incompleto nunca seja lido
+    let mut temporary = cache.as_os_str().to_os_string();
+    temporary.push(".tmp");
+    fs::write(&temporary, &writer.bytes).map_err(|error| error.to_string())?;
+    fs::rename(&temporary, cache).map_err(|error| error.to_string())
+}
+
+// Retorna None se o cache for de outra versão, de outro arquivo de origem ou estiver corrompido
+fn read_cache(bytes: &[u8], key: u64) -> Option<MeshData> {
+    let mut reader = CacheReader { bytes };
+    if reader.take(4)? != CACHE_MAGIC || reader.u32()? != CACHE_VERSION || reader.u64()? != key {
+        return None;
+    }
+
+    let mut mesh_data = MeshData::new();
+    mesh_data.bbox_min = reader.vec3()?;
+    mesh_data.bbox_max = reader.vec3()?;
+    mesh_data.positions = reader.f32s()?;
+    mesh_data.normals = reader.f32s()?;
+    mesh_data.texcoords = reader.f32s()?;
+    mesh_data.colors = reader.f32s()?;
+    mesh_data.tangents = reader.f32s()?;
+
+    mesh_data.indices = reader.u32s()?;
+
+    for _ in 0..reader.u32()? {
+        let name = reader.string()?;
+        let index_offset = reader.u32()? as usize;
+        let index_count = reader.u32()? as usize;
+        let material = reader.u32()?;
+        mesh_data.ranges.push(MeshRange {
+            name,
+            index_offset,
+            index_count,
+            material: if material == std::u32::MAX {
+                None
+            } else {
+                Some(material as usize)
+            },
+        });
+    }
+
+    for _ in 0..reader.u32()? {
+        mesh_data.materials.push(MaterialData {
+            name: reader.string()?,
+            ambient: reader.vec3()?,
+            diffuse: reader.vec3()?,
+            specular: reader.vec3()?,
+            shininess: reader.f32()?,
+            diffuse_texture: reader.texture()?,
+            bump_texture: reader.texture()?,
+        });
+    }
+
+    for _ in 0..reader.u32()? {
+        let error = reader.f32()?;
+        let indices = reader.u32s()?;
+        let mut ranges = Vec::new();
+        for _ in 0..mesh_data.ranges.len() {
+            ranges.push((reader.u32()? as usize, reader.u32()? as usize));
+        }
+        mesh_data.lods.push(MeshLod {
+            indices,
+            ranges,
+            error,
+        });
+    }
+
+    if reader.bytes.len() > 0 {
+        return None;
+    }
+    Some(mesh_data)
+}
+
+struct CacheWriter {
+    bytes: Vec<u8>,
+}
+
+impl CacheWriter {
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.u32(value.to_bits());
+    }
+
+    fn vec3(&mut self, value: &glm::Vec3) {
+        self.f32(value.x);
+        self.f32(value.y);
+        self.f32(value.z);
+    }
+
+    fn f32s(&mut self, values: &[f32]) {
+        self.u32(values.len() as u32);
+        for value in values.iter() {
+            self.f32(*value);
+        }
+    }
+
+    fn data(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.data(value.as_bytes());
+    }
+
+    // 0 -> Sem textura; 1 -> Caminho; 2 -> Imagem embutida
+    fn texture(&mut self, texture: &Option<TextureSource>) {
+        match *texture {
+            None => self.bytes.push(0),
+            Some(TextureSource::File(ref path)) => {
+                self.bytes.push(1);
+                self.string(&path.to_string_lossy());
+            }
+            Some(TextureSource::Encoded(ref bytes)) => {
+                self.bytes.push(2);
+                self.data(bytes);
+            }
+        }
+    }
+}
+
+struct CacheReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> CacheReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < len {
+            return None;
+        }
+        let (value, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Some(value)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let b = self.take(4)?;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        let b = self.take(8)?;
+        Some(u64::from_le_bytes([
+            b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7],
+        ]))
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.u32().map(f32::from_bits)
+    }
+
+    fn vec3(&mut self) -> Option<glm::Vec3> {
+        Some(glm::vec3(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    // Lista de u32 precedida pela quantidade, lida de uma vez
+    fn u32s(&mut self) -> Option<Vec<u32>> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len.checked_mul(4)?)?;
+        Some(
+            bytes
+                .chunks(4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        )
+    }
+
+    fn f32s(&mut self) -> Option<Vec<f32>> {
+        Some(self.u32s()?.into_iter().map(f32::from_bits).collect())
+    }
+
+    fn data(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        String::from_utf8(self.data()?.to_vec()).ok()
+    }
+
+    fn texture(&mut self) -> Option<Option<TextureSource>> {
+        match self.take(1)?[0] {
+            0 => Some(None),
+            1 => Some(Some(TextureSource::File(PathBuf::from(self.string()?)))),
+            2 => Some(Some(TextureSource::Encoded(self.data()?.to_vec()))),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    // Diretorio temporario exclusivo do teste, com uma copia do cubo
+    fn temporary_cube(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mesh_cache_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let cube = dir.join("cube.obj");
+        fs::copy("src/data/objs/cube.obj", &cube).unwrap();
+        cube
+    }
+
+    fn assert_same_mesh(a: &MeshData, b: &MeshData) {
+        assert_eq!(a.positions, b.positions);
+        assert_eq!(a.normals, b.normals);
+        assert_eq!(a.texcoords, b.texcoords);
+        assert_eq!(a.colors, b.colors);
+        assert_eq!(a.tangents, b.tangents);
+        assert_eq!(a.indices, b.indices);
+        assert_eq!(a.bbox_min, b.bbox_min);
+        assert_eq!(a.bbox_max, b.bbox_max);
+        assert_eq!(a.ranges.len(), b.ranges.len());
+        for (range_a, range_b) in a.ranges.iter().zip(b.ranges.iter()) {
+            assert_eq!(range_a.name, range_b.name);
+            assert_eq!(range_a.index_offset, range_b.index_offset);
+            assert_eq!(range_a.index_count, range_b.index_count);
+            assert_eq!(range_a.material, range_b.material);
+        }
+        assert_eq!(a.materials.len(), b.materials.len());
+        assert_eq!(a.lods.len(), b.lods.len());
+        for (lod_a, lod_b) in a.lods.iter().zip(b.lods.iter()) {
+            assert_eq!(lod_a.indices, lod_b.indices);
+            assert_eq!(lod_a.ranges, lod_b.ranges);
+            assert_eq!(lod_a.error, lod_b.error);
+        }
+    }
+
+    #[test]
+    fn write_read_round_trip() {
+        let cube = temporary_cube("round_trip");
+        let path = cube.to_str().unwrap();
+        let mesh_data = MeshData::load(path, NormalMode::Flat).unwrap();
+
+        let key = source_key(path, NormalMode::Flat).unwrap();
+        let cache = cache_path(path, NormalMode::Flat).unwrap();
+        write_cache(&cache, key, &mesh_data).unwrap();
+
+        let cached = read_cache(&fs::read(&cache).unwrap(), key).unwrap();
+        assert_same_mesh(&cached, &mesh_data);
+        assert!(cached.validate().is_ok());
+
+        // Cache truncado é descartado
+        let bytes = fs::read(&cache).unwrap();
+        assert!(read_cache(&bytes[..bytes.len() - 1], key).is_none());
+
+        let _ = fs::remove_dir_all(cube.parent().unwrap());
+    }
+
+    #[test]
+    fn stale_key_is_reloaded() {
+        let cube = temporary_cube("stale_key");
+        let path = cube.to_str().unwrap();
+
+        let original = load_cached(path, NormalMode::Imported).unwrap();
+        let cache = cache_path(path, NormalMode::Imported).unwrap();
+        let key = source_key(path, NormalMode::Imported).unwrap();
+        assert!(read_cache(&fs::read(&cache).unwrap(), key).is_some());
+
+        // Mudança no arquivo de origem ou no modo de normais muda a chave
+        let mut source = fs::read_to_string(&cube).unwrap();
+        source = source.replacen("v  1.0  1.0  1.0", "v  2.0  1.0  1.0", 1);
+        fs::write(&cube, source).unwrap();
+        let new_key = source_key(path, NormalMode::Imported).unwrap();
+        assert_ne!(key, new_key);
+        assert_ne!(key, source_key(path, NormalMode::Smooth).unwrap());
+        assert!(read_cache(&fs::read(&cache).unwrap(), new_key).is_none());
+
+        let reloaded = load_cached(path, NormalMode::Imported).unwrap();
+        assert_eq!(original.bbox_max.x, 1.0);
+        assert_eq!(reloaded.bbox_max.x, 2.0);
+        assert!(read_cache(&fs::read(&cache).unwrap(), new_key).is_some());
+
+        let _ = fs::remove_dir_all(cube.parent().unwrap());
+    }
+
+    #[test]
+    fn normal_modes_use_separate_files() {
+        let cube = temporary_cube("normal_modes");
+        let path = cube.to_str().unwrap();
+
+        let imported = load_cached(path, NormalMode::Imported).unwrap();
+        let flat = load_cached(path, NormalMode::Flat).unwrap();
+        let imported_cache = cache_path(path, NormalMode::Imported).unwrap();
+        let flat_cache = cache_path(path, NormalMode::Flat).unwrap();
+        assert_ne!(imported_cache, flat_cache);
+        assert_ne!(
+            cache_path(path, NormalMode::Crease(0.5)),
+            cache_path(path, NormalMode::Crease(0.6))
+        );
+
+        // Os dois caches continuam validos depois de carregar o outro modo
+        let imported_key = source_key(path, NormalMode::Imported).unwrap();
+        let flat_key = source_key(path, NormalMode::Flat).unwrap();
+        let cached = read_cache(&fs::read(&imported_cache).unwrap(), imported_key).unwrap();
+        assert_same_mesh(&cached, &imported);
+        let cached = read_cache(&fs::read(&flat_cache).unwrap(), flat_key).unwrap();
+        assert_same_mesh(&cached, &flat);
+
+        let _ = fs::remove_dir_all(cube.parent().unwrap());
+    }
+}
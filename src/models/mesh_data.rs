@@ -0,0 +1,660 @@
+use models::material::MaterialData;
+use models::matrix::compute_normal;
+use models::normals::NormalMesh;
+use models::normals::NormalMode;
+use models::ply_loader::load_ply;
+use models::ray::ray_triangle_intersection;
+use models::ray::Ray;
+use models::simplify::generate_lods;
+use models::stl_loader::load_stl;
+use models::tangents::generate_tangents;
+use std::fmt;
+use std::path::Path;
+use tobj;
+
+#[allow(dead_code)]
+#[derive(Debug)]
+// Erros de carregamento e validação de uma malha
+pub enum MeshError {
+    // Falha ao ler ou interpretar o arquivo
+    Load {
+        path: String,
+        message: String,
+    },
+    // Malha sem triangulos
+    Empty,
+    // Quantidade de valores de um atributo não corresponde ao numero de vertices
+    AttributeLength {
+        attribute: &'static str,
+        len: usize,
+        expected: usize,
+    },
+    // Numero de indices não é multiplo de 3
+    IncompleteTriangle(usize),
+    // Indice referencia um vertice inexistente
+    IndexOutOfRange {
+        index: u32,
+        vertex_count: usize,
+    },
+    // Posição com NaN ou infinito
+    NonFinitePosition(usize),
+    // Submesh fora do buffer de indices ou com material inexistente
+    InvalidRange(String),
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MeshError::Load {
+                ref path,
+                ref message,
+            } => write!(f, "Falha ao carregar malha {}: {}", path, message),
+            MeshError::Empty => write!(f, "Malha sem triangulos"),
+            MeshError::AttributeLength {
+                attribute,
+                len,
+                expected,
+            } => write!(
+                f,
+                "Atributo {} com {} valores, esperado {}",
+                attribute, len, expected
+            ),
+            MeshError::IncompleteTriangle(len) => {
+                write!(f, "{} indices não formam triangulos completos", len)
+            }
+            MeshError::IndexOutOfRange {
+                index,
+                vertex_count,
+            } => write!(
+                f,
+                "Indice {} fora da malha com {} vertices",
+                index, vertex_count
+            ),
+            MeshError::NonFinitePosition(vertex) => {
+                write!(f, "Posição do vertice {} não é finita", vertex)
+            }
+            MeshError::InvalidRange(ref name) => write!(f, "Submesh {} invalido", name),
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Intervalo do buffer de indices que forma um submesh
+// material -> Indice em MeshData::materials
+pub struct MeshRange {
+    pub name: String,
+    pub index_offset: usize,
+    pub index_count: usize,
+    pub material: Option<usize>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Nivel de detalhe simplificado da malha, com os mesmos vertices e menos triangulos
+// indices -> Indices do nivel, submesh a submesh
+// ranges -> Offset e quantidade de indices de cada submesh, na ordem de MeshData::ranges
+// error -> Erro geometrico aproximado (distancia no espaço local) em relação a malha completa
+pub struct MeshLod {
+    pub indices: Vec<u32>,
+    pub ranges: Vec<(usize, usize)>,
+    pub error: f32,
+}
+
+#[allow(dead_code)]
+impl MeshLod {
+    // Nivel com todos os triangulos da malha
+    pub fn from_mesh(mesh: &MeshData) -> Self {
+        MeshLod {
+            indices: mesh.indices.clone(),
+            ranges: mesh
+                .ranges
+                .iter()
+                .map(|range| (range.index_offset, range.index_count))
+                .collect(),
+            error: 0.0,
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Clone)]
+// Malha na memoria da CPU, pronta para ser enviada para a GPU
+// Não depende de contexto OpenGL, podendo ser carregada e validada isoladamente
+// positions -> X Y Z W (w = 1) de cada vertice
+// normals -> X Y Z W (w = 0) de cada vertice
+// texcoords -> U V de cada vertice, ou vazio se a malha não tiver textura
+// colors -> R G B A de cada vertice, em [0..1], ou vazio se a malha não tiver cores
+// tangents -> X Y Z e sinal da bitangente (W) de cada vertice, ou vazio se a malha não tiver
+//             textura. Ver tangents::generate_tangents
+// joints -> Indices das 4 juntas do esqueleto que deformam cada vertice, ou vazio se a malha não
+//           tiver esqueleto. Ver skeleton::Skeleton
+// weights -> Pesos das 4 juntas de cada vertice, somando 1. Vertices com pesos zerados não são
+//            deformados
+// indices -> 3 indices por triangulo
+// bbox_min/max -> Bounding box local da malha
+// lods -> Niveis de detalhe simplificados, do mais detalhado ao menos. Ver simplify::generate_lods
+pub struct MeshData {
+    pub positions: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub texcoords: Vec<f32>,
+    pub colors: Vec<f32>,
+    pub tangents: Vec<f32>,
+    pub joints: Vec<u16>,
+    pub weights: Vec<f32>,
+    pub indices: Vec<u32>,
+    pub bbox_min: glm::Vec3,
+    pub bbox_max: glm::Vec3,
+    pub ranges: Vec<MeshRange>,
+    pub materials: Vec<MaterialData>,
+    pub lods: Vec<MeshLod>,
+}
+
+// Não imprime os vertices, apenas o tamanho da malha
+impl fmt::Debug for MeshData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "MeshData {{ vertices: {}, triangles: {}, submeshes: {}, lods: {} }}",
+            self.vertex_count(),
+            self.triangle_count(),
+            self.ranges.len(),
+            self.lods.len()
+        )
+    }
+}
+
+#[allow(dead_code)]
+impl MeshData {
+    // Malha vazia, com bbox invertida para ser expandida pelos vertices adicionados
+    pub fn new() -> Self {
+        MeshData {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            texcoords: Vec::new(),
+            colors: Vec::new(),
+            tangents: Vec::new(),
+            joints: Vec::new(),
+            weights: Vec::new(),
+            indices: Vec::new(),
+            bbox_min: glm::vec3(std::f32::MAX, std::f32::MAX, std::f32::MAX),
+            bbox_max: glm::vec3(std::f32::MIN, std::f32::MIN, std::f32::MIN),
+            ranges: Vec::new(),
+            materials: Vec::new(),
+            lods: Vec::new(),
+        }
+    }
+
+    // Carrega um arquivo de malha, escolhendo o formato pela extensão (.obj, .ply ou .stl)
+    // As tangentes e os niveis de detalhe são calculados após o carregamento, com as normais
+    // já definidas
+    pub fn load(path: &str, normal_mode: NormalMode) -> Result<Self, MeshError> {
+        let extension = Path::new(path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or(String::new());
+
+        let mut mesh_data = match extension.as_str() {
+            "ply" => load_ply(path, normal_mode),
+            "stl" => load_stl(path, normal_mode),
+            _ => MeshData::load_obj(path, normal_mode),
+        }?;
+        generate_tangents(&mut mesh_data);
+        generate_lods(&mut mesh_data);
+        Ok(mesh_data)
+    }
+
+    // Carrega um arquivo obj e os materiais do .mtl referenciado
+    // Cada modelo do arquivo vira um submesh, com normais calculadas segundo normal_mode
+    pub fn load_obj(path: &str, normal_mode: NormalMode) -> Result<Self, MeshError> {
+        let (models, obj_materials) =
+            tobj::load_obj(Path::new(path)).map_err(|error| MeshError::Load {
+                path: path.to_string(),
+                message: format!("{:?}", error),
+            })?;
+
+        let base_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+
+        let mut mesh_data = MeshData::new();
+        mesh_data.materials = obj_materials
+            .iter()
+            .map(|material| MaterialData::from_tobj(material, base_dir))
+            .collect();
+
+        for model in models.iter() {
+            let mesh = NormalMesh {
+                positions: model.mesh.positions.clone(),
+                normals: model.mesh.normals.clone(),
+                texcoords: model.mesh.texcoords.clone(),
+                colors: Vec::new(),
+                joints: Vec::new(),
+                weights: Vec::new(),
+                indices: model.mesh.indices.clone(),
+            }
+            .with_normal_mode(normal_mode);
+
+            mesh_data.push_mesh(&model.name, &mesh, model.mesh.material_id);
+        }
+
+        mesh_data.validate()?;
+        Ok(mesh_data)
+    }
+
+    // Adiciona um modelo como novo submesh
+    // Os indices do modelo são relativos a ele e são deslocados pelo numero de vertices
+    // já existentes no buffer compartilhado
+    // Tangentes e niveis de detalhe existentes são descartados: eles são calculados sobre a
+    // malha completa
+    pub fn push_mesh(&mut self, name: &str, mesh: &NormalMesh, material: Option<usize>) {
+        self.tangents.clear();
+        self.lods.clear();
+        let vertex_offset = self.vertex_count();
+        let mesh_vertex_count = mesh.positions.len() / 3;
+
+        self.ranges.push(MeshRange {
+            name: name.to_string(),
+            index_offset: self.indices.len(),
+            index_count: mesh.indices.len(),
+            material,
+        });
+
+        for index in mesh.indices.iter() {
+            self.indices.push(index + vertex_offset as u32);
+        }
+
+        for v in 0..mesh_vertex_count {
+            let x = mesh.positions[3 * v];
+            let y = mesh.positions[3 * v + 1];
+            let z = mesh.positions[3 * v + 2];
+            self.positions.extend_from_slice(&[x, y, z, 1.0]);
+
+            self.bbox_min.x = glm::min(self.bbox_min.x, x);
+            self.bbox_min.y = glm::min(self.bbox_min.y, y);
+            self.bbox_min.z = glm::min(self.bbox_min.z, z);
+            self.bbox_max.x = glm::max(self.bbox_max.x, x);
+            self.bbox_max.y = glm::max(self.bbox_max.y, y);
+            self.bbox_max.z = glm::max(self.bbox_max.z, z);
+
+            self.normals
+                .extend_from_slice(&mesh.normals[3 * v..3 * v + 3]);
+            self.normals.push(0.0);
+        }
+
+        // Se algum modelo possui coordenadas de textura, os que não possuem são preenchidos
+        // com zeros para manter os atributos alinhados com as posições
+        if mesh.texcoords.len() > 0 {
+            self.texcoords.resize(2 * vertex_offset, 0.0);
+            self.texcoords.extend_from_slice(&mesh.texcoords);
+        } else if self.texcoords.len() > 0 {
+            self.texcoords.resize(2 * self.vertex_count(), 0.0);
+        }
+
+        // Mesmo para cores, com branco nos modelos sem cor
+        if mesh.colors.len() > 0 {
+            self.colors.resize(4 * vertex_offset, 1.0);
+            self.colors.extend_from_slice(&mesh.colors);
+        } else if self.colors.len() > 0 {
+            self.colors.resize(4 * self.vertex_count(), 1.0);
+        }
+
+        // Mesmo para juntas e pesos, com pesos zerados nos modelos sem esqueleto
+        if mesh.weights.len() > 0 {
+            self.joints.resize(4 * vertex_offset, 0);
+            self.weights.resize(4 * vertex_offset, 0.0);
+            self.joints.extend_from_slice(&mesh.joints);
+            self.weights.extend_from_slice(&mesh.weights);
+        } else if self.weights.len() > 0 {
+            self.joints.resize(4 * self.vertex_count(), 0);
+            self.weights.resize(4 * self.vertex_count(), 0.0);
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len() / 4
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn position(&self, vertex: usize) -> glm::Vec4 {
+        let p = &self.positions[4 * vertex..4 * vertex + 4];
+        glm::vec4(p[0], p[1], p[2], p[3])
+    }
+
+    pub fn normal(&self, vertex: usize) -> glm::Vec4 {
+        let n = &self.normals[4 * vertex..4 * vertex + 4];
+        glm::vec4(n[0], n[1], n[2], n[3])
+    }
+
+    // Tangente (w = 0) e bitangente do vertice, se a malha tiver tangentes
+    pub fn tangent_frame(&self, vertex: usize) -> Option<(glm::Vec4, glm::Vec4)> {
+        if self.tangents.len() == 0 {
+            return None;
+        }
+        let t = &self.tangents[4 * vertex..4 * vertex + 4];
+        let tangent = glm::vec3(t[0], t[1], t[2]);
+        let n = self.normal(vertex);
+        let bitangent = glm::cross(glm::vec3(n.x, n.y, n.z), tangent) * t[3];
+        Some((
+            glm::vec4(tangent.x, tangent.y, tangent.z, 0.0),
+            glm::vec4(bitangent.x, bitangent.y, bitangent.z, 0.0),
+        ))
+    }
+
+    // Malha deformada por um esqueleto
+    pub fn is_skinned(&self) -> bool {
+        self.weights.len() > 0
+    }
+
+    // Identificação da malha para mensagens: nomes dos submeshes e tamanho, sem os vertices
+    pub fn describe(&self) -> String {
+        let names: Vec<&str> = self
+            .ranges
+            .iter()
+            .map(|range| range.name.as_str())
+            .filter(|name| !name.is_empty())
+            .collect();
+        let name = if names.is_empty() {
+            "sem nome".to_string()
+        } else {
+            names.join(", ")
+        };
+        format!(
+            "'{}' ({} vertices, {} triangulos)",
+            name,
+            self.vertex_count(),
+            self.triangle_count()
+        )
+    }
+
+    pub fn range(&self, name: &str) -> Option<&MeshRange> {
+        self.ranges.iter().find(|range| range.name == name)
+    }
+
+    // Verifica se os atributos e indices formam uma malha que pode ser desenhada
+    pub fn validate(&self) -> Result<(), MeshError> {
+        let vertex_count = self.vertex_count();
+
+        if self.indices.len() == 0 || vertex_count == 0 {
+            return Err(MeshError::Empty);
+        }
+        if self.positions.len() % 4 != 0 {
+            return Err(MeshError::AttributeLength {
+                attribute: "positions",
+                len: self.positions.len(),
+                expected: 4 * vertex_count,
+            });
+        }
+        if self.normals.len() != 4 * vertex_count {
+            return Err(MeshError::AttributeLength {
+                attribute: "normals",
+                len: self.normals.len(),
+                expected: 4 * vertex_count,
+            });
+        }
+        if self.texcoords.len() > 0 && self.texcoords.len() != 2 * vertex_count {
+            return Err(MeshError::AttributeLength {
+                attribute: "texcoords",
+                len: self.texcoords.len(),
+                expected: 2 * vertex_count,
+            });
+        }
+        if self.colors.len() > 0 && self.colors.len() != 4 * vertex_count {
+            return Err(MeshError::AttributeLength {
+                attribute: "colors",
+                len: self.colors.len(),
+                expected: 4 * vertex_count,
+            });
+        }
+        if self.tangents.len() > 0 && self.tangents.len() != 4 * vertex_count {
+            return Err(MeshError::AttributeLength {
+                attribute: "tangents",
+                len: self.tangents.len(),
+                expected: 4 * vertex_count,
+            });
+        }
+        if self.weights.len() > 0 && self.weights.len() != 4 * vertex_count {
+            return Err(MeshError::AttributeLength {
+                attribute: "weights",
+                len: self.weights.len(),
+                expected: 4 * vertex_count,
+            });
+        }
+        if self.joints.len() != self.weights.len() {
+            return Err(MeshError::AttributeLength {
+                attribute: "joints",
+                len: self.joints.len(),
+                expected: self.weights.len(),
+            });
+        }
+        if self.indices.len() % 3 != 0 {
+            return Err(MeshError::IncompleteTriangle(self.indices.len()));
+        }
+        if let Some(index) = self
+            .indices
+            .iter()
+            .find(|index| **index as usize >= vertex_count)
+        {
+            return Err(MeshError::IndexOutOfRange {
+                index: *index,
+                vertex_count,
+            });
+        }
+        if let Some(vertex) = self.positions.iter().position(|value| !value.is_finite()) {
+            return Err(MeshError::NonFinitePosition(vertex / 4));
+        }
+        for lod in self.lods.iter() {
+            let valid_ranges = lod.ranges.len() == self.ranges.len()
+                && lod
+                    .ranges
+                    .iter()
+                    .all(|&(offset, count)| offset + count <= lod.indices.len() && count % 3 == 0);
+            if !valid_ranges
+                || lod
+                    .indices
+                    .iter()
+                    .any(|index| *index as usize >= vertex_count)
+            {
+                return Err(MeshError::InvalidRange(format!("lod {}", lod.error)));
+            }
+        }
+        for range in self.ranges.iter() {
+            let in_buffer = range.index_offset + range.index_count <= self.indices.len();
+            let valid_material = range
+                .material
+                .map_or(true, |material| material < self.materials.len());
+            if !in_buffer || !valid_material {
+                return Err(MeshError::InvalidRange(range.name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    // Raycast contra os triangulos da malha, com o raio no espaço local
+    // Retorna o parametro t e a normal da face do triangulo mais proximo
+    pub fn raycast(&self, ray: &Ray) -> Option<(f32, glm::Vec4)> {
+        let mut closest: Option<(f32, glm::Vec4)> = None;
+
+        for triangle in self.indices.chunks(3) {
+            if triangle.len() < 3 {
+                break;
+            }
+            let p0 = self.position(triangle[0] as usize);
+            let p1 = self.position(triangle[1] as usize);
+            let p2 = self.position(triangle[2] as usize);
+
+            if let Some(t) = ray_triangle_intersection(ray, &p0, &p1, &p2) {
+                if closest.map_or(true, |(closest_t, _)| t < closest_t) {
+                    closest = Some((t, compute_normal(&p0, &p1, &p2)));
+                }
+            }
+        }
+        closest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Triangulo no plano z = 0, com coordenadas de textura opcionais
+    fn triangle(texcoords: bool) -> NormalMesh {
+        NormalMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            texcoords: if texcoords {
+                vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0]
+            } else {
+                Vec::new()
+            },
+            colors: Vec::new(),
+            joints: Vec::new(),
+            weights: Vec::new(),
+            indices: vec![0, 1, 2],
+        }
+    }
+
+    fn triangle_mesh() -> MeshData {
+        let mut mesh_data = MeshData::new();
+        mesh_data.push_mesh("triangulo", &triangle(true), None);
+        mesh_data
+    }
+
+    fn load_cube() -> MeshData {
+        MeshData::load_obj("src/data/objs/cube.obj", NormalMode::Imported).unwrap()
+    }
+
+    #[test]
+    fn load_obj_cube_bounds() {
+        let cube = load_cube();
+        assert_eq!(cube.bbox_min, glm::vec3(0.0, 0.0, 0.0));
+        assert_eq!(cube.bbox_max, glm::vec3(1.0, 1.0, 1.0));
+        assert_eq!(cube.triangle_count(), 12);
+        assert_eq!(cube.ranges.len(), 1);
+        assert_eq!(cube.ranges[0].index_offset, 0);
+        assert_eq!(cube.ranges[0].index_count, cube.indices.len());
+        assert!(cube.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_index_out_of_range() {
+        let mut mesh_data = triangle_mesh();
+        mesh_data.indices.extend_from_slice(&[0, 1, 3]);
+        match mesh_data.validate() {
+            Err(MeshError::IndexOutOfRange {
+                index,
+                vertex_count,
+            }) => {
+                assert_eq!(index, 3);
+                assert_eq!(vertex_count, 3);
+            }
+            result => panic!("resultado inesperado {:?}", result),
+        }
+    }
+
+    #[test]
+    fn validate_attribute_length() {
+        let mut mesh_data = triangle_mesh();
+        mesh_data.normals.truncate(8);
+        match mesh_data.validate() {
+            Err(MeshError::AttributeLength {
+                attribute,
+                len,
+                expected,
+            }) => {
+                assert_eq!(attribute, "normals");
+                assert_eq!(len, 8);
+                assert_eq!(expected, 12);
+            }
+            result => panic!("resultado inesperado {:?}", result),
+        }
+
+        let mut mesh_data = triangle_mesh();
+        mesh_data.texcoords.push(0.0);
+        match mesh_data.validate() {
+            Err(MeshError::AttributeLength { attribute, .. }) => {
+                assert_eq!(attribute, "texcoords")
+            }
+            result => panic!("resultado inesperado {:?}", result),
+        }
+    }
+
+    #[test]
+    fn validate_incomplete_triangle() {
+        let mut mesh_data = triangle_mesh();
+        mesh_data.indices.push(0);
+        match mesh_data.validate() {
+            Err(MeshError::IncompleteTriangle(len)) => assert_eq!(len, 4),
+            result => panic!("resultado inesperado {:?}", result),
+        }
+    }
+
+    #[test]
+    fn validate_empty_and_non_finite() {
+        match MeshData::new().validate() {
+            Err(MeshError::Empty) => {}
+            result => panic!("resultado inesperado {:?}", result),
+        }
+
+        let mut mesh_data = triangle_mesh();
+        mesh_data.positions[4] = std::f32::NAN;
+        match mesh_data.validate() {
+            Err(MeshError::NonFinitePosition(vertex)) => assert_eq!(vertex, 1),
+            result => panic!("resultado inesperado {:?}", result),
+        }
+    }
+
+    #[test]
+    fn validate_invalid_range() {
+        let mut mesh_data = MeshData::new();
+        mesh_data.push_mesh("sem material", &triangle(false), Some(0));
+        match mesh_data.validate() {
+            Err(MeshError::InvalidRange(name)) => assert_eq!(name, "sem material"),
+            result => panic!("resultado inesperado {:?}", result),
+        }
+    }
+
+    #[test]
+    fn push_mesh_offsets() {
+        let mut mesh_data = MeshData::new();
+        mesh_data.push_mesh("primeiro", &triangle(false), None);
+        mesh_data.push_mesh("segundo", &triangle(true), None);
+
+        assert_eq!(mesh_data.vertex_count(), 6);
+        assert_eq!(mesh_data.indices, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(mesh_data.ranges[1].name, "segundo");
+        assert_eq!(mesh_data.ranges[1].index_offset, 3);
+        assert_eq!(mesh_data.ranges[1].index_count, 3);
+
+        // O primeiro triangulo, sem texturas, é preenchido com zeros
+        assert_eq!(mesh_data.texcoords.len(), 12);
+        assert_eq!(&mesh_data.texcoords[..6], &[0.0; 6]);
+        assert_eq!(&mesh_data.texcoords[6..], &[0.0, 0.0, 1.0, 0.0, 0.0, 1.0]);
+        assert!(mesh_data.validate().is_ok());
+    }
+
+    #[test]
+    fn raycast_cube() {
+        let cube = load_cube();
+
+        let ray = Ray::new(
+            glm::vec4(0.5, 0.5, -1.0, 1.0),
+            glm::vec4(0.0, 0.0, 1.0, 0.0),
+        );
+        let (t, normal) = cube.raycast(&ray).unwrap();
+        assert!((t - 1.0).abs() < 1e-5);
+        assert!(normal.z < 0.0);
+        assert!(normal.x.abs() < 1e-5 && normal.y.abs() < 1e-5);
+
+        let ray = Ray::new(
+            glm::vec4(2.0, 2.0, -1.0, 1.0),
+            glm::vec4(0.0, 0.0, 1.0, 0.0),
+        );
+        assert!(cube.raycast(&ray).is_none());
+    }
+}
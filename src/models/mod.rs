@@ -1,7 +1,30 @@
+pub mod animation;
+pub mod asset_loader;
+pub mod block_compression;
 pub mod complex_obj;
 pub mod composite_obj;
 pub mod draw;
+pub mod gltf_loader;
+pub mod instanced;
+pub mod load_cubemap;
 pub mod load_texture;
+pub mod material;
 pub mod matrix;
+pub mod mesh_cache;
+pub mod mesh_data;
+pub mod normals;
 pub mod obj_model;
+pub mod ply_loader;
+pub mod primitives;
+pub mod quaternion;
+pub mod ray;
+pub mod resources;
+pub mod rig_loader;
+pub mod sampler;
 pub mod scene_object;
+pub mod simplify;
+pub mod skeleton;
+pub mod stl_loader;
+pub mod tangents;
+pub mod texture_container;
+pub mod texture_units;
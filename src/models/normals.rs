@@ -0,0 +1,411 @@
+use models::matrix::compute_normal;
+use models::matrix::norm;
+use models::matrix::normalize_vector;
+use std::collections::HashMap;
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+// Modo de calculo das normais dos vertices no carregamento de um obj
+// Imported -> Usa as normais do arquivo, calculando normais suaves se o arquivo não tiver
+// Flat -> Normal da face em cada vertice, vertices compartilhados são duplicados
+// Smooth -> Media das normais das faces que compartilham a posição do vertice
+// Crease -> Media apenas das faces com angulo (em radianos) menor que o informado em relação a
+//           face do vertice. Arestas com angulo maior ficam vincadas
+pub enum NormalMode {
+    Imported,
+    Flat,
+    Smooth,
+    Crease(f32),
+}
+
+impl Default for NormalMode {
+    fn default() -> Self {
+        NormalMode::Imported
+    }
+}
+
+// Vertices e indices de um modelo, no formato do tobj: 3 valores por posição e normal,
+// 2 por coordenada de textura e 4 (RGBA) por cor. Texturas e cores são opcionais
+// Malhas com esqueleto tem também 4 juntas e 4 pesos por vertice (ver MeshData::joints)
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct NormalMesh {
+    pub positions: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub texcoords: Vec<f32>,
+    pub colors: Vec<f32>,
+    pub joints: Vec<u16>,
+    pub weights: Vec<f32>,
+    pub indices: Vec<u32>,
+}
+
+#[allow(dead_code)]
+impl NormalMesh {
+    // Recalcula as normais segundo o modo informado. Modos que vincam arestas
+    // (Flat e Crease) duplicam os vertices para que cada triangulo tenha os seus
+    pub fn with_normal_mode(self, mode: NormalMode) -> Self {
+        match mode {
+            NormalMode::Imported => {
+                if self.normals.len() == self.positions.len() && self.normals.len() > 0 {
+                    self
+                } else {
+                    self.with_normal_mode(NormalMode::Smooth)
+                }
+            }
+            NormalMode::Smooth => {
+                let normals = smooth_normals(&self.positions, &self.indices);
+                NormalMesh { normals, ..self }
+            }
+            NormalMode::Flat => self.with_normal_mode(NormalMode::Crease(0.0)),
+            NormalMode::Crease(angle) => {
+                let unwelded = self.unweld();
+                let normals = crease_normals(&unwelded.positions, &unwelded.indices, angle);
+                NormalMesh {
+                    normals,
+                    ..unwelded
+                }
+            }
+        }
+    }
+
+    // Cria um vertice para cada canto de cada triangulo
+    pub fn unweld(&self) -> Self {
+        let has_texcoords = self.texcoords.len() > 0;
+        let has_colors = self.colors.len() > 0;
+        let has_weights = self.weights.len() > 0;
+        let mut mesh = NormalMesh {
+            positions: Vec::with_capacity(self.indices.len() * 3),
+            normals: Vec::new(),
+            texcoords: Vec::new(),
+            colors: Vec::new(),
+            joints: Vec::new(),
+            weights: Vec::new(),
+            indices: Vec::with_capacity(self.indices.len()),
+        };
+
+        for (corner, index) in self.indices.iter().enumerate() {
+            let v = *index as usize;
+            mesh.positions
+                .extend_from_slice(&self.positions[3 * v..3 * v + 3]);
+            if has_texcoords {
+                mesh.texcoords
+                    .extend_from_slice(&self.texcoords[2 * v..2 * v + 2]);
+            }
+            if has_colors {
+                mesh.colors
+                    .extend_from_slice(&self.colors[4 * v..4 * v + 4]);
+            }
+            if has_weights {
+                mesh.joints
+                    .extend_from_slice(&self.joints[4 * v..4 * v + 4]);
+                mesh.weights
+                    .extend_from_slice(&self.weights[4 * v..4 * v + 4]);
+            }
+            mesh.indices.push(corner as u32);
+        }
+        mesh
+    }
+}
+
+fn vertex(positions: &[f32], index: u32) -> glm::Vec4 {
+    let i = index as usize;
+    glm::vec4(
+        positions[3 * i],
+        positions[3 * i + 1],
+        positions[3 * i + 2],
+        1.0,
+    )
+}
+
+// Chave de uma posição, para agrupar vertices duplicados pelo tobj (ex: cantos do cubo com
+// coordenadas de textura diferentes) que devem compartilhar a normal
+fn position_key(positions: &[f32], index: u32) -> [u32; 3] {
+    let i = index as usize;
+    [
+        positions[3 * i].to_bits(),
+        positions[3 * i + 1].to_bits(),
+        positions[3 * i + 2].to_bits(),
+    ]
+}
+
+// Normais unitarias das faces
+fn face_normals(positions: &[f32], indices: &[u32]) -> Vec<glm::Vec4> {
+    indices
+        .chunks(3)
+        .filter(|triangle| triangle.len() == 3)
+        .map(|triangle| {
+            normalize_vector(compute_normal(
+                &vertex(positions, triangle[0]),
+                &vertex(positions, triangle[1]),
+                &vertex(positions, triangle[2]),
+            ))
+        })
+        .collect()
+}
+
+// Angulo interno do triangulo t no canto v, usado como peso da normal da face
+// Diferente do peso por area, não depende de como as faces foram trianguladas
+fn corner_angle(positions: &[f32], indices: &[u32], t: usize, v: usize) -> f32 {
+    let p = vertex(positions, indices[3 * t + v]);
+    let a = normalize_vector(vertex(positions, indices[3 * t + (v + 1) % 3]) - p);
+    let b = normalize_vector(vertex(positions, indices[3 * t + (v + 2) % 3]) - p);
+    glm::acos(glm::clamp(a.x * b.x + a.y * b.y + a.z * b.z, -1.0, 1.0))
+}
+
+fn push_normal(normals: &mut Vec<f32>, n: glm::Vec4) {
+    let length = norm(n);
+    if length > 0.0 {
+        normals.extend_from_slice(&[n.x / length, n.y / length, n.z / length]);
+    } else {
+        normals.extend_from_slice(&[0.0, 0.0, 0.0]);
+    }
+}
+
+// Media das normais das faces, ponderada pelo angulo, agrupando vertices na mesma posição
+#[allow(dead_code)]
+pub fn smooth_normals(positions: &[f32], indices: &[u32]) -> Vec<f32> {
+    let faces = face_normals(positions, indices);
+    let mut accumulated: HashMap<[u32; 3], glm::Vec4> = HashMap::new();
+
+    for (t, n) in faces.iter().enumerate() {
+        for v in 0..3 {
+            let key = position_key(positions, indices[3 * t + v]);
+            let weight = corner_angle(positions, indices, t, v);
+            let sum = accumulated
+                .entry(key)
+                .or_insert(glm::vec4(0.0, 0.0, 0.0, 0.0));
+            *sum = *sum + *n * weight;
+        }
+    }
+
+    let mut normals = Vec::with_capacity(positions.len());
+    for v in 0..positions.len() / 3 {
+        let n = accumulated
+            .get(&position_key(positions, v as u32))
+            .cloned()
+            .unwrap_or(glm::vec4(0.0, 0.0, 0.0, 0.0));
+        push_normal(&mut normals, n);
+    }
+    normals
+}
+
+// Normais por canto de triangulo: cada canto soma as faces vizinhas (mesma posição) cujo
+// angulo com a sua face não passa de crease_angle. Espera vertices não compartilhados (unweld)
+#[allow(dead_code)]
+pub fn crease_normals(positions: &[f32], indices: &[u32], crease_angle: f32) -> Vec<f32> {
+    let faces = face_normals(positions, indices);
+
+    // Faces que tocam cada posição, com o peso do canto
+    let mut faces_at_position: HashMap<[u32; 3], Vec<(usize, f32)>> = HashMap::new();
+    for t in 0..faces.len() {
+        for v in 0..3 {
+            let weight = corner_angle(positions, indices, t, v);
+            faces_at_position
+                .entry(position_key(positions, indices[3 * t + v]))
+                .or_insert_with(Vec::new)
+                .push((t, weight));
+        }
+    }
+
+    // Pequena tolerancia para que faces coplanares sejam agrupadas mesmo com angulo 0
+    let cos_crease = glm::cos(crease_angle) - 1e-5;
+    let mut normals = vec![0.0; positions.len()];
+
+    for t in 0..faces.len() {
+        for v in 0..3 {
+            let index = indices[3 * t + v];
+            let mut n = glm::vec4(0.0, 0.0, 0.0, 0.0);
+
+            for &(neighbour, weight) in faces_at_position[&position_key(positions, index)].iter() {
+                let a = faces[t];
+                let b = faces[neighbour];
+                if a.x * b.x + a.y * b.y + a.z * b.z >= cos_crease {
+                    n = n + b * weight;
+                }
+            }
+
+            let mut normal = Vec::with_capacity(3);
+            push_normal(&mut normal, n);
+            let i = index as usize;
+            normals[3 * i..3 * i + 3].copy_from_slice(&normal);
+        }
+    }
+    normals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::mesh_data::MeshData;
+
+    // Carrega pelo mesmo caminho dos modelos do jogo, para que o teste cubra a copia das
+    // normais do arquivo para a malha
+    fn load_mesh(path: &str, mode: NormalMode) -> MeshData {
+        MeshData::load(path, mode).unwrap()
+    }
+
+    fn normal(mesh: &MeshData, vertex: usize) -> glm::Vec3 {
+        let n = mesh.normal(vertex);
+        glm::vec3(n.x, n.y, n.z)
+    }
+
+    fn position(mesh: &MeshData, vertex: usize) -> glm::Vec3 {
+        let p = mesh.position(vertex);
+        glm::vec3(p.x, p.y, p.z)
+    }
+
+    fn assert_unit_normals(mesh: &MeshData) {
+        assert_eq!(mesh.normals.len(), mesh.positions.len());
+        for v in 0..mesh.vertex_count() {
+            let length = glm::length(normal(mesh, v));
+            assert!(
+                (length - 1.0).abs() < 1e-4,
+                "normal {} com tamanho {}",
+                v,
+                length
+            );
+            assert_eq!(mesh.normals[4 * v + 3], 0.0);
+        }
+    }
+
+    // Normal de cada canto de triangulo do cubo: o eixo em que os 3 cantos tem a mesma coordenada,
+    // apontando para fora do cubo (centro em 0.5)
+    fn cube_face_normal(mesh: &MeshData, triangle: &[u32]) -> glm::Vec3 {
+        let corners: Vec<glm::Vec3> = triangle
+            .iter()
+            .map(|index| position(mesh, *index as usize))
+            .collect();
+        let mut face = glm::vec3(0.0, 0.0, 0.0);
+        for axis in 0..3 {
+            if corners
+                .iter()
+                .all(|corner| corner[axis] == corners[0][axis])
+            {
+                face[axis] = if corners[0][axis] > 0.5 { 1.0 } else { -1.0 };
+            }
+        }
+        face
+    }
+
+    fn assert_cube_flat(mesh: &MeshData) {
+        for triangle in mesh.indices.chunks(3) {
+            let face = cube_face_normal(mesh, triangle);
+            for index in triangle.iter() {
+                let n = normal(mesh, *index as usize);
+                assert!(
+                    glm::distance(n, face) < 1e-4,
+                    "normal {:?}, esperado {:?}",
+                    n,
+                    face
+                );
+            }
+        }
+    }
+
+    fn assert_sphere_smooth(mesh: &MeshData, tolerance: f32) {
+        for v in 0..mesh.vertex_count() {
+            let n = normal(mesh, v);
+            let p = glm::normalize(position(mesh, v));
+            assert!(
+                glm::dot(n, p) > tolerance,
+                "normal {:?} na posição {:?}",
+                n,
+                p
+            );
+        }
+    }
+
+    // Nos cantos do cubo as 3 faces tem o mesmo peso, e a normal suave aponta do centro
+    fn assert_cube_smooth(mesh: &MeshData) {
+        let center = glm::vec3(0.5, 0.5, 0.5);
+        for v in 0..mesh.vertex_count() {
+            let expected = glm::normalize(position(mesh, v) - center);
+            assert!(glm::distance(normal(mesh, v), expected) < 1e-4);
+        }
+    }
+
+    #[test]
+    fn imported_keeps_file_normals() {
+        let cube = load_mesh("src/data/objs/cube.obj", NormalMode::Imported);
+        assert_unit_normals(&cube);
+        assert_cube_flat(&cube);
+
+        let sphere = load_mesh("src/data/objs/sphere.obj", NormalMode::Imported);
+        assert_unit_normals(&sphere);
+        assert_sphere_smooth(&sphere, 0.99);
+    }
+
+    #[test]
+    fn imported_without_normals_is_smooth() {
+        // pyramid.obj não tem normais
+        let imported = load_mesh("src/data/objs/pyramid.obj", NormalMode::Imported);
+        let smooth = load_mesh("src/data/objs/pyramid.obj", NormalMode::Smooth);
+        assert_unit_normals(&imported);
+        assert_eq!(imported.normals, smooth.normals);
+    }
+
+    #[test]
+    fn flat_cube_normals_are_axes() {
+        let cube = load_mesh("src/data/objs/cube.obj", NormalMode::Flat);
+        // Nenhum vertice é compartilhado entre cantos de triangulos
+        let mut indices = cube.indices.clone();
+        indices.sort();
+        indices.dedup();
+        assert_eq!(indices.len(), cube.indices.len());
+        assert_unit_normals(&cube);
+        assert_cube_flat(&cube);
+    }
+
+    #[test]
+    fn smooth_normals_follow_position() {
+        let cube = load_mesh("src/data/objs/cube.obj", NormalMode::Smooth);
+        assert_unit_normals(&cube);
+        assert_cube_smooth(&cube);
+
+        let sphere = load_mesh("src/data/objs/sphere.obj", NormalMode::Smooth);
+        assert_unit_normals(&sphere);
+        assert_sphere_smooth(&sphere, 0.99);
+    }
+
+    #[test]
+    fn crease_splits_cube_edges() {
+        let cube = load_mesh(
+            "src/data/objs/cube.obj",
+            NormalMode::Crease(glm::radians(30.0)),
+        );
+        assert_unit_normals(&cube);
+        assert_cube_flat(&cube);
+
+        // Cada canto do cubo fica com as normais das 3 faces
+        let corner = glm::vec3(1.0, 1.0, 1.0);
+        let mut corner_normals: Vec<glm::Vec3> = Vec::new();
+        for v in 0..cube.vertex_count() {
+            let n = normal(&cube, v);
+            if position(&cube, v) == corner
+                && !corner_normals
+                    .iter()
+                    .any(|other| glm::distance(*other, n) < 1e-4)
+            {
+                corner_normals.push(n);
+            }
+        }
+        assert_eq!(corner_normals.len(), 3);
+    }
+
+    #[test]
+    fn crease_wider_than_edges_is_smooth() {
+        let cube = load_mesh(
+            "src/data/objs/cube.obj",
+            NormalMode::Crease(glm::radians(120.0)),
+        );
+        assert_unit_normals(&cube);
+        assert_cube_smooth(&cube);
+
+        let sphere = load_mesh(
+            "src/data/objs/sphere.obj",
+            NormalMode::Crease(glm::radians(60.0)),
+        );
+        assert_unit_normals(&sphere);
+        assert_sphere_smooth(&sphere, 0.99);
+    }
+}
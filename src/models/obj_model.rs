@@ -1,352 +1,504 @@
 use super::draw::Draw;
-use super::matrix::compute_normal;
+use super::matrix::dot_product;
 use super::matrix::identity_matrix;
 use super::matrix::norm;
+use super::matrix::normalize_vector;
 use super::matrix::GLMatrix;
 use super::matrix::MatrixTransform;
-use gl::types::GLfloat;
-use gl::types::GLsizeiptr;
-use gl::types::GLuint;
-use models::load_texture::load_texture;
+use models::asset_loader::request_mesh;
+use models::asset_loader::resolve_mesh;
+use models::load_texture::TextureError;
+use models::material::Material;
+use models::mesh_data::MeshData;
+use models::normals::NormalMode;
+use models::ray::ray_bbox_intersection;
+use models::ray::Ray;
+use models::ray::RayHit;
+use models::resources::load_mesh;
+use models::resources::load_normal_map_resource;
+use models::resources::load_texture_resource;
+use models::resources::GpuMesh;
+use models::resources::MeshHandle;
+use models::resources::TextureHandle;
 use models::scene_object::check_bbox_bbox_intersection;
 use models::scene_object::SceneObject;
+use models::skeleton::Skin;
+use models::skeleton::MAX_JOINTS;
+use models::texture_units::flip_uv;
+use models::texture_units::TextureUnits;
 use std::ffi::c_void;
 use std::ffi::CString;
 use std::mem;
-use std::path::Path;
-use std::ptr::null;
-use tobj;
+use std::sync::Arc;
+use world::frustum::Frustum;
+use world::screen_projection::current_projection;
 
 #[allow(dead_code)]
-#[derive(Debug, Copy)]
+#[derive(Debug, Clone)]
+// Parte de um obj desenhada separadamente, com seu proprio material
+// Cada modelo do arquivo obj (grupo "o"/"g" e cada troca de material com usemtl) gera um submesh
+// name -> Nome do modelo no arquivo obj
+// index_offset/index_count -> Intervalo do submesh no buffer de indices compartilhado
+// color_override -> Cor do submesh, tem prioridade sobre a cor do obj e do material se setada
+pub struct Submesh {
+    pub name: String,
+    pub index_offset: usize,
+    pub index_count: usize,
+    pub material: Option<Material>,
+    pub color_override: glm::Vec3,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
 pub struct ObjModel {
     pub model: GLMatrix,
-    pub vao: u32,
-    ebo: u32,
-    geometry_vbo: u32,
-    texture_vbo: u32,
-    normal_vbo: u32,
-    index_len: usize,
+    pub gpu_mesh: MeshHandle,
     pub bbox_min: glm::Vec3,
     pub bbox_max: glm::Vec3,
-    pub texture_override: u32,
+    pub texture_override: Option<TextureHandle>,
+    pub normal_map_override: Option<TextureHandle>,
     pub phong_q_overide: f32,
     pub specular_reflectance_override: glm::Vec3,
     pub ambient_reflectance_overide: glm::Vec3,
     pub color_overide: glm::Vec3,
     pub texture_map_type: i32,
     pub lighting_source_override: glm::Vec4,
+    pub submeshes: Arc<Vec<Submesh>>,
+    pub skin: Option<Skin>,
 }
 
 static ID_MATRIX: GLMatrix = identity_matrix();
 
+// Fração da altura da tela abaixo da qual cada nivel de detalhe é utilizado: abaixo de 40%
+// o nivel 1 (metade dos triangulos), abaixo de 20% o nivel 2 e abaixo de 10% o nivel 3
+static LOD_SCREEN_SIZES: [f32; 3] = [0.4, 0.2, 0.1];
+
 #[allow(dead_code)]
 impl ObjModel {
     pub fn new(path: &str) -> Self {
-        // Carrega arquivo obj
-        let (models, _materials) = tobj::load_obj(Path::new(path)).unwrap();
+        ObjModel::new_with_normals(path, NormalMode::default())
+    }
+
+    // Carrega o obj recalculando as normais segundo normal_mode
+    // Arquivos .ply e .stl também são aceitos, ver MeshData::load
+    // A malha é carregada uma unica vez por caminho, ver resources::load_mesh
+    pub fn new_with_normals(path: &str, normal_mode: NormalMode) -> Self {
+        match load_mesh(path, normal_mode) {
+            Ok(gpu_mesh) => ObjModel::from_gpu_mesh(gpu_mesh),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    // Pede o carregamento da malha no rayon, retornando imediatamente um obj com malha provisoria
+    // O obj passa a usar a malha carregada em resolve_assets, ver asset_loader.rs
+    pub fn new_async(path: &str) -> Self {
+        ObjModel::from_gpu_mesh(request_mesh(path, NormalMode::default()))
+    }
+
+    // Troca a malha provisoria pela malha carregada, se ela já estiver pronta, mantendo
+    // transformações, overrides e esqueleto do obj. Cores de submeshes não são mantidas, pois
+    // a malha provisoria não tem os submeshes do arquivo
+    pub fn resolve_assets(&self) -> Self {
+        let gpu_mesh = match resolve_mesh(&self.gpu_mesh) {
+            Some(gpu_mesh) => gpu_mesh,
+            None => return self.clone(),
+        };
+        let loaded = ObjModel::from_gpu_mesh(gpu_mesh);
+
+        // O esqueleto foi aceito pela malha provisoria (ver with_skin), e só é mantido se a malha
+        // carregada tiver juntas e pesos
+        let skin = match self.skin {
+            Some(_) if !loaded.mesh().is_skinned() => {
+                println!(
+                    "Malha sem juntas e pesos, esqueleto ignorado: {}",
+                    loaded.mesh().describe()
+                );
+                None
+            }
+            ref skin => skin.clone(),
+        };
+
+        ObjModel {
+            // Malhas com cores usam as cores dos vertices, se o tipo de mapeamento não foi setado
+            texture_map_type: if self.texture_map_type == 0 {
+                loaded.texture_map_type
+            } else {
+                self.texture_map_type
+            },
+            bbox_min: loaded.bbox_min,
+            bbox_max: loaded.bbox_max,
+            submeshes: loaded.submeshes,
+            gpu_mesh: loaded.gpu_mesh,
+            skin,
+            ..self.clone()
+        }
+    }
+
+    // Obj sem geometria, usado como raiz de hierarquias cujo nó não possui malha
+    pub fn empty() -> Self {
+        let mut mesh_data = MeshData::new();
+        mesh_data.bbox_min = glm::vec3(0.0, 0.0, 0.0);
+        mesh_data.bbox_max = glm::vec3(0.0, 0.0, 0.0);
+        ObjModel::from_mesh_data(mesh_data)
+    }
+
+    // Envia uma malha carregada na CPU para a GPU, sem compartilhar com outros carregamentos
+    pub fn from_mesh_data(mesh_data: MeshData) -> Self {
+        ObjModel::from_gpu_mesh(Arc::new(GpuMesh::upload(Arc::new(mesh_data))))
+    }
 
+    pub fn from_gpu_mesh(gpu_mesh: MeshHandle) -> Self {
         // Objeto simples:
         // Define propriedades do arquivo obj que representa um obj na tela
-        // vao -> Endereço da vao do objeto, não é criada em clones do obj
-        // ebo -> Indices do obj
-        // texture,normal,geometry vbo -> Auto descritivo
+        // gpu_mesh -> Malha na GPU, compartilhada pelos clones do obj e liberada com o ultimo clone
         // model: Matrix model inicial do obj. Padrão é matriz identidade
-        // Tamanho do indice dos vertices do obj
         // bbox_min/max -> Bounding box computada na inicialização do obj
-        // Texture overide -> Local da textura que sobreescreve a textura atual do obj se texture map type for setado.
+        // Texture overide -> Textura que sobreescreve a textura atual do obj se texture map type for setado.
+        // Normal map override -> Normal map que sobreescreve o do material (map_Bump/norm). Usado apenas
+        // com as coordenadas de textura do arquivo (texture map type 0) em malhas com tangentes
+        // Overrides de cor, refletancias, q de phong e textura substituem os valores do material do .mtl, se setados
         // Textura map type: Tipo de mapeamento da textura. 0 - Arquivo OBJ; 1- Planar XY; 2- Esferico; 3- Cilindrico
-        let mut myself = ObjModel {
-            vao: 0u32,
-            ebo: 0u32,
-            geometry_vbo: 0u32,
-            texture_vbo: 0u32,
-            normal_vbo: 0u32,
+        // 5 - Cor dos vertices, padrão para malhas com cores (ex: PLY)
+        // Skin -> Esqueleto e animações das malhas com juntas e pesos (ver skeleton.rs). A bbox é a da
+        // pose de repouso
+        ObjModel {
             model: ID_MATRIX,
-            index_len: 0,
-            bbox_min: glm::vec3(0.0, 0.0, 0.0),
-            bbox_max: glm::vec3(0.0, 0.0, 0.0),
-            texture_override: 0,
-            texture_map_type: 0,
+            bbox_min: gpu_mesh.mesh.bbox_min,
+            bbox_max: gpu_mesh.mesh.bbox_max,
+            texture_override: None,
+            normal_map_override: None,
+            texture_map_type: if gpu_mesh.mesh.colors.len() > 0 { 5 } else { 0 },
             specular_reflectance_override: glm::vec3(0.0, 0.0, 0.0),
             ambient_reflectance_overide: glm::vec3(0.0, 0.0, 0.0),
-            phong_q_overide: 1.0,
+            phong_q_overide: 0.0,
             color_overide: glm::vec3(0.0, 0.0, 0.0),
             lighting_source_override: glm::vec4(0.0, 0.0, 0.0, 0.0),
-        };
-
-        let mut position_array = Vec::new();
-        let mut normal_array = Vec::new();
-        let mut index_array = Vec::new();
-        let mut texture_array = Vec::new();
-
-        // Carrega dados de posições e indices para em vetores contínuos
-        // 3 valores no vetor de indices representam os vertices de um indice
-        // 4 valores no vetor de posição representam a posição de um vertice
-        for (_index, model) in models.iter().enumerate() {
-            let mesh = &model.mesh;
-
-            for f in 0..mesh.indices.len() {
-                // Vertices X Y Z de um triangulo
-                index_array.push(mesh.indices[f]);
-            }
-
-            for v in 0..mesh.positions.len() / 3 {
-                // Insere uma posição de um vertice
-                // X Y Z W em ordem
-                let x = mesh.positions[3 * v];
-                let y = mesh.positions[3 * v + 1];
-                let z = mesh.positions[3 * v + 2];
-
-                position_array.push(x);
-                position_array.push(y);
-                position_array.push(z);
-                position_array.push(1f32);
-
-                myself.bbox_min.x = glm::min(myself.bbox_min.x, x);
-                myself.bbox_min.y = glm::min(myself.bbox_min.y, y);
-                myself.bbox_min.z = glm::min(myself.bbox_min.z, z);
-                myself.bbox_max.x = glm::max(myself.bbox_max.x, x);
-                myself.bbox_max.y = glm::max(myself.bbox_max.y, y);
-                myself.bbox_max.z = glm::max(myself.bbox_max.z, z);
-            }
-
-            // Insere texturas
-            if mesh.texcoords.len() > 0 {
-                for v in 0..mesh.texcoords.len() {
-                    texture_array.push(mesh.texcoords[v]);
-                }
-            }
-
-            // Verifica se existem normais no obj, e insere
-            if mesh.normals.len() > 0 {
-                for v in 0..mesh.normals.len() / 3 {
-                    // Insere um normal de um vertice
-                    // X Y Z W em ordem
-                    normal_array.push(mesh.positions[3 * v]);
-                    normal_array.push(mesh.positions[3 * v + 1]);
-                    normal_array.push(mesh.positions[3 * v + 2]);
-                    normal_array.push(0f32);
-                }
-            } else {
-                // Computa normais dos vertices se não existe no obj
-                let num_vertices = mesh.positions.len() / 3;
-
-                let mut num_triangles_per_vertex: Vec<u32> = vec![0; num_vertices];
-
-                let mut vertex_normals: Vec<glm::Vec4> =
-                    vec![glm::Vec4::new(0f32, 0f32, 0f32, 1f32); num_vertices];
-
-                //println!("{:?}", mesh.indices.len() / 3);
-                for t in 0..mesh.indices.len() / 3 {
-                    // Calcula a normal de todos os triangulos
-
-                    let mut triangle_vertex_array = [
-                        glm::Vec4::new(0f32, 0f32, 0f32, 1f32),
-                        glm::Vec4::new(0f32, 0f32, 0f32, 1f32),
-                        glm::Vec4::new(0f32, 0f32, 0f32, 1f32),
-                    ];
-
-                    for v in 0..3 {
-                        let idx = mesh.indices[3 * t + v] as usize;
-                        let vx = mesh.positions[3 * idx + 0];
-                        let vy = mesh.positions[3 * idx + 1];
-                        let vz = mesh.positions[3 * idx + 2];
-
-                        triangle_vertex_array[v].x = vx;
-                        triangle_vertex_array[v].y = vy;
-                        triangle_vertex_array[v].z = vz;
-                    }
-
-                    let [a, b, c] = triangle_vertex_array;
-                    let n = compute_normal(&a, &b, &c);
-                    for v in 0..3 {
-                        let idx = mesh.indices[3 * t + v] as usize;
-                        num_triangles_per_vertex[idx] = num_triangles_per_vertex[idx] + 1;
-                        vertex_normals[idx] = vertex_normals[idx] + n;
-                    }
-                }
-
-                for i in 0..vertex_normals.len() {
-                    let mut n = vertex_normals[i] / num_triangles_per_vertex[i] as f32;
-                    n = n / norm(n);
-
-                    normal_array.push(n.x);
-                    normal_array.push(n.y);
-                    normal_array.push(n.z);
-                    normal_array.push(0f32);
-                }
-            }
+            submeshes: gpu_mesh.submeshes.clone(),
+            skin: None,
+            gpu_mesh,
         }
+    }
 
-        // Alocação de VAO e VBOS
-        unsafe {
-            // Definição dos atributos dos vertices
-            // Cria VAO do obj e "liga" ele
-            gl::GenVertexArrays(1, &mut myself.vao);
-            gl::BindVertexArray(myself.vao);
-
-            // Cria identificador do VBO a ser utilizado pelos atributos de geometria e "liga" o mesmo
-            gl::GenBuffers(1, &mut myself.geometry_vbo);
-            gl::BindBuffer(gl::ARRAY_BUFFER, myself.geometry_vbo);
-
-            // Aloca memória para o VBO acima.
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (position_array.len() * mem::size_of::<GLfloat>()) as GLsizeiptr, // Tamanho dos vertices
-                null(),
-                gl::STATIC_DRAW,
-            );
-
-            //Copia valores dos array de vertices para o VBO
-            gl::BufferSubData(
-                gl::ARRAY_BUFFER,
-                0,
-                (position_array.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-                position_array.as_ptr() as *const c_void,
-            );
-
-            // Location no shader para o VBO acima
-            let location: GLuint = 0; // location 0 no vertex shader
-
-            // "Liga" VAO e VBO
-            gl::VertexAttribPointer(location, 4, gl::FLOAT, gl::FALSE, 0, null());
-            // Ativa atributos
-            gl::EnableVertexAttribArray(location);
-            // Desliga VBO
-            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-
-            //Normais:
-            if normal_array.len() > 0 {
-                gl::GenBuffers(1, &mut myself.normal_vbo);
-                gl::BindBuffer(gl::ARRAY_BUFFER, myself.normal_vbo);
-
-                gl::BufferData(
-                    gl::ARRAY_BUFFER,
-                    (normal_array.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-                    null(),
-                    gl::STATIC_DRAW,
-                );
-                gl::BufferSubData(
-                    gl::ARRAY_BUFFER,
-                    0,
-                    (normal_array.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-                    normal_array.as_ptr() as *const c_void,
-                );
-
-                // Location das normais no shader
-                let location: GLuint = 1;
-
-                gl::VertexAttribPointer(location, 4, gl::FLOAT, gl::FALSE, 0, null());
-                gl::EnableVertexAttribArray(location);
-                gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-            }
-
-            // Aloca texturas
-            if texture_array.len() > 0 {
-                gl::GenBuffers(1, &mut myself.texture_vbo);
-                gl::BindBuffer(gl::ARRAY_BUFFER, myself.texture_vbo);
-
-                gl::BufferData(
-                    gl::ARRAY_BUFFER,
-                    (texture_array.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-                    null(),
-                    gl::STATIC_DRAW,
-                );
-                gl::BufferSubData(
-                    gl::ARRAY_BUFFER,
-                    0,
-                    (texture_array.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-                    texture_array.as_ptr() as *const c_void,
-                );
-
-                // Location das texturas no shader
-                let location: GLuint = 2;
-
-                gl::VertexAttribPointer(location, 4, gl::FLOAT, gl::FALSE, 0, null());
-                gl::EnableVertexAttribArray(location);
-                gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-            }
-
-            // Topolgia:
-            gl::GenBuffers(1, &mut myself.ebo);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, myself.ebo);
+    // Malha na memoria da CPU
+    pub fn mesh(&self) -> &MeshData {
+        &self.gpu_mesh.mesh
+    }
 
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                (index_array.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-                null(),
-                gl::STATIC_DRAW,
-            );
-            gl::BufferSubData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                0,
-                (index_array.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-                index_array.as_ptr() as *const c_void,
-            );
-            gl::BindVertexArray(0);
-            myself.index_len = index_array.len();
+    pub fn with_texture(&self, texture: &TextureHandle, texture_map_type: i32) -> Self {
+        Self {
+            texture_override: Some(texture.clone()),
+            texture_map_type: texture_map_type,
+            ..self.clone()
         }
-        myself
     }
 
-    pub fn with_texture(&self, texture: &u32, texture_map_type: i32) -> Self {
+    pub fn with_normal_map(&self, normal_map: &TextureHandle) -> Self {
         Self {
-            texture_override: *texture,
-            texture_map_type: texture_map_type,
-            ..*self
+            normal_map_override: Some(normal_map.clone()),
+            ..self.clone()
         }
     }
 
+    pub unsafe fn load_normal_map(&self, path: &str) -> Result<Self, TextureError> {
+        Ok(self.with_normal_map(&load_normal_map_resource(path)?))
+    }
+
     pub fn with_texture_map_type(&self, texture_map_type: i32) -> Self {
         Self {
             texture_map_type: texture_map_type,
-            ..*self
+            ..self.clone()
         }
     }
 
-    pub unsafe fn load_texture(&self, path: &str) -> Self {
-        let (tex, _) = load_texture(path);
-        Self {
-            texture_override: tex,
-            ..*self
-        }
+    pub unsafe fn load_texture(&self, path: &str) -> Result<Self, TextureError> {
+        Ok(Self {
+            texture_override: Some(load_texture_resource(path)?),
+            ..self.clone()
+        })
     }
 
     pub fn with_specular_reflectance(&self, specular_reflectance: &glm::Vec3) -> Self {
         Self {
             specular_reflectance_override: *specular_reflectance,
-            ..*self
+            ..self.clone()
         }
     }
 
     pub fn with_lighting_source_override(&self, lighting_source_override: &glm::Vec4) -> Self {
         Self {
             lighting_source_override: *lighting_source_override,
-            ..*self
+            ..self.clone()
         }
     }
 
     pub fn with_ambient_reflectance(&self, ambient_reflectance: &glm::Vec3) -> Self {
         Self {
             ambient_reflectance_overide: *ambient_reflectance,
-            ..*self
+            ..self.clone()
         }
     }
     pub fn with_specular_phong_q(&self, phong_q: &f32) -> Self {
         Self {
             phong_q_overide: *phong_q,
-            ..*self
+            ..self.clone()
         }
     }
 
     pub fn with_color(&self, color: &glm::Vec3) -> Self {
         Self {
             color_overide: *color,
-            ..*self
+            ..self.clone()
+        }
+    }
+
+    // Liga o obj a um esqueleto. Apenas malhas com juntas e pesos são deformadas
+    // Malhas provisorias (ver asset_loader.rs) aceitam o esqueleto da malha em carregamento
+    pub fn with_skin(&self, skin: &Skin) -> Self {
+        if !self.mesh().is_skinned() && self.gpu_mesh.pending.is_none() {
+            println!(
+                "Malha sem juntas e pesos, esqueleto ignorado: {}",
+                self.mesh().describe()
+            );
+            return self.clone();
+        }
+        Self {
+            skin: Some(skin.clone()),
+            ..self.clone()
+        }
+    }
+
+    // Aplica uma mudança no esqueleto do obj. Objs sem esqueleto não mudam
+    fn map_skin<F: FnOnce(&Skin) -> Skin>(&self, change: F) -> Self {
+        match self.skin {
+            Some(ref skin) => Self {
+                skin: Some(change(skin)),
+                ..self.clone()
+            },
+            None => self.clone(),
         }
     }
 
+    // Reproduz o clip em loop, ver Skin::play
+    pub fn play(&self, clip: &str) -> Self {
+        self.map_skin(|skin| skin.play(clip))
+    }
+
+    pub fn play_once(&self, clip: &str) -> Self {
+        self.map_skin(|skin| skin.play_once(clip))
+    }
+
+    // Transição suave para o clip, ver Skin::cross_fade
+    pub fn cross_fade(&self, clip: &str, duration: f32) -> Self {
+        self.map_skin(|skin| skin.cross_fade(clip, duration))
+    }
+
+    // Mistura o clip aos clips em reprodução, ver Skin::blend
+    pub fn blend_clip(&self, clip: &str, weight: f32) -> Self {
+        self.map_skin(|skin| skin.blend(clip, weight))
+    }
+
+    // Avança a animação em delta_time segundos
+    pub fn update_animation(&self, delta_time: f32) -> Self {
+        self.map_skin(|skin| skin.update(delta_time))
+    }
+
+    // Busca um submesh pelo nome do modelo no arquivo obj
+    pub fn submesh(&self, name: &str) -> Option<&Submesh> {
+        self.submeshes.iter().find(|submesh| submesh.name == name)
+    }
+
+    pub fn submesh_names(&self) -> Vec<&str> {
+        self.submeshes
+            .iter()
+            .map(|submesh| submesh.name.as_str())
+            .collect()
+    }
+
+    // Muda a cor dos submeshes com o nome informado. A lista de submeshes é compartilhada
+    // entre os clones do obj, então uma nova lista é alocada apenas para o obj retornado
+    pub fn with_submesh_color(&self, name: &str, color: &glm::Vec3) -> Self {
+        if self.submesh(name).is_none() {
+            println!("Submesh não encontrado: {}", name);
+            return self.clone();
+        }
+
+        let submeshes: Vec<Submesh> = self
+            .submeshes
+            .iter()
+            .map(|submesh| {
+                if submesh.name == name {
+                    Submesh {
+                        color_override: *color,
+                        ..submesh.clone()
+                    }
+                } else {
+                    submesh.clone()
+                }
+            })
+            .collect();
+
+        Self {
+            submeshes: Arc::new(submeshes),
+            ..self.clone()
+        }
+    }
+
+    // Resolve os valores utilizados no desenho de um submesh: override se setado (diferente de 0),
+    // se não o valor do material. Retorna (cor, textura, especular, ambiente, q de phong)
+    pub fn resolve_material(
+        &self,
+        submesh: &Submesh,
+    ) -> (glm::Vec3, Option<TextureHandle>, glm::Vec3, glm::Vec3, f32) {
+        let zero = glm::vec3(0.0, 0.0, 0.0);
+        let (diffuse, diffuse_texture, specular, ambient, shininess) = match &submesh.material {
+            Some(material) => (
+                material.diffuse,
+                material.diffuse_texture.as_ref(),
+                material.specular,
+                material.ambient,
+                material.shininess,
+            ),
+            None => (zero, None, zero, zero, 0.0),
+        };
+
+        let texture = self.texture_override.as_ref().or(diffuse_texture).cloned();
+
+        // Cor zerada indica ao shader que a textura deve ser utilizada
+        let color = if submesh.color_override != zero {
+            submesh.color_override
+        } else if self.color_overide != zero {
+            self.color_overide
+        } else if texture.is_some() {
+            zero
+        } else {
+            diffuse
+        };
+
+        let specular = if self.specular_reflectance_override != zero {
+            self.specular_reflectance_override
+        } else {
+            specular
+        };
+
+        let ambient = if self.ambient_reflectance_overide != zero {
+            self.ambient_reflectance_overide
+        } else {
+            ambient
+        };
+
+        let phong_q = if self.phong_q_overide > 0.0 {
+            self.phong_q_overide
+        } else if shininess > 0.0 {
+            shininess
+        } else {
+            1.0
+        };
+
+        (color, texture, specular, ambient, phong_q)
+    }
+
+    // Normal map do submesh: o do obj, se setado, ou o do material. None se não houver ou se a
+    // malha não tiver tangentes (malhas sem coordenadas de textura)
+    pub fn resolve_normal_map(&self, submesh: &Submesh) -> Option<TextureHandle> {
+        if self.mesh().tangents.len() == 0 {
+            return None;
+        }
+        let material_normal_map = submesh
+            .material
+            .as_ref()
+            .and_then(|material| material.bump_texture.as_ref());
+
+        self.normal_map_override
+            .as_ref()
+            .or(material_normal_map)
+            .cloned()
+    }
+
+    // Nivel de detalhe para o tamanho do obj na tela da view atual (ver View::render)
+    // 0 é a malha completa. Sem view ou sem niveis gerados, a malha completa é utilizada
+    pub fn select_lod(&self) -> usize {
+        let lod_count = self.gpu_mesh.lod_count();
+        let projection = match current_projection() {
+            Some(projection) if lod_count > 1 => projection,
+            _ => return 0,
+        };
+
+        let (world_min, world_max) = self.world_bbox();
+        let center = (world_min + world_max) / 2.0;
+        let radius = glm::length(world_max - world_min) / 2.0;
+        let screen_size = projection.screen_size(&center, radius);
+
+        LOD_SCREEN_SIZES
+            .iter()
+            .filter(|threshold| screen_size < **threshold)
+            .count()
+            .min(lod_count - 1)
+    }
+
+    // Bounding box em coordenadas globais, envolvendo os 8 vertices da bbox local transformados
+    pub fn world_bbox(&self) -> (glm::Vec4, glm::Vec4) {
+        let min = self.bbox_min;
+        let max = self.bbox_max;
+
+        let mut world_min = glm::vec4(std::f32::MAX, std::f32::MAX, std::f32::MAX, 1.0);
+        let mut world_max = glm::vec4(std::f32::MIN, std::f32::MIN, std::f32::MIN, 1.0);
+
+        for corner in 0..8 {
+            let local = glm::vec4(
+                if corner & 1 == 0 { min.x } else { max.x },
+                if corner & 2 == 0 { min.y } else { max.y },
+                if corner & 4 == 0 { min.z } else { max.z },
+                1.0,
+            );
+            let world = self.model.matrix * local;
+
+            world_min.x = glm::min(world_min.x, world.x);
+            world_min.y = glm::min(world_min.y, world.y);
+            world_min.z = glm::min(world_min.z, world.z);
+            world_max.x = glm::max(world_max.x, world.x);
+            world_max.y = glm::max(world_max.y, world.y);
+            world_max.z = glm::max(world_max.z, world.z);
+        }
+        (world_min, world_max)
+    }
+
+    // Esfera global que envolve a bbox global
+    pub fn world_bounding_sphere(&self) -> (glm::Vec4, f32) {
+        let (world_min, world_max) = self.world_bbox();
+        let center = (world_min + world_max) / 2.0;
+        (center, norm(world_max - center))
+    }
+
+    // Raycast contra os triangulos do obj. O raio é levado para o espaço local do obj,
+    // testado contra a bbox local e depois contra cada triangulo
+    pub fn raycast(&self, ray: &Ray) -> Option<RayHit> {
+        let inverse_model = self.model.inverse()?;
+        let local_ray = ray.transform(&inverse_model);
+
+        let local_bbox_min = glm::vec4(self.bbox_min.x, self.bbox_min.y, self.bbox_min.z, 1.0);
+        let local_bbox_max = glm::vec4(self.bbox_max.x, self.bbox_max.y, self.bbox_max.z, 1.0);
+        ray_bbox_intersection(&local_ray, &local_bbox_min, &local_bbox_max)?;
+
+        let (t, local_normal) = self.mesh().raycast(&local_ray)?;
+
+        // Direção global normalizada: t local é igual a distancia global
+        let normal_matrix = self.model.normal_matrix().unwrap_or(ID_MATRIX);
+        let mut normal = normalize_vector(normal_matrix.matrix * local_normal);
+        normal.w = 0.0;
+
+        // Normal voltada para a origem do raio
+        if dot_product(normal, ray.direction) > 0.0 {
+            normal = -normal;
+        }
+
+        Some(RayHit {
+            distance: t,
+            point: ray.at(t),
+            normal,
+            object_index: 0,
+        })
+    }
+
     pub fn check_intersection(&self, obj2: &SceneObject) -> bool {
         let obj1 = self;
 
@@ -393,13 +545,9 @@ impl MatrixTransform for ObjModel {
         self
     }
     fn from_matrix(&self, matrix: &GLMatrix) -> Self {
-        *self.clone().update_matrix(matrix)
-    }
-}
-
-impl Clone for ObjModel {
-    fn clone(&self) -> Self {
-        *self
+        let mut obj = self.clone();
+        obj.update_matrix(matrix);
+        obj
     }
 }
 
@@ -409,12 +557,20 @@ impl Draw for ObjModel {
         unsafe {
             gl::UseProgram(*program);
 
-            gl::BindVertexArray(self.vao);
+            gl::BindVertexArray(self.gpu_mesh.vao);
 
             // Carrega uniforms com atributos do objeto definidos na inicialização
-            gl::Uniform1i(
-                gl::GetUniformLocation(*program, CString::new("texture_overide").unwrap().as_ptr()),
-                self.texture_override as i32,
+            let texture_uniform =
+                gl::GetUniformLocation(*program, CString::new("texture_overide").unwrap().as_ptr());
+            let normal_map_uniform =
+                gl::GetUniformLocation(*program, CString::new("normal_map").unwrap().as_ptr());
+            let has_normal_map_uniform =
+                gl::GetUniformLocation(*program, CString::new("has_normal_map").unwrap().as_ptr());
+            let flip_texture_uv_uniform =
+                gl::GetUniformLocation(*program, CString::new("flip_texture_uv").unwrap().as_ptr());
+            let flip_normal_map_uv_uniform = gl::GetUniformLocation(
+                *program,
+                CString::new("flip_normal_map_uv").unwrap().as_ptr(),
             );
             let texture_map_type_uniform = gl::GetUniformLocation(
                 *program,
@@ -427,6 +583,9 @@ impl Draw for ObjModel {
             let model_uniform =
                 gl::GetUniformLocation(*program, CString::new("model").unwrap().as_ptr());
 
+            let normal_matrix_uniform =
+                gl::GetUniformLocation(*program, CString::new("normal_matrix").unwrap().as_ptr());
+
             let bbox_min_uniform =
                 gl::GetUniformLocation(*program, CString::new("bbox_min").unwrap().as_ptr());
 
@@ -477,43 +636,104 @@ impl Draw for ObjModel {
                 1.0,
             );
 
-            gl::Uniform3f(
-                color_overide_uniform,
-                self.color_overide.x,
-                self.color_overide.y,
-                self.color_overide.z,
-            );
-
-            gl::Uniform3f(
-                specular_reflectance_uniform,
-                self.specular_reflectance_override.x,
-                self.specular_reflectance_override.y,
-                self.specular_reflectance_override.z,
-            );
-
-            gl::Uniform3f(
-                ambient_reflectance_uniform,
-                self.ambient_reflectance_overide.x,
-                self.ambient_reflectance_overide.y,
-                self.ambient_reflectance_overide.z,
-            );
-
             gl::UniformMatrix4fv(
                 model_uniform,
                 1,
                 gl::FALSE,
                 mem::transmute(&self.model.matrix[0]),
             );
-            gl::Uniform1f(phong_q_uniform, self.phong_q_overide);
-
-            // Desenha elemento
-            gl::DrawElements(
-                gl::TRIANGLES,
-                self.index_len as i32,
-                gl::UNSIGNED_INT,
-                0 as *const i32 as *const c_void,
+
+            // Matriz de normais calculada na CPU. Se a model for singular (escala 0), o obj
+            // não é visivel e a identidade serve apenas para evitar valores invalidos no shader
+            let normal_matrix = self.model.normal_matrix().unwrap_or(ID_MATRIX);
+            gl::UniformMatrix4fv(
+                normal_matrix_uniform,
+                1,
+                gl::FALSE,
+                mem::transmute(&normal_matrix.matrix[0]),
             );
+
+            // Pose do esqueleto: matrizes das juntas, ou deformação desligada em objs sem esqueleto
+            let skinned_uniform =
+                gl::GetUniformLocation(*program, CString::new("skinned").unwrap().as_ptr());
+            match self.skin {
+                Some(ref skin) if self.mesh().is_skinned() => {
+                    let joint_matrices_uniform = gl::GetUniformLocation(
+                        *program,
+                        CString::new("joint_matrices").unwrap().as_ptr(),
+                    );
+                    let joint_matrices: Vec<f32> = skin
+                        .joint_matrices()
+                        .iter()
+                        .take(MAX_JOINTS)
+                        .flat_map(|joint| {
+                            let m = joint.matrix;
+                            vec![
+                                m.c0.x, m.c0.y, m.c0.z, m.c0.w, m.c1.x, m.c1.y, m.c1.z, m.c1.w,
+                                m.c2.x, m.c2.y, m.c2.z, m.c2.w, m.c3.x, m.c3.y, m.c3.z, m.c3.w,
+                            ]
+                        })
+                        .collect();
+                    gl::UniformMatrix4fv(
+                        joint_matrices_uniform,
+                        (joint_matrices.len() / 16) as i32,
+                        gl::FALSE,
+                        joint_matrices.as_ptr(),
+                    );
+                    gl::Uniform1i(skinned_uniform, 1);
+                }
+                _ => gl::Uniform1i(skinned_uniform, 0),
+            }
+
+            // Desenha cada submesh com os valores do seu material
+            let lod = self.select_lod();
+            for (submesh_id, submesh) in self.submeshes.iter().enumerate() {
+                let (color, texture, specular, ambient, phong_q) = self.resolve_material(submesh);
+
+                let normal_map = self.resolve_normal_map(submesh);
+
+                // Cada submesh liga as suas texturas nas unidades a partir da primeira
+                let mut texture_units = TextureUnits::new();
+                texture_units.bind_uniform(texture_uniform, texture.as_ref());
+                let has_normal_map =
+                    texture_units.bind_uniform(normal_map_uniform, normal_map.as_ref());
+                gl::Uniform1i(has_normal_map_uniform, has_normal_map as i32);
+                gl::Uniform1i(flip_texture_uv_uniform, flip_uv(texture.as_ref()) as i32);
+                gl::Uniform1i(
+                    flip_normal_map_uv_uniform,
+                    flip_uv(normal_map.as_ref()) as i32,
+                );
+                gl::Uniform3f(color_overide_uniform, color.x, color.y, color.z);
+                gl::Uniform3f(
+                    specular_reflectance_uniform,
+                    specular.x,
+                    specular.y,
+                    specular.z,
+                );
+                gl::Uniform3f(ambient_reflectance_uniform, ambient.x, ambient.y, ambient.z);
+                gl::Uniform1f(phong_q_uniform, phong_q);
+
+                // Desenha elemento
+                let (index_offset, index_count) = self.gpu_mesh.submesh_range(lod, submesh_id);
+                gl::DrawElements(
+                    gl::TRIANGLES,
+                    index_count as i32,
+                    gl::UNSIGNED_INT,
+                    (index_offset * mem::size_of::<u32>()) as *const c_void,
+                );
+            }
         }
         self
     }
+
+    fn draw_in_frustum(&self, program: &u32, frustum: &Frustum) -> usize {
+        let (world_min, world_max) = self.world_bbox();
+
+        if frustum.intersects_bbox(&world_min, &world_max) {
+            self.draw(program);
+            0
+        } else {
+            1
+        }
+    }
 }
@@ -0,0 +1,476 @@
+use models::mesh_data::MeshData;
+use models::mesh_data::MeshError;
+use models::normals::NormalMesh;
+use models::normals::NormalMode;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+// Tipos escalares de propriedades do PLY
+enum PlyType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl PlyType {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "char" | "int8" => Ok(PlyType::Int8),
+            "uchar" | "uint8" => Ok(PlyType::UInt8),
+            "short" | "int16" => Ok(PlyType::Int16),
+            "ushort" | "uint16" => Ok(PlyType::UInt16),
+            "int" | "int32" => Ok(PlyType::Int32),
+            "uint" | "uint32" => Ok(PlyType::UInt32),
+            "float" | "float32" => Ok(PlyType::Float32),
+            "double" | "float64" => Ok(PlyType::Float64),
+            _ => Err(format!("Tipo de propriedade desconhecido: {}", name)),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match *self {
+            PlyType::Int8 | PlyType::UInt8 => 1,
+            PlyType::Int16 | PlyType::UInt16 => 2,
+            PlyType::Int32 | PlyType::UInt32 | PlyType::Float32 => 4,
+            PlyType::Float64 => 8,
+        }
+    }
+
+    // Valor maximo de cores inteiras, usado para normalizar para [0..1]
+    // Em tipos com sinal os valores negativos são limitados a 0 após a normalização
+    fn color_scale(&self) -> f64 {
+        match *self {
+            PlyType::UInt8 => 255.0,
+            PlyType::Int8 => 127.0,
+            PlyType::UInt16 => 65535.0,
+            PlyType::Int16 => 32767.0,
+            _ => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+// Propriedade de um elemento. Listas tem o tipo do contador em list_count
+struct PlyProperty {
+    name: String,
+    data_type: PlyType,
+    list_count: Option<PlyType>,
+}
+
+#[derive(Debug, Clone)]
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+// Leitor do corpo do arquivo, em texto ou binario little endian
+enum PlyReader<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary(&'a [u8]),
+}
+
+impl<'a> PlyReader<'a> {
+    fn read(&mut self, data_type: PlyType) -> Result<f64, String> {
+        match *self {
+            PlyReader::Ascii(ref mut tokens) => {
+                let token = tokens
+                    .next()
+                    .ok_or_else(|| "Fim inesperado do arquivo".to_string())?;
+                token
+                    .parse::<f64>()
+                    .map_err(|_| format!("Valor invalido: {}", token))
+            }
+            PlyReader::Binary(ref mut bytes) => {
+                let size = data_type.size();
+                if bytes.len() < size {
+                    return Err("Fim inesperado do arquivo".to_string());
+                }
+                let (value, rest) = bytes.split_at(size);
+                *bytes = rest;
+
+                let mut b = [0u8; 8];
+                b[..size].copy_from_slice(value);
+                Ok(match data_type {
+                    PlyType::Int8 => b[0] as i8 as f64,
+                    PlyType::UInt8 => b[0] as f64,
+                    PlyType::Int16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    PlyType::UInt16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    PlyType::Int32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    PlyType::UInt32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    PlyType::Float32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    PlyType::Float64 => f64::from_le_bytes(b),
+                })
+            }
+        }
+    }
+}
+
+// Carrega um arquivo PLY (ASCII ou binario little endian) como malha de um submesh
+// Lê posições, normais, coordenadas de textura e cores dos vertices, e faces poligonais
+// que são trianguladas em leque. Outros elementos do arquivo são ignorados
+#[allow(dead_code)]
+pub fn load_ply(path: &str, normal_mode: NormalMode) -> Result<MeshData, MeshError> {
+    let load_error = |message: String| MeshError::Load {
+        path: path.to_string(),
+        message,
+    };
+
+    let bytes = fs::read(path).map_err(|error| load_error(error.to_string()))?;
+    let mesh = parse_ply(&bytes).map_err(load_error)?;
+
+    let name = Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or(String::new());
+
+    let mut mesh_data = MeshData::new();
+    mesh_data.push_mesh(&name, &mesh.with_normal_mode(normal_mode), None);
+    mesh_data.validate()?;
+    Ok(mesh_data)
+}
+
+fn parse_ply(bytes: &[u8]) -> Result<NormalMesh, String> {
+    // Cabeçalho em texto, terminado por end_header
+    let header_end = find_header_end(bytes).ok_or_else(|| "Cabeçalho PLY invalido".to_string())?;
+    let header = String::from_utf8_lossy(&bytes[..header_end]);
+    let (format, elements) = parse_header(&header)?;
+
+    let body = &bytes[header_end..];
+    let body_text;
+    let mut reader = match format {
+        PlyFormat::Ascii => {
+            body_text = String::from_utf8_lossy(body);
+            PlyReader::Ascii(body_text.split_whitespace())
+        }
+        PlyFormat::BinaryLittleEndian => PlyReader::Binary(body),
+    };
+
+    let mut mesh = NormalMesh {
+        positions: Vec::new(),
+        normals: Vec::new(),
+        texcoords: Vec::new(),
+        colors: Vec::new(),
+        joints: Vec::new(),
+        weights: Vec::new(),
+        indices: Vec::new(),
+    };
+
+    for element in elements.iter() {
+        match element.name.as_str() {
+            "vertex" => read_vertices(&mut reader, element, &mut mesh)?,
+            "face" => read_faces(&mut reader, element, &mut mesh)?,
+            _ => {
+                // Elemento desconhecido: lido apenas para avançar o leitor
+                for _ in 0..element.count {
+                    read_row(&mut reader, element)?;
+                }
+            }
+        }
+    }
+    Ok(mesh)
+}
+
+// Posição do primeiro byte após a linha end_header
+// Apenas uma linha com end_header sozinho termina o cabeçalho, e não um comentario que o contenha
+fn find_header_end(bytes: &[u8]) -> Option<usize> {
+    let mut line_start = 0;
+    while line_start < bytes.len() {
+        let line_end = bytes[line_start..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map(|newline| line_start + newline)?;
+        let line = String::from_utf8_lossy(&bytes[line_start..line_end]);
+        if line.trim() == "end_header" {
+            return Some(line_end + 1);
+        }
+        line_start = line_end + 1;
+    }
+    None
+}
+
+fn parse_header(header: &str) -> Result<(PlyFormat, Vec<PlyElement>), String> {
+    let mut lines = header.lines().map(|line| line.trim());
+    if lines.next() != Some("ply") {
+        return Err("Arquivo não é PLY".to_string());
+    }
+
+    let mut format = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+
+    for line in lines {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["format", "ascii", _] => format = Some(PlyFormat::Ascii),
+            ["format", "binary_little_endian", _] => format = Some(PlyFormat::BinaryLittleEndian),
+            ["format", other, _] => return Err(format!("Formato PLY não suportado: {}", other)),
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| format!("Quantidade invalida: {}", line))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_type, data_type, name] => elements
+                .last_mut()
+                .ok_or_else(|| format!("Propriedade sem elemento: {}", line))?
+                .properties
+                .push(PlyProperty {
+                    name: name.to_string(),
+                    data_type: PlyType::parse(data_type)?,
+                    list_count: Some(PlyType::parse(count_type)?),
+                }),
+            ["property", data_type, name] => elements
+                .last_mut()
+                .ok_or_else(|| format!("Propriedade sem elemento: {}", line))?
+                .properties
+                .push(PlyProperty {
+                    name: name.to_string(),
+                    data_type: PlyType::parse(data_type)?,
+                    list_count: None,
+                }),
+            _ => {} // comment, obj_info, end_header
+        }
+    }
+
+    let format = format.ok_or_else(|| "Formato PLY ausente".to_string())?;
+    Ok((format, elements))
+}
+
+// Lê uma linha de um elemento: um vetor de valores por propriedade (listas com varios)
+fn read_row(reader: &mut PlyReader, element: &PlyElement) -> Result<Vec<Vec<f64>>, String> {
+    let mut row = Vec::with_capacity(element.properties.len());
+    for property in element.properties.iter() {
+        match property.list_count {
+            Some(count_type) => {
+                let count = reader.read(count_type)? as usize;
+                let mut values = Vec::with_capacity(count);
+                for _ in 0..count {
+                    values.push(reader.read(property.data_type)?);
+                }
+                row.push(values);
+            }
+            None => row.push(vec![reader.read(property.data_type)?]),
+        }
+    }
+    Ok(row)
+}
+
+fn property_index(element: &PlyElement, names: &[&str]) -> Option<usize> {
+    element
+        .properties
+        .iter()
+        .position(|property| names.contains(&property.name.as_str()))
+}
+
+fn read_vertices(
+    reader: &mut PlyReader,
+    element: &PlyElement,
+    mesh: &mut NormalMesh,
+) -> Result<(), String> {
+    let find = |names: &[&str]| property_index(element, names);
+
+    let position = [find(&["x"]), find(&["y"]), find(&["z"])];
+    let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+    let texcoord = [
+        find(&["u", "s", "texture_u", "texture_s"]),
+        find(&["v", "t", "texture_v", "texture_t"]),
+    ];
+    let color = [
+        find(&["red", "r", "diffuse_red"]),
+        find(&["green", "g", "diffuse_green"]),
+        find(&["blue", "b", "diffuse_blue"]),
+    ];
+    let alpha = find(&["alpha", "a"]);
+
+    if position.iter().any(|index| index.is_none()) {
+        return Err("Vertices sem posição x y z".to_string());
+    }
+    let has_normals = normal.iter().all(|index| index.is_some());
+    let has_texcoords = texcoord.iter().all(|index| index.is_some());
+    let has_colors = color.iter().all(|index| index.is_some());
+
+    // Normaliza cores inteiras (ex: 0..255) para [0..1]
+    let color_scale = |index: usize| element.properties[index].data_type.color_scale();
+
+    for _ in 0..element.count {
+        let row = read_row(reader, element)?;
+        // Escalares declarados como lista usam o primeiro valor, listas vazias são invalidas
+        let value = |index: Option<usize>| match index {
+            Some(i) => row[i].first().cloned().ok_or_else(|| {
+                format!(
+                    "Vertice com propriedade vazia: {}",
+                    element.properties[i].name
+                )
+            }),
+            None => Ok(0.0),
+        };
+
+        for index in position.iter() {
+            mesh.positions.push(value(*index)? as f32);
+        }
+        if has_normals {
+            for index in normal.iter() {
+                mesh.normals.push(value(*index)? as f32);
+            }
+        }
+        if has_texcoords {
+            for index in texcoord.iter() {
+                mesh.texcoords.push(value(*index)? as f32);
+            }
+        }
+        if has_colors {
+            for index in color.iter() {
+                let i = index.unwrap();
+                mesh.colors
+                    .push(glm::clamp(value(*index)? / color_scale(i), 0.0, 1.0) as f32);
+            }
+            let a = match alpha {
+                Some(i) => glm::clamp(value(alpha)? / color_scale(i), 0.0, 1.0),
+                None => 1.0,
+            };
+            mesh.colors.push(a as f32);
+        }
+    }
+    Ok(())
+}
+
+fn read_faces(
+    reader: &mut PlyReader,
+    element: &PlyElement,
+    mesh: &mut NormalMesh,
+) -> Result<(), String> {
+    let indices = property_index(element, &["vertex_indices", "vertex_index"])
+        .ok_or_else(|| "Faces sem vertex_indices".to_string())?;
+
+    for _ in 0..element.count {
+        let row = read_row(reader, element)?;
+        let face = &row[indices];
+
+        // Triangulação em leque de poligonos convexos
+        for i in 1..face.len().saturating_sub(1) {
+            mesh.indices.push(face[0] as u32);
+            mesh.indices.push(face[i] as u32);
+            mesh.indices.push(face[i + 1] as u32);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Quadrado com 4 vertices coloridos e uma face de 4 lados
+    static ASCII_PLY: &str = "ply
+format ascii 1.0
+comment cabeçalho com end_header em um comentario
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 255
+4 0 1 2 3
+";
+
+    fn binary_ply(color_type: &str, colors: &[i8]) -> Vec<u8> {
+        let mut bytes = format!(
+            "ply\nformat binary_little_endian 1.0\nelement vertex 3\nproperty float x\n\
+             property float y\nproperty float z\nproperty {0} red\nproperty {0} green\n\
+             property {0} blue\nelement face 1\nproperty list uchar uint vertex_indices\n\
+             end_header\n",
+            color_type
+        )
+        .into_bytes();
+        let positions = [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        for (vertex, position) in positions.iter().enumerate() {
+            for value in position.iter() {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            for channel in 0..3 {
+                bytes.push(colors[3 * vertex + channel] as u8);
+            }
+        }
+        bytes.push(3);
+        for index in 0..3u32 {
+            bytes.extend_from_slice(&index.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn parse_ascii_ply() {
+        let mesh = parse_ply(ASCII_PLY.as_bytes()).unwrap();
+        assert_eq!(mesh.positions.len(), 12);
+        assert_eq!(&mesh.positions[6..9], &[1.0, 1.0, 0.0]);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert!(mesh.normals.is_empty());
+        assert_eq!(&mesh.colors[..8], &[1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn parse_binary_ply() {
+        let mesh = parse_ply(&binary_ply("uchar", &[-1, 0, 0, 0, -1, 0, 0, 0, -1])).unwrap();
+        assert_eq!(
+            mesh.positions,
+            vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+        );
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+        // 0xff sem sinal é a cor maxima
+        assert_eq!(&mesh.colors[..4], &[1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn signed_colors_are_clamped() {
+        let mesh = parse_ply(&binary_ply("char", &[127, -128, 0, 64, 0, 0, 0, 0, 0])).unwrap();
+        assert_eq!(&mesh.colors[..4], &[1.0, 0.0, 0.0, 1.0]);
+        assert!((mesh.colors[4] - 64.0 / 127.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn header_end_must_be_a_line() {
+        let header = b"ply\ncomment end_header no comentario\nend_header\n0 0 0";
+        assert_eq!(find_header_end(header), Some(header.len() - 5));
+        assert_eq!(find_header_end(b"ply\ncomment end_header\n"), None);
+        assert_eq!(find_header_end(b"ply\r\nend_header\r\nx"), Some(17));
+    }
+
+    #[test]
+    fn rejects_invalid_ply() {
+        assert!(parse_ply(b"obj\nend_header\n").is_err());
+        assert!(parse_ply(b"ply\nformat binary_big_endian 1.0\nend_header\n").is_err());
+        let truncated = &ASCII_PLY[..ASCII_PLY.len() - 4];
+        assert!(parse_ply(truncated.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn empty_scalar_list_is_an_error() {
+        let ply = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\n\
+                   property list uchar float z\nelement face 0\n\
+                   property list uchar uint vertex_indices\nend_header\n0 0 0\n";
+        // z declarado como lista, sem valores na linha do vertice
+        let error = parse_ply(ply.as_bytes()).err().expect("lista vazia aceita");
+        assert!(error.contains("vazia"), "resultado inesperado {}", error);
+    }
+}
@@ -0,0 +1,665 @@
+use models::mesh_data::MeshData;
+use models::normals::NormalMesh;
+use models::obj_model::ObjModel;
+use models::resources::load_generated_mesh;
+use models::tangents::generate_tangents;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+// Primitivas geradas proceduralmente, sem arquivos .obj
+// Todas são centradas na origem, com o eixo Y para cima, triangulos em sentido anti-horario
+// vistos de fora, normais suaves (exceto em arestas vivas, onde os vertices são duplicados) e
+// coordenadas de textura entre 0 e 1. As funções *_mesh retornam a malha na CPU; as demais
+// retornam um ObjModel cuja malha é compartilhada por primitivas com os mesmos parametros
+
+#[allow(dead_code)]
+// Malha em construção
+struct PrimitiveBuilder {
+    mesh: NormalMesh,
+}
+
+#[allow(dead_code)]
+impl PrimitiveBuilder {
+    fn new() -> Self {
+        PrimitiveBuilder {
+            mesh: NormalMesh {
+                positions: Vec::new(),
+                normals: Vec::new(),
+                texcoords: Vec::new(),
+                colors: Vec::new(),
+                joints: Vec::new(),
+                weights: Vec::new(),
+                indices: Vec::new(),
+            },
+        }
+    }
+
+    fn vertex_count(&self) -> u32 {
+        (self.mesh.positions.len() / 3) as u32
+    }
+
+    // Adiciona um vertice e retorna seu indice
+    fn vertex(&mut self, position: glm::Vec3, normal: glm::Vec3, uv: glm::Vec2) -> u32 {
+        let index = self.vertex_count();
+        let normal = glm::normalize(normal);
+        self.mesh
+            .positions
+            .extend_from_slice(&[position.x, position.y, position.z]);
+        self.mesh
+            .normals
+            .extend_from_slice(&[normal.x, normal.y, normal.z]);
+        self.mesh.texcoords.extend_from_slice(&[uv.x, uv.y]);
+        index
+    }
+
+    fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        self.mesh.indices.extend_from_slice(&[a, b, c]);
+    }
+
+    // Grade de (columns + 1) x (rows + 1) vertices a partir de first, linha a linha
+    // A coluna cresce no sentido anti-horario e a linha "para cima" em relação a face visivel
+    fn grid(&mut self, first: u32, columns: u32, rows: u32) {
+        for row in 0..rows {
+            for column in 0..columns {
+                let a = first + row * (columns + 1) + column;
+                let b = a + 1;
+                let c = a + columns + 1;
+                let d = c + 1;
+                self.triangle(a, b, d);
+                self.triangle(a, d, c);
+            }
+        }
+    }
+
+    // Revolve um perfil em torno do eixo Y. Cada ponto do perfil é
+    // (raio, y, normal no plano (raio, y), v) e o perfil vai de cima para baixo
+    // A costura em u = 0 e u = 1 tem vertices duplicados
+    // Pontos com raio 0 (polos, ponta do cone) formam um leque: um triangulo por segmento, com o
+    // vertice do eixo no meio do segmento para receber a normal e o u do seu triangulo
+    fn lathe(&mut self, profile: &[(f32, f32, glm::Vec2, f32)], segments: u32) {
+        let first = self.vertex_count();
+        for &(radius, y, normal, v) in profile.iter().rev() {
+            let offset = if radius == 0.0 { 0.5 } else { 0.0 };
+            for segment in 0..=segments {
+                let u = (segment as f32 + offset) / segments as f32;
+                let (sin, cos) = (2.0 * PI * u).sin_cos();
+                self.vertex(
+                    glm::vec3(radius * sin, y, radius * cos),
+                    glm::vec3(normal.x * sin, normal.y, normal.x * cos),
+                    glm::vec2(u, v),
+                );
+            }
+        }
+
+        // Mesma ordem da grid, de baixo para cima
+        let radii: Vec<f32> = profile.iter().rev().map(|point| point.0).collect();
+        for row in 0..radii.len() as u32 - 1 {
+            for column in 0..segments {
+                let a = first + row * (segments + 1) + column;
+                let b = a + 1;
+                let c = a + segments + 1;
+                let d = c + 1;
+                if radii[row as usize + 1] == 0.0 {
+                    self.triangle(a, b, c);
+                } else if radii[row as usize] == 0.0 {
+                    self.triangle(a, d, c);
+                } else {
+                    self.triangle(a, b, d);
+                    self.triangle(a, d, c);
+                }
+            }
+        }
+    }
+
+    // Tampa circular em y, voltada para cima (up) ou para baixo
+    // Coordenadas de textura planares, com o disco inscrito no quadrado [0, 1]
+    fn cap(&mut self, y: f32, radius: f32, segments: u32, up: bool) {
+        let normal = glm::vec3(0.0, if up { 1.0 } else { -1.0 }, 0.0);
+        let center = self.vertex(glm::vec3(0.0, y, 0.0), normal, glm::vec2(0.5, 0.5));
+        for segment in 0..=segments {
+            let (sin, cos) = (2.0 * PI * segment as f32 / segments as f32).sin_cos();
+            let v = if up { 0.5 - 0.5 * cos } else { 0.5 + 0.5 * cos };
+            self.vertex(
+                glm::vec3(radius * sin, y, radius * cos),
+                normal,
+                glm::vec2(0.5 + 0.5 * sin, v),
+            );
+        }
+        for segment in 0..segments {
+            let a = center + 1 + segment;
+            if up {
+                self.triangle(center, a, a + 1);
+            } else {
+                self.triangle(center, a + 1, a);
+            }
+        }
+    }
+
+    fn build(self, name: &str) -> MeshData {
+        let mut mesh_data = MeshData::new();
+        mesh_data.push_mesh(name, &self.mesh, None);
+        generate_tangents(&mut mesh_data);
+        if let Err(error) = mesh_data.validate() {
+            panic!("{}", error);
+        }
+        mesh_data
+    }
+}
+
+// Esfera UV: segments divisões em torno do eixo Y e rings divisões de polo a polo
+#[allow(dead_code)]
+pub fn uv_sphere_mesh(radius: f32, segments: u32, rings: u32) -> MeshData {
+    let (segments, rings) = (segments.max(3), rings.max(2));
+    let profile: Vec<(f32, f32, glm::Vec2, f32)> = (0..=rings)
+        .map(|ring| {
+            let t = ring as f32 / rings as f32;
+            let (mut sin, cos) = (PI * t).sin_cos();
+            // sin(PI) não é exatamente 0, e os polos devem ficar no eixo
+            if ring == rings {
+                sin = 0.0;
+            }
+            (radius * sin, radius * cos, glm::vec2(sin, cos), 1.0 - t)
+        })
+        .collect();
+
+    let mut builder = PrimitiveBuilder::new();
+    builder.lathe(&profile, segments);
+    builder.build("uv_sphere")
+}
+
+// Icosfera: icosaedro com as faces subdivididas subdivisions vezes e projetado na esfera
+// Triangulos mais uniformes que os da esfera UV. As coordenadas de textura são esfericas,
+// com os vertices da costura e dos polos duplicados
+#[allow(dead_code)]
+pub fn icosphere_mesh(radius: f32, subdivisions: u32) -> MeshData {
+    let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let mut points: Vec<glm::Vec3> = [
+        (-1.0, t, 0.0),
+        (1.0, t, 0.0),
+        (-1.0, -t, 0.0),
+        (1.0, -t, 0.0),
+        (0.0, -1.0, t),
+        (0.0, 1.0, t),
+        (0.0, -1.0, -t),
+        (0.0, 1.0, -t),
+        (t, 0.0, -1.0),
+        (t, 0.0, 1.0),
+        (-t, 0.0, -1.0),
+        (-t, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| glm::normalize(glm::vec3(x, y, z)))
+    .collect();
+
+    let mut faces: Vec<[u32; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    // Cada triangulo é dividido em 4, com os pontos medios das arestas compartilhados
+    for _ in 0..subdivisions {
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32, points: &mut Vec<glm::Vec3>| {
+            let key = (a.min(b), a.max(b));
+            *midpoints.entry(key).or_insert_with(|| {
+                points.push(glm::normalize(
+                    (points[a as usize] + points[b as usize]) * 0.5,
+                ));
+                points.len() as u32 - 1
+            })
+        };
+
+        let mut subdivided = Vec::with_capacity(faces.len() * 4);
+        for &[a, b, c] in faces.iter() {
+            let ab = midpoint(a, b, &mut points);
+            let bc = midpoint(b, c, &mut points);
+            let ca = midpoint(c, a, &mut points);
+            subdivided.push([a, ab, ca]);
+            subdivided.push([b, bc, ab]);
+            subdivided.push([c, ca, bc]);
+            subdivided.push([ab, bc, ca]);
+        }
+        faces = subdivided;
+    }
+
+    // Mesmo mapeamento da esfera UV: u = 0 em +Z crescendo em direção a +X, v = 1 no polo norte
+    let spherical_uv = |p: &glm::Vec3| {
+        let mut u = p.x.atan2(p.z) / (2.0 * PI);
+        if u < 0.0 {
+            u = u + 1.0;
+        }
+        glm::vec2(u, 0.5 + p.y.max(-1.0).min(1.0).asin() / PI)
+    };
+    let is_pole = |p: &glm::Vec3| p.x * p.x + p.z * p.z < 1e-8;
+
+    let mut builder = PrimitiveBuilder::new();
+    for p in points.iter() {
+        builder.vertex(*p * radius, *p, spherical_uv(p));
+    }
+
+    // Triangulos que cruzam a costura usam copias dos vertices com u + 1, e os polos
+    // recebem um vertice por triangulo com u medio dos outros cantos
+    let mut seam_copies: HashMap<u32, u32> = HashMap::new();
+    for face in faces.iter() {
+        let mut corners = *face;
+        let uvs: Vec<glm::Vec2> = face
+            .iter()
+            .map(|i| spherical_uv(&points[*i as usize]))
+            .collect();
+        // O u dos polos é indefinido e não conta para a costura
+        let us_off_pole: Vec<f32> = (0..3)
+            .filter(|corner| !is_pole(&points[face[*corner] as usize]))
+            .map(|corner| uvs[corner].x)
+            .collect();
+        let max_u = us_off_pole.iter().cloned().fold(0.0, f32::max);
+        let min_u = us_off_pole.iter().cloned().fold(1.0, f32::min);
+        let crosses_seam = max_u - min_u > 0.5;
+
+        // Cantos do lado u < 0.5 de um triangulo na costura, incluindo os que estão sobre ela
+        // (u = 0), passam para u + 1
+        let wrapped: Vec<bool> = uvs.iter().map(|uv| crosses_seam && uv.x < 0.5).collect();
+        let us: Vec<f32> = (0..3)
+            .map(|corner| uvs[corner].x + if wrapped[corner] { 1.0 } else { 0.0 })
+            .collect();
+
+        for corner in 0..3 {
+            let point = points[face[corner] as usize];
+            if is_pole(&point) {
+                let others: Vec<usize> = (0..3).filter(|other| *other != corner).collect();
+                let u = (us[others[0]] + us[others[1]]) / 2.0;
+                let uv = glm::vec2(u, uvs[corner].y);
+                corners[corner] = builder.vertex(point * radius, point, uv);
+            } else if wrapped[corner] {
+                let original = face[corner];
+                let uv = glm::vec2(us[corner], uvs[corner].y);
+                corners[corner] = *seam_copies
+                    .entry(original)
+                    .or_insert_with(|| builder.vertex(point * radius, point, uv));
+            }
+        }
+        builder.triangle(corners[0], corners[1], corners[2]);
+    }
+
+    // Os vertices originais dos polos não são usados por nenhum triangulo, mas não afetam o
+    // desenho e mantém os indices dos demais
+    builder.build("icosphere")
+}
+
+// Caixa com as dimensões informadas. Cada face tem seus 4 vertices e a textura inteira
+#[allow(dead_code)]
+pub fn box_mesh(width: f32, height: f32, depth: f32) -> MeshData {
+    let half = glm::vec3(width / 2.0, height / 2.0, depth / 2.0);
+    // Normal e eixos u e v da face, com u x v = normal
+    let faces = [
+        (
+            glm::vec3(1.0, 0.0, 0.0),
+            glm::vec3(0.0, 0.0, -1.0),
+            glm::vec3(0.0, 1.0, 0.0),
+        ),
+        (
+            glm::vec3(-1.0, 0.0, 0.0),
+            glm::vec3(0.0, 0.0, 1.0),
+            glm::vec3(0.0, 1.0, 0.0),
+        ),
+        (
+            glm::vec3(0.0, 1.0, 0.0),
+            glm::vec3(1.0, 0.0, 0.0),
+            glm::vec3(0.0, 0.0, -1.0),
+        ),
+        (
+            glm::vec3(0.0, -1.0, 0.0),
+            glm::vec3(1.0, 0.0, 0.0),
+            glm::vec3(0.0, 0.0, 1.0),
+        ),
+        (
+            glm::vec3(0.0, 0.0, 1.0),
+            glm::vec3(1.0, 0.0, 0.0),
+            glm::vec3(0.0, 1.0, 0.0),
+        ),
+        (
+            glm::vec3(0.0, 0.0, -1.0),
+            glm::vec3(-1.0, 0.0, 0.0),
+            glm::vec3(0.0, 1.0, 0.0),
+        ),
+    ];
+
+    let mut builder = PrimitiveBuilder::new();
+    for &(normal, u_axis, v_axis) in faces.iter() {
+        let first = builder.vertex_count();
+        for &(u, v) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)].iter() {
+            let corner = normal + u_axis * (2.0 * u - 1.0) + v_axis * (2.0 * v - 1.0);
+            builder.vertex(corner * half, normal, glm::vec2(u, v));
+        }
+        builder.grid(first, 1, 1);
+    }
+    builder.build("box")
+}
+
+// Cilindro de altura height ao longo do eixo Y, com tampas
+#[allow(dead_code)]
+pub fn cylinder_mesh(radius: f32, height: f32, segments: u32) -> MeshData {
+    let segments = segments.max(3);
+    let side = glm::vec2(1.0, 0.0);
+    let profile = [
+        (radius, height / 2.0, side, 1.0),
+        (radius, -height / 2.0, side, 0.0),
+    ];
+
+    let mut builder = PrimitiveBuilder::new();
+    builder.lathe(&profile, segments);
+    builder.cap(height / 2.0, radius, segments, true);
+    builder.cap(-height / 2.0, radius, segments, false);
+    builder.build("cylinder")
+}
+
+// Cone de altura height ao longo do eixo Y, com a ponta para cima e base fechada
+// A ponta tem um vertice por segmento para que cada lado tenha a normal correta
+#[allow(dead_code)]
+pub fn cone_mesh(radius: f32, height: f32, segments: u32) -> MeshData {
+    let segments = segments.max(3);
+    let side = glm::normalize(glm::vec2(height, radius));
+    let profile = [
+        (0.0, height / 2.0, side, 1.0),
+        (radius, -height / 2.0, side, 0.0),
+    ];
+
+    let mut builder = PrimitiveBuilder::new();
+    builder.lathe(&profile, segments);
+    builder.cap(-height / 2.0, radius, segments, false);
+    builder.build("cone")
+}
+
+// Toro em torno do eixo Y. major_radius é a distancia do centro ao centro do tubo e
+// minor_radius o raio do tubo. u percorre o anel e v o tubo
+#[allow(dead_code)]
+pub fn torus_mesh(major_radius: f32, minor_radius: f32, segments: u32, sides: u32) -> MeshData {
+    let (segments, sides) = (segments.max(3), sides.max(3));
+
+    let mut builder = PrimitiveBuilder::new();
+    for side in 0..=sides {
+        let v = side as f32 / sides as f32;
+        let (tube_sin, tube_cos) = (2.0 * PI * v).sin_cos();
+        for segment in 0..=segments {
+            let u = segment as f32 / segments as f32;
+            let (sin, cos) = (2.0 * PI * u).sin_cos();
+            let normal = glm::vec3(sin * tube_cos, tube_sin, cos * tube_cos);
+            let center = glm::vec3(major_radius * sin, 0.0, major_radius * cos);
+            builder.vertex(center + normal * minor_radius, normal, glm::vec2(u, v));
+        }
+    }
+    builder.grid(0, segments, sides);
+    builder.build("torus")
+}
+
+// Plano no XZ voltado para +Y, dividido em subdivisions_x por subdivisions_z quadrados
+// v cresce em direção a -Z, como a textura vista de cima
+#[allow(dead_code)]
+pub fn plane_mesh(width: f32, depth: f32, subdivisions_x: u32, subdivisions_z: u32) -> MeshData {
+    let (columns, rows) = (subdivisions_x.max(1), subdivisions_z.max(1));
+
+    let mut builder = PrimitiveBuilder::new();
+    for row in 0..=rows {
+        let v = row as f32 / rows as f32;
+        for column in 0..=columns {
+            let u = column as f32 / columns as f32;
+            builder.vertex(
+                glm::vec3(width * (u - 0.5), 0.0, depth * (0.5 - v)),
+                glm::vec3(0.0, 1.0, 0.0),
+                glm::vec2(u, v),
+            );
+        }
+    }
+    builder.grid(0, columns, rows);
+    builder.build("plane")
+}
+
+// Capsula: cilindro de altura height ao longo do eixo Y com semiesferas nas pontas
+// A altura total é height + 2 * radius. rings é o numero de divisões de cada semiesfera, e v é
+// proporcional ao comprimento do perfil para que a textura não estique no cilindro
+#[allow(dead_code)]
+pub fn capsule_mesh(radius: f32, height: f32, segments: u32, rings: u32) -> MeshData {
+    let (segments, rings) = (segments.max(3), rings.max(1));
+    let length = PI * radius + height;
+    let mut profile = Vec::new();
+
+    for ring in 0..=rings {
+        let angle = PI / 2.0 * ring as f32 / rings as f32;
+        let (sin, cos) = angle.sin_cos();
+        let arc = radius * angle;
+        profile.push((
+            radius * sin,
+            height / 2.0 + radius * cos,
+            glm::vec2(sin, cos),
+            1.0 - arc / length,
+        ));
+    }
+    for ring in 0..=rings {
+        let angle = PI / 2.0 * (1.0 + ring as f32 / rings as f32);
+        let (mut sin, cos) = angle.sin_cos();
+        // Polo inferior no eixo, como na esfera UV
+        if ring == rings {
+            sin = 0.0;
+        }
+        let arc = radius * angle + height;
+        profile.push((
+            radius * sin,
+            -height / 2.0 + radius * cos,
+            glm::vec2(sin, cos),
+            1.0 - arc / length,
+        ));
+    }
+
+    let mut builder = PrimitiveBuilder::new();
+    builder.lathe(&profile, segments);
+    builder.build("capsule")
+}
+
+// Obj da primitiva gerada, com a malha compartilhada por chave
+fn primitive<F>(key: String, generate: F) -> ObjModel
+where
+    F: FnOnce() -> MeshData,
+{
+    ObjModel::from_gpu_mesh(load_generated_mesh(&key, generate))
+}
+
+#[allow(dead_code)]
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> ObjModel {
+    primitive(
+        format!("uv_sphere({},{},{})", radius, segments, rings),
+        || uv_sphere_mesh(radius, segments, rings),
+    )
+}
+
+#[allow(dead_code)]
+pub fn icosphere(radius: f32, subdivisions: u32) -> ObjModel {
+    primitive(format!("icosphere({},{})", radius, subdivisions), || {
+        icosphere_mesh(radius, subdivisions)
+    })
+}
+
+#[allow(dead_code)]
+pub fn cuboid(width: f32, height: f32, depth: f32) -> ObjModel {
+    primitive(format!("box({},{},{})", width, height, depth), || {
+        box_mesh(width, height, depth)
+    })
+}
+
+#[allow(dead_code)]
+pub fn cylinder(radius: f32, height: f32, segments: u32) -> ObjModel {
+    primitive(
+        format!("cylinder({},{},{})", radius, height, segments),
+        || cylinder_mesh(radius, height, segments),
+    )
+}
+
+#[allow(dead_code)]
+pub fn cone(radius: f32, height: f32, segments: u32) -> ObjModel {
+    primitive(format!("cone({},{},{})", radius, height, segments), || {
+        cone_mesh(radius, height, segments)
+    })
+}
+
+#[allow(dead_code)]
+pub fn torus(major_radius: f32, minor_radius: f32, segments: u32, sides: u32) -> ObjModel {
+    primitive(
+        format!(
+            "torus({},{},{},{})",
+            major_radius, minor_radius, segments, sides
+        ),
+        || torus_mesh(major_radius, minor_radius, segments, sides),
+    )
+}
+
+#[allow(dead_code)]
+pub fn plane(width: f32, depth: f32, subdivisions_x: u32, subdivisions_z: u32) -> ObjModel {
+    primitive(
+        format!(
+            "plane({},{},{},{})",
+            width, depth, subdivisions_x, subdivisions_z
+        ),
+        || plane_mesh(width, depth, subdivisions_x, subdivisions_z),
+    )
+}
+
+#[allow(dead_code)]
+pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> ObjModel {
+    primitive(
+        format!("capsule({},{},{},{})", radius, height, segments, rings),
+        || capsule_mesh(radius, height, segments, rings),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(mesh: &MeshData, vertex: usize) -> glm::Vec3 {
+        let p = mesh.position(vertex);
+        glm::vec3(p.x, p.y, p.z)
+    }
+
+    fn normal(mesh: &MeshData, vertex: usize) -> glm::Vec3 {
+        let n = mesh.normal(vertex);
+        glm::vec3(n.x, n.y, n.z)
+    }
+
+    // Normais unitarias, apontando para fora em relação ao ponto interno mais proximo do vertice,
+    // e triangulos sem area nula no sentido das normais
+    fn assert_outward<F>(mesh: &MeshData, inner_point: F)
+    where
+        F: Fn(glm::Vec3) -> glm::Vec3,
+    {
+        for v in 0..mesh.vertex_count() {
+            let n = normal(mesh, v);
+            let p = position(mesh, v);
+            assert!((glm::length(n) - 1.0).abs() < 1e-5, "normal {:?}", n);
+            assert!(
+                glm::dot(n, p - inner_point(p)) > 0.0,
+                "normal {:?} para dentro em {:?}",
+                n,
+                p
+            );
+        }
+
+        for triangle in mesh.indices.chunks(3) {
+            let [a, b, c] = [
+                position(mesh, triangle[0] as usize),
+                position(mesh, triangle[1] as usize),
+                position(mesh, triangle[2] as usize),
+            ];
+            let face = glm::cross(b - a, c - a);
+            assert!(
+                glm::length(face) > 1e-6,
+                "triangulo sem area {:?}",
+                triangle
+            );
+
+            let corners = normal(mesh, triangle[0] as usize)
+                + normal(mesh, triangle[1] as usize)
+                + normal(mesh, triangle[2] as usize);
+            assert!(
+                glm::dot(face, corners) > 0.0,
+                "triangulo invertido {:?}",
+                triangle
+            );
+        }
+    }
+
+    fn origin(_: glm::Vec3) -> glm::Vec3 {
+        glm::vec3(0.0, 0.0, 0.0)
+    }
+
+    // Ponto do eixo Y na altura do vertice, limitado a [-half_height, half_height]
+    fn axis(half_height: f32) -> impl Fn(glm::Vec3) -> glm::Vec3 {
+        move |p: glm::Vec3| glm::vec3(0.0, glm::clamp(p.y, -half_height, half_height), 0.0)
+    }
+
+    #[test]
+    fn spheres_point_outward() {
+        assert_outward(&uv_sphere_mesh(2.0, 16, 8), origin);
+        assert_outward(&uv_sphere_mesh(1000.0, 3, 2), origin);
+        assert_outward(&icosphere_mesh(1.5, 2), origin);
+    }
+
+    #[test]
+    fn box_points_outward() {
+        assert_outward(&box_mesh(1.0, 2.0, 3.0), origin);
+    }
+
+    #[test]
+    fn cylinder_and_cone_point_outward() {
+        assert_outward(&cylinder_mesh(0.5, 2.0, 12), axis(0.5));
+        // Na ponta e na base do cone o ponto interno é o centro
+        assert_outward(&cone_mesh(0.5, 2.0, 12), origin);
+    }
+
+    #[test]
+    fn torus_points_outward() {
+        assert_outward(&torus_mesh(2.0, 0.5, 16, 8), |p: glm::Vec3| {
+            glm::normalize(glm::vec3(p.x, 0.0, p.z)) * 2.0
+        });
+    }
+
+    #[test]
+    fn plane_points_up() {
+        let plane = plane_mesh(2.0, 3.0, 2, 4);
+        assert_outward(&plane, |p: glm::Vec3| p - glm::vec3(0.0, 1.0, 0.0));
+        for v in 0..plane.vertex_count() {
+            assert_eq!(normal(&plane, v), glm::vec3(0.0, 1.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn capsule_points_outward() {
+        assert_outward(&capsule_mesh(0.5, 1.0, 12, 4), axis(0.5));
+        assert_outward(&capsule_mesh(0.5, 0.2, 3, 1), axis(0.1));
+    }
+
+    #[test]
+    fn poles_are_fans() {
+        // Um triangulo por segmento nos anéis dos polos e dois nos demais
+        let sphere = uv_sphere_mesh(1.0, 16, 8);
+        assert_eq!(sphere.triangle_count(), 16 * (2 * 8 - 2));
+
+        let cone = cone_mesh(0.5, 2.0, 12);
+        assert_eq!(cone.triangle_count(), 12 + 12);
+    }
+}
@@ -0,0 +1,307 @@
+use glm::acos;
+use glm::asin;
+use glm::cos;
+use glm::sin;
+use glm::sqrt;
+use models::matrix::GLMatrix;
+use std::ops::Mul;
+
+// Abaixo desse valor de 1 - cos(theta) o slerp é aproximado por interpolação linear
+static SLERP_EPSILON: f32 = 1e-5;
+
+#[allow(dead_code)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+// Quaternion de rotação q = w + xi + yj + zk
+// Evita gimbal lock das rotações por angulos de euler e pode ser renormalizado para evitar
+// acumulo de erro numerico quando aplicado a cada frame
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+#[allow(dead_code)]
+impl Quaternion {
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Quaternion { x, y, z, w }
+    }
+
+    pub const fn identity() -> Self {
+        Quaternion::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    // Rotação de angle radianos ao redor do eixo axis (w do eixo é ignorado)
+    pub fn from_axis_angle(axis: glm::Vec4, angle: f32) -> Self {
+        let axis_norm = sqrt(axis.x * axis.x + axis.y * axis.y + axis.z * axis.z);
+        if axis_norm == 0.0 {
+            return Quaternion::identity();
+        }
+
+        let s = sin(angle / 2.0) / axis_norm;
+        Quaternion::new(axis.x * s, axis.y * s, axis.z * s, cos(angle / 2.0))
+    }
+
+    // Retorna eixo (com w = 0) e angulo da rotação
+    pub fn to_axis_angle(&self) -> (glm::Vec4, f32) {
+        let q = self.normalize();
+        let angle = 2.0 * acos(glm::clamp(q.w, -1.0, 1.0));
+        let s = sqrt(1.0 - q.w * q.w);
+
+        // Rotação nula, qualquer eixo serve
+        if s < SLERP_EPSILON {
+            return (glm::vec4(1.0, 0.0, 0.0, 0.0), angle);
+        }
+        (glm::vec4(q.x / s, q.y / s, q.z / s, 0.0), angle)
+    }
+
+    // Extrai a rotação de uma matriz ortonormal (sem escala), pelo metodo de Shepperd
+    pub fn from_rotation_matrix(matrix: &GLMatrix) -> Self {
+        let m = matrix.to_points();
+        let (r00, r01, r02) = (m[0], m[1], m[2]);
+        let (r10, r11, r12) = (m[4], m[5], m[6]);
+        let (r20, r21, r22) = (m[8], m[9], m[10]);
+        let trace = r00 + r11 + r22;
+
+        // Escolhe o maior componente como divisor para manter estabilidade numerica
+        let q = if trace > 0.0 {
+            let s = sqrt(trace + 1.0) * 2.0;
+            Quaternion::new((r21 - r12) / s, (r02 - r20) / s, (r10 - r01) / s, 0.25 * s)
+        } else if r00 > r11 && r00 > r22 {
+            let s = sqrt(1.0 + r00 - r11 - r22) * 2.0;
+            Quaternion::new(0.25 * s, (r01 + r10) / s, (r02 + r20) / s, (r21 - r12) / s)
+        } else if r11 > r22 {
+            let s = sqrt(1.0 + r11 - r00 - r22) * 2.0;
+            Quaternion::new((r01 + r10) / s, 0.25 * s, (r12 + r21) / s, (r02 - r20) / s)
+        } else {
+            let s = sqrt(1.0 + r22 - r00 - r11) * 2.0;
+            Quaternion::new((r02 + r20) / s, (r12 + r21) / s, 0.25 * s, (r10 - r01) / s)
+        };
+        q.normalize()
+    }
+
+    // Angulos de euler em radianos, aplicados na mesma ordem de
+    // rotate_x(x).rotate_y(y).rotate_z(z) da GLMatrix (X, depois Y, depois Z)
+    pub fn from_euler(x: f32, y: f32, z: f32) -> Self {
+        let qx = Quaternion::from_axis_angle(glm::vec4(1.0, 0.0, 0.0, 0.0), x);
+        let qy = Quaternion::from_axis_angle(glm::vec4(0.0, 1.0, 0.0, 0.0), y);
+        let qz = Quaternion::from_axis_angle(glm::vec4(0.0, 0.0, 1.0, 0.0), z);
+        qz * qy * qx
+    }
+
+    // Inverso de from_euler. Em gimbal lock (y = +-90 graus) o angulo z é fixado em 0
+    pub fn to_euler(&self) -> glm::Vec3 {
+        let q = self.normalize();
+
+        // Elementos da matriz de rotação necessarios para extrair os angulos
+        let r00 = 1.0 - 2.0 * (q.y * q.y + q.z * q.z);
+        let r01 = 2.0 * (q.x * q.y - q.w * q.z);
+        let r10 = 2.0 * (q.x * q.y + q.w * q.z);
+        let r11 = 1.0 - 2.0 * (q.x * q.x + q.z * q.z);
+        let r20 = 2.0 * (q.x * q.z - q.w * q.y);
+        let r21 = 2.0 * (q.y * q.z + q.w * q.x);
+        let r22 = 1.0 - 2.0 * (q.x * q.x + q.y * q.y);
+
+        let y = asin(glm::clamp(-r20, -1.0, 1.0));
+
+        // glm::atan2 inverte a ordem dos argumentos, utilizamos o atan2 da std
+        if r20 <= -1.0 + SLERP_EPSILON {
+            glm::vec3(r01.atan2(r11), y, 0.0)
+        } else if r20 >= 1.0 - SLERP_EPSILON {
+            glm::vec3((-r01).atan2(r11), y, 0.0)
+        } else {
+            glm::vec3(r21.atan2(r22), y, r10.atan2(r00))
+        }
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn length(&self) -> f32 {
+        sqrt(self.dot(self))
+    }
+
+    // Trata quaternion nulo da mesma forma que normalize_vector
+    pub fn normalize(&self) -> Self {
+        let length = self.length();
+        if length == 0.0 {
+            return *self;
+        }
+        Quaternion::new(
+            self.x / length,
+            self.y / length,
+            self.z / length,
+            self.w / length,
+        )
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Quaternion::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn inverse(&self) -> Self {
+        let length_sq = self.dot(self);
+        if length_sq == 0.0 {
+            return *self;
+        }
+        let c = self.conjugate();
+        Quaternion::new(
+            c.x / length_sq,
+            c.y / length_sq,
+            c.z / length_sq,
+            c.w / length_sq,
+        )
+    }
+
+    // Rotaciona um vetor ou ponto, preservando o w
+    pub fn rotate_vector(&self, v: glm::Vec4) -> glm::Vec4 {
+        let p = Quaternion::new(v.x, v.y, v.z, 0.0);
+        let r = *self * p * self.inverse();
+        glm::vec4(r.x, r.y, r.z, v.w)
+    }
+
+    // Interpolação esferica pelo menor caminho, t em [0..1]
+    pub fn slerp(&self, other: &Quaternion, t: f32) -> Self {
+        let a = self.normalize();
+        let mut b = other.normalize();
+
+        // q e -q representam a mesma rotação, inverte para seguir o menor arco
+        let mut cos_theta = a.dot(&b);
+        if cos_theta < 0.0 {
+            b = Quaternion::new(-b.x, -b.y, -b.z, -b.w);
+            cos_theta = -cos_theta;
+        }
+
+        let (wa, wb) = if 1.0 - cos_theta < SLERP_EPSILON {
+            // Quaternions quase iguais: sin(theta) tende a 0, usa interpolação linear
+            (1.0 - t, t)
+        } else {
+            let theta = acos(glm::clamp(cos_theta, -1.0, 1.0));
+            let sin_theta = sin(theta);
+            (
+                sin((1.0 - t) * theta) / sin_theta,
+                sin(t * theta) / sin_theta,
+            )
+        };
+
+        Quaternion::new(
+            a.x * wa + b.x * wb,
+            a.y * wa + b.y * wb,
+            a.z * wa + b.z * wb,
+            a.w * wa + b.w * wb,
+        )
+        .normalize()
+    }
+
+    // Matriz de rotação equivalente, no mesmo formato das funções de matrix.rs
+    pub fn to_matrix(&self) -> GLMatrix {
+        let q = self.normalize();
+        let (x, y, z, w) = (q.x, q.y, q.z, q.w);
+
+        GLMatrix::new([
+            1.0 - 2.0 * (y * y + z * z), // LINHA 1
+            2.0 * (x * y - w * z),
+            2.0 * (x * z + w * y),
+            0.0,
+            2.0 * (x * y + w * z), // LINHA 2
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - w * x),
+            0.0,
+            2.0 * (x * z - w * y), // LINHA 3
+            2.0 * (y * z + w * x),
+            1.0 - 2.0 * (x * x + y * y),
+            0.0,
+            0.0, // LINHA 4
+            0.0,
+            0.0,
+            1.0,
+        ])
+    }
+}
+
+// Produto de hamilton: (a * b) aplica primeiro a rotação b, depois a
+impl Mul<Quaternion> for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, b: Quaternion) -> Quaternion {
+        let a = self;
+        Quaternion::new(
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::matrix::rotation_matrix_z;
+
+    fn assert_quaternion_near(a: &Quaternion, b: &Quaternion) {
+        assert!(
+            (a.x - b.x).abs() < 1e-5
+                && (a.y - b.y).abs() < 1e-5
+                && (a.z - b.z).abs() < 1e-5
+                && (a.w - b.w).abs() < 1e-5,
+            "{:?} diferente de {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn axis_angle_round_trip() {
+        let axis = glm::normalize(glm::vec3(1.0, 2.0, -0.5));
+        let axis = glm::vec4(axis.x, axis.y, axis.z, 0.0);
+        for angle in [0.3, 1.5, 3.0].iter() {
+            let q = Quaternion::from_axis_angle(axis, *angle);
+            assert!((q.length() - 1.0).abs() < 1e-6);
+
+            let (result_axis, result_angle) = q.to_axis_angle();
+            assert!((result_angle - angle).abs() < 1e-5);
+            assert!(glm::distance(result_axis, axis) < 1e-5);
+        }
+
+        // Eixo não normalizado gera a mesma rotação
+        assert_quaternion_near(
+            &Quaternion::from_axis_angle(axis * 3.0, 1.5),
+            &Quaternion::from_axis_angle(axis, 1.5),
+        );
+    }
+
+    #[test]
+    fn axis_angle_matches_rotation_matrix() {
+        let q = Quaternion::from_axis_angle(glm::vec4(0.0, 0.0, 1.0, 0.0), 0.8);
+        let expected = rotation_matrix_z(0.8);
+        for (a, b) in q
+            .to_matrix()
+            .to_points()
+            .iter()
+            .zip(expected.to_points().iter())
+        {
+            assert!((a - b).abs() < 1e-6);
+        }
+        assert_quaternion_near(&Quaternion::from_rotation_matrix(&expected), &q);
+    }
+
+    #[test]
+    fn slerp_endpoints() {
+        let a = Quaternion::from_axis_angle(glm::vec4(0.0, 1.0, 0.0, 0.0), 0.2);
+        let b = Quaternion::from_axis_angle(glm::vec4(1.0, 0.0, 1.0, 0.0), 2.0);
+        assert_quaternion_near(&a.slerp(&b, 0.0), &a);
+        assert_quaternion_near(&a.slerp(&b, 1.0), &b);
+
+        // -b é a mesma rotação que b: o fim da interpolação é b pelo menor arco
+        let negated = Quaternion::new(-b.x, -b.y, -b.z, -b.w);
+        assert_quaternion_near(&a.slerp(&negated, 1.0), &b);
+
+        // Meio do caminho entre rotações no mesmo eixo
+        let axis = glm::vec4(0.0, 0.0, 1.0, 0.0);
+        let half = Quaternion::from_axis_angle(axis, 0.0)
+            .slerp(&Quaternion::from_axis_angle(axis, 1.0), 0.5);
+        assert_quaternion_near(&half, &Quaternion::from_axis_angle(axis, 0.5));
+    }
+}
@@ -0,0 +1,222 @@
+use models::matrix::cross_product;
+use models::matrix::normalize_vector;
+use models::matrix::GLMatrix;
+
+// Abaixo desse valor o raio é considerado paralelo ao triangulo ou a um eixo da bbox
+static RAY_EPSILON: f32 = 1e-7;
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone)]
+// Raio com origem (ponto, w = 1) e direção (vetor, w = 0)
+pub struct Ray {
+    pub origin: glm::Vec4,
+    pub direction: glm::Vec4,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone)]
+// Resultado de um raycast
+// distance -> Distancia da origem do raio até o ponto atingido, em coordenadas globais
+// point -> Ponto atingido
+// normal -> Normal da face atingida, voltada para a origem do raio
+// object_index -> Indice do obj simples atingido, na ordem de desenho (raiz = 0, filhos em seguida)
+pub struct RayHit {
+    pub distance: f32,
+    pub point: glm::Vec4,
+    pub normal: glm::Vec4,
+    pub object_index: usize,
+}
+
+#[allow(dead_code)]
+impl Ray {
+    // A direção é normalizada, para que o parametro t seja igual a distancia percorrida
+    pub fn new(origin: glm::Vec4, direction: glm::Vec4) -> Self {
+        Ray {
+            origin: glm::vec4(origin.x, origin.y, origin.z, 1.0),
+            direction: normalize_vector(glm::vec4(direction.x, direction.y, direction.z, 0.0)),
+        }
+    }
+
+    // Ponto do raio no parametro t
+    pub fn at(&self, t: f32) -> glm::Vec4 {
+        self.origin + self.direction * t
+    }
+
+    // Leva o raio para outro sistema de coordenadas sem normalizar a direção,
+    // preservando o parametro t dos pontos ao longo do raio
+    pub fn transform(&self, matrix: &GLMatrix) -> Self {
+        Ray {
+            origin: matrix.matrix * self.origin,
+            direction: matrix.matrix * self.direction,
+        }
+    }
+}
+
+// Interseção raio-AABB pelo metodo dos slabs
+// Retorna o intervalo (t_near, t_far) em que o raio está dentro da caixa, se existir
+#[allow(dead_code)]
+pub fn ray_bbox_intersection(
+    ray: &Ray,
+    bbox_min: &glm::Vec4,
+    bbox_max: &glm::Vec4,
+) -> Option<(f32, f32)> {
+    let mut t_near = std::f32::MIN;
+    let mut t_far = std::f32::MAX;
+
+    let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+    let direction = [ray.direction.x, ray.direction.y, ray.direction.z];
+    let min = [bbox_min.x, bbox_min.y, bbox_min.z];
+    let max = [bbox_max.x, bbox_max.y, bbox_max.z];
+
+    for axis in 0..3 {
+        if direction[axis].abs() < RAY_EPSILON {
+            // Raio paralelo ao slab: só intersecta se a origem estiver entre os planos
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+        } else {
+            let inv_dir = 1.0 / direction[axis];
+            let mut t0 = (min[axis] - origin[axis]) * inv_dir;
+            let mut t1 = (max[axis] - origin[axis]) * inv_dir;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            t_near = glm::max(t_near, t0);
+            t_far = glm::min(t_far, t1);
+            if t_near > t_far {
+                return None;
+            }
+        }
+    }
+
+    // Caixa inteiramente atrás da origem
+    if t_far < 0.0 {
+        return None;
+    }
+    Some((t_near, t_far))
+}
+
+// Interseção raio-triangulo de Möller–Trumbore
+// Retorna o parametro t do ponto atingido, considerando as duas faces do triangulo
+#[allow(dead_code)]
+pub fn ray_triangle_intersection(
+    ray: &Ray,
+    p0: &glm::Vec4,
+    p1: &glm::Vec4,
+    p2: &glm::Vec4,
+) -> Option<f32> {
+    let edge1 = *p1 - *p0;
+    let edge2 = *p2 - *p0;
+
+    let p = cross_product(ray.direction, edge2);
+    let det = edge1.x * p.x + edge1.y * p.y + edge1.z * p.z;
+
+    if det.abs() < RAY_EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    // Coordenadas baricentricas u e v do ponto atingido
+    let s = ray.origin - *p0;
+    let u = (s.x * p.x + s.y * p.y + s.z * p.z) * inv_det;
+    if u < 0.0 || u > 1.0 {
+        return None;
+    }
+
+    let q = cross_product(s, edge1);
+    let v = (ray.direction.x * q.x + ray.direction.y * q.y + ray.direction.z * q.z) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = (edge2.x * q.x + edge2.y * q.y + edge2.z * q.z) * inv_det;
+    if t < 0.0 {
+        return None;
+    }
+    Some(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ray(origin: (f32, f32, f32), direction: (f32, f32, f32)) -> Ray {
+        Ray::new(
+            glm::vec4(origin.0, origin.1, origin.2, 1.0),
+            glm::vec4(direction.0, direction.1, direction.2, 0.0),
+        )
+    }
+
+    fn unit_box(ray: &Ray) -> Option<(f32, f32)> {
+        ray_bbox_intersection(
+            ray,
+            &glm::vec4(-1.0, -1.0, -1.0, 1.0),
+            &glm::vec4(1.0, 1.0, 1.0, 1.0),
+        )
+    }
+
+    fn assert_interval(result: Option<(f32, f32)>, expected: (f32, f32)) {
+        let (t_near, t_far) = result.expect("raio sem interseção");
+        assert!(
+            (t_near - expected.0).abs() < 1e-5 && (t_far - expected.1).abs() < 1e-5,
+            "resultado inesperado {:?}",
+            (t_near, t_far)
+        );
+    }
+
+    #[test]
+    fn bbox_axis_parallel_rays() {
+        // Paralelo a x e y, dentro dos slabs desses eixos
+        assert_interval(
+            unit_box(&ray((0.5, -0.5, 5.0), (0.0, 0.0, -1.0))),
+            (4.0, 6.0),
+        );
+        // Paralelo a x e y, fora do slab de x
+        assert!(unit_box(&ray((1.5, 0.0, 5.0), (0.0, 0.0, -1.0))).is_none());
+        // Diagonal que passa ao lado da caixa
+        assert!(unit_box(&ray((3.0, 0.0, 5.0), (0.0, 1.0, -1.0))).is_none());
+    }
+
+    #[test]
+    fn bbox_origin_inside_and_behind() {
+        // Origem dentro da caixa: t_near negativo, t_far na saida
+        assert_interval(
+            unit_box(&ray((0.0, 0.0, 0.5), (0.0, 0.0, 1.0))),
+            (-1.5, 0.5),
+        );
+        // Caixa atras do raio
+        assert!(unit_box(&ray((0.0, 0.0, 5.0), (0.0, 0.0, 1.0))).is_none());
+    }
+
+    // Triangulo no plano z = 0, com a face da frente voltada para +z
+    fn triangle(ray: &Ray) -> Option<f32> {
+        ray_triangle_intersection(
+            ray,
+            &glm::vec4(0.0, 0.0, 0.0, 1.0),
+            &glm::vec4(1.0, 0.0, 0.0, 1.0),
+            &glm::vec4(0.0, 1.0, 0.0, 1.0),
+        )
+    }
+
+    #[test]
+    fn triangle_hit_and_edge_miss() {
+        let t = triangle(&ray((0.25, 0.25, 2.0), (0.0, 0.0, -1.0))).unwrap();
+        assert!((t - 2.0).abs() < 1e-5, "resultado inesperado {}", t);
+
+        // Logo além da hipotenusa (u + v > 1) e dos catetos (u < 0, v < 0)
+        assert!(triangle(&ray((0.51, 0.51, 2.0), (0.0, 0.0, -1.0))).is_none());
+        assert!(triangle(&ray((-0.01, 0.5, 2.0), (0.0, 0.0, -1.0))).is_none());
+        assert!(triangle(&ray((0.5, -0.01, 2.0), (0.0, 0.0, -1.0))).is_none());
+
+        // Raio paralelo ao plano e triangulo atras do raio
+        assert!(triangle(&ray((0.25, 0.25, 2.0), (1.0, 0.0, 0.0))).is_none());
+        assert!(triangle(&ray((0.25, 0.25, 2.0), (0.0, 0.0, 1.0))).is_none());
+    }
+
+    #[test]
+    fn triangle_back_face_hit() {
+        let t = triangle(&ray((0.25, 0.25, -3.0), (0.0, 0.0, 1.0))).unwrap();
+        assert!((t - 3.0).abs() < 1e-5, "resultado inesperado {}", t);
+    }
+}
@@ -0,0 +1,657 @@
+use gl::types::GLenum;
+use gl::types::GLfloat;
+use gl::types::GLsizeiptr;
+use gl::types::GLuint;
+use models::load_texture::decode_texture;
+use models::load_texture::decode_texture_from_memory;
+use models::load_texture::upload_texture;
+use models::load_texture::DecodedTexture;
+use models::load_texture::TextureError;
+use models::load_texture::TextureUsage;
+use models::material::Material;
+use models::material::MaterialData;
+use models::mesh_cache::load_cached;
+use models::mesh_data::MeshData;
+use models::mesh_data::MeshError;
+use models::normals::NormalMode;
+use models::obj_model::Submesh;
+use models::primitives::box_mesh;
+use models::sampler::load_sampler;
+use models::sampler::SamplerDesc;
+use models::sampler::SamplerHandle;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::fmt;
+use std::mem;
+use std::ptr::null;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+use std::thread;
+use std::thread::ThreadId;
+
+// Handles compartilhados dos recursos na GPU. Clonar um handle apenas incrementa a contagem
+// de referencias, e os objetos OpenGL são deletados quando o ultimo handle é descartado.
+// Objs da cena podem ser lidos e transformados em paralelo (ex: intersecções com rayon), então o
+// ultimo handle pode ser descartado fora da thread do contexto OpenGL. Nesse caso a deleção é
+// adiada até a proxima chamada de collect_resources, ver delete_gl_object
+pub type MeshHandle = Arc<GpuMesh>;
+pub type TextureHandle = Arc<Texture>;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+// Objeto OpenGL a ser deletado quando o seu dono é descartado
+pub enum GlObject {
+    Buffer(u32),
+    VertexArray(u32),
+    Texture(u32),
+    Sampler(u32),
+}
+
+impl GlObject {
+    fn id(&self) -> u32 {
+        match *self {
+            GlObject::Buffer(id)
+            | GlObject::VertexArray(id)
+            | GlObject::Texture(id)
+            | GlObject::Sampler(id) => id,
+        }
+    }
+
+    unsafe fn delete(&self) {
+        match *self {
+            GlObject::Buffer(id) => gl::DeleteBuffers(1, &id),
+            GlObject::VertexArray(id) => gl::DeleteVertexArrays(1, &id),
+            GlObject::Texture(id) => gl::DeleteTextures(1, &id),
+            GlObject::Sampler(id) => gl::DeleteSamplers(1, &id),
+        }
+    }
+}
+
+// Objetos descartados fora da thread do contexto OpenGL, aguardando a deleção
+static DELETION_QUEUE: Mutex<Vec<GlObject>> = Mutex::new(Vec::new());
+
+// Deleta o objeto se a thread atual for a thread do contexto OpenGL que o criou (gl_thread)
+// Em outras threads, onde chamadas OpenGL não tem contexto, o objeto entra na fila de deleção
+pub fn delete_gl_object(object: GlObject, gl_thread: ThreadId) {
+    if object.id() == 0 {
+        return;
+    }
+    if thread::current().id() == gl_thread {
+        unsafe { object.delete() };
+    } else {
+        DELETION_QUEUE.lock().unwrap().push(object);
+    }
+}
+
+// Deleta os objetos da fila de deleção. Deve ser chamada na thread do contexto OpenGL
+// Retorna a quantidade de objetos deletados
+fn delete_queued_gl_objects() -> usize {
+    let queued = mem::replace(&mut *DELETION_QUEUE.lock().unwrap(), Vec::new());
+    for object in queued.iter() {
+        unsafe { object.delete() };
+    }
+    queued.len()
+}
+
+#[allow(dead_code)]
+// Malha enviada para a GPU
+// vao -> Endereço da vao da malha, compartilhada por todos os objs que usam a malha
+// ebo -> Indices da malha
+// texture,normal,geometry,color,tangent,joint,weight vbo -> Auto descritivo
+// index_len -> Tamanho do indice dos vertices
+// mesh -> Malha mantida na CPU para consultas como raycast
+// submeshes -> Submeshes com os materiais (e texturas) da malha
+// lod_ranges -> Offset e quantidade de indices de cada submesh em cada nivel de detalhe da malha.
+//               Os indices dos niveis ficam no mesmo buffer, após os indices da malha completa
+// pending -> Chave (ver ResourceManager::mesh_key) da malha em carregamento assincrono que esta
+//            malha substitui temporariamente, ver asset_loader.rs. None em malhas carregadas
+// gl_thread -> Thread do contexto OpenGL em que os buffers foram criados
+pub struct GpuMesh {
+    pub vao: u32,
+    ebo: u32,
+    geometry_vbo: u32,
+    texture_vbo: u32,
+    color_vbo: u32,
+    normal_vbo: u32,
+    tangent_vbo: u32,
+    joint_vbo: u32,
+    weight_vbo: u32,
+    pub index_len: usize,
+    pub mesh: Arc<MeshData>,
+    pub submeshes: Arc<Vec<Submesh>>,
+    pub lod_ranges: Vec<Vec<(usize, usize)>>,
+    pub pending: Option<String>,
+    gl_thread: ThreadId,
+}
+
+// Não imprime a malha, apenas os identificadores na GPU
+impl fmt::Debug for GpuMesh {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "GpuMesh {{ vao: {}, index_len: {}, mesh: {:?} }}",
+            self.vao, self.index_len, self.mesh
+        )
+    }
+}
+
+#[allow(dead_code)]
+impl GpuMesh {
+    // Envia uma malha carregada na CPU para a GPU, carregando as texturas dos materiais
+    pub fn upload(mesh: Arc<MeshData>) -> Self {
+        GpuMesh::upload_with_materials(mesh, Material::from_data)
+    }
+
+    // Como upload, criando os materiais com a função informada (ex: Material::request, que
+    // decodifica as texturas fora da thread do contexto)
+    pub fn upload_with_materials<F>(mesh: Arc<MeshData>, load_material: F) -> Self
+    where
+        F: Fn(&MaterialData) -> Material,
+    {
+        let materials: Vec<Material> = mesh.materials.iter().map(load_material).collect();
+        let submeshes: Vec<Submesh> = mesh
+            .ranges
+            .iter()
+            .map(|range| Submesh {
+                name: range.name.clone(),
+                index_offset: range.index_offset,
+                index_count: range.index_count,
+                material: range.material.map(|id| materials[id].clone()),
+                color_override: glm::vec3(0.0, 0.0, 0.0),
+            })
+            .collect();
+
+        // Niveis de detalhe compartilham os vertices: apenas os indices são adicionados ao buffer
+        let mut indices = mesh.indices.clone();
+        let mut lod_ranges = Vec::new();
+        for lod in mesh.lods.iter() {
+            let base = indices.len();
+            indices.extend_from_slice(&lod.indices);
+            lod_ranges.push(
+                lod.ranges
+                    .iter()
+                    .map(|&(offset, count)| (base + offset, count))
+                    .collect(),
+            );
+        }
+
+        let mut gpu_mesh = GpuMesh {
+            vao: 0u32,
+            ebo: 0u32,
+            geometry_vbo: 0u32,
+            texture_vbo: 0u32,
+            color_vbo: 0u32,
+            normal_vbo: 0u32,
+            tangent_vbo: 0u32,
+            joint_vbo: 0u32,
+            weight_vbo: 0u32,
+            index_len: mesh.indices.len(),
+            mesh: mesh.clone(),
+            submeshes: Arc::new(submeshes),
+            lod_ranges,
+            pending: None,
+            gl_thread: thread::current().id(),
+        };
+
+        // Alocação de VAO e VBOS
+        unsafe {
+            // Definição dos atributos dos vertices
+            // Cria VAO da malha e "liga" ela
+            gl::GenVertexArrays(1, &mut gpu_mesh.vao);
+            gl::BindVertexArray(gpu_mesh.vao);
+
+            // Posições, location 0 no vertex shader
+            gpu_mesh.geometry_vbo = upload_attribute(&mesh.positions, 0, 4);
+
+            //Normais:
+            if mesh.normals.len() > 0 {
+                gpu_mesh.normal_vbo = upload_attribute(&mesh.normals, 1, 4);
+            }
+
+            // Coordenadas de textura, 2 valores (U V) por vertice
+            if mesh.texcoords.len() > 0 {
+                gpu_mesh.texture_vbo = upload_attribute(&mesh.texcoords, 2, 2);
+            }
+
+            // Cores dos vertices, 4 valores (R G B A) por vertice
+            if mesh.colors.len() > 0 {
+                gpu_mesh.color_vbo = upload_attribute(&mesh.colors, 3, 4);
+            }
+
+            // Tangentes, 4 valores (X Y Z e sinal da bitangente) por vertice
+            if mesh.tangents.len() > 0 {
+                gpu_mesh.tangent_vbo = upload_attribute(&mesh.tangents, 4, 4);
+            }
+
+            // Juntas (inteiros, location 14) e pesos (location 15) das malhas com esqueleto
+            // Locations 5 a 13 são dos atributos por instancia, ver instanced.rs
+            if mesh.weights.len() > 0 {
+                gpu_mesh.joint_vbo = upload_joints(&mesh.joints, 14);
+                gpu_mesh.weight_vbo = upload_attribute(&mesh.weights, 15, 4);
+            }
+
+            // Topolgia:
+            gl::GenBuffers(1, &mut gpu_mesh.ebo);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, gpu_mesh.ebo);
+
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                (indices.len() * mem::size_of::<u32>()) as GLsizeiptr,
+                null(),
+                gl::STATIC_DRAW,
+            );
+            gl::BufferSubData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                0,
+                (indices.len() * mem::size_of::<u32>()) as GLsizeiptr,
+                indices.as_ptr() as *const c_void,
+            );
+            gl::BindVertexArray(0);
+        }
+
+        gpu_mesh
+    }
+
+    // Caixa unitaria desenhada no lugar da malha com a chave informada enquanto ela é carregada
+    pub fn placeholder(key: &str) -> Self {
+        let mut gpu_mesh = GpuMesh::upload(Arc::new(box_mesh(1.0, 1.0, 1.0)));
+        gpu_mesh.pending = Some(key.to_string());
+        gpu_mesh
+    }
+
+    // Quantidade de niveis de detalhe, incluindo a malha completa (nivel 0)
+    pub fn lod_count(&self) -> usize {
+        1 + self.lod_ranges.len()
+    }
+
+    // Offset e quantidade de indices do submesh no nivel de detalhe informado
+    pub fn submesh_range(&self, lod: usize, submesh: usize) -> (usize, usize) {
+        if lod == 0 || lod > self.lod_ranges.len() {
+            let submesh = &self.submeshes[submesh];
+            (submesh.index_offset, submesh.index_count)
+        } else {
+            self.lod_ranges[lod - 1][submesh]
+        }
+    }
+}
+
+// Cria um VBO com os valores do atributo e liga ao location do shader na VAO atual
+unsafe fn upload_attribute(values: &[f32], location: GLuint, size: i32) -> u32 {
+    let mut vbo = 0;
+    gl::GenBuffers(1, &mut vbo);
+    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+    // Aloca memória para o VBO e copia os valores
+    gl::BufferData(
+        gl::ARRAY_BUFFER,
+        (values.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+        null(),
+        gl::STATIC_DRAW,
+    );
+    gl::BufferSubData(
+        gl::ARRAY_BUFFER,
+        0,
+        (values.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+        values.as_ptr() as *const c_void,
+    );
+
+    // "Liga" VAO e VBO e ativa o atributo
+    gl::VertexAttribPointer(location, size, gl::FLOAT, gl::FALSE, 0, null());
+    gl::EnableVertexAttribArray(location);
+    gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+    vbo
+}
+
+// Como upload_attribute, para os indices das juntas: 4 inteiros por vertice, lidos como uvec4
+unsafe fn upload_joints(joints: &[u16], location: GLuint) -> u32 {
+    let mut vbo = 0;
+    gl::GenBuffers(1, &mut vbo);
+    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+    gl::BufferData(
+        gl::ARRAY_BUFFER,
+        (joints.len() * mem::size_of::<u16>()) as GLsizeiptr,
+        joints.as_ptr() as *const c_void,
+        gl::STATIC_DRAW,
+    );
+
+    // Atributo inteiro: sem conversão para float
+    gl::VertexAttribIPointer(location, 4, gl::UNSIGNED_SHORT, 0, null());
+    gl::EnableVertexAttribArray(location);
+    gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+    vbo
+}
+
+// Libera os buffers quando o ultimo obj que usa a malha é descartado
+impl Drop for GpuMesh {
+    fn drop(&mut self) {
+        let buffers = [
+            self.ebo,
+            self.geometry_vbo,
+            self.texture_vbo,
+            self.color_vbo,
+            self.normal_vbo,
+            self.tangent_vbo,
+            self.joint_vbo,
+            self.weight_vbo,
+        ];
+        for buffer in buffers.iter() {
+            delete_gl_object(GlObject::Buffer(*buffer), self.gl_thread);
+        }
+        delete_gl_object(GlObject::VertexArray(self.vao), self.gl_thread);
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+// Textura e sampler na GPU
+// target -> Tipo da textura: TEXTURE_2D ou TEXTURE_CUBE_MAP
+// O sampler é compartilhado com as texturas de descritor identico (ver sampler.rs), e pode ser
+// trocado com a textura em uso: todos os handles da textura passam a utilizar o novo sampler
+// O Mutex mantém a textura acessivel pelas threads que leem os objs da cena
+// gl_thread -> Thread do contexto OpenGL em que a textura foi criada
+// flip_uv -> Se a imagem está na orientação do arquivo, sem o giro de 180 graus esperado pelos
+//            shaders (ver DecodedTexture::flip_uv). Atualizado com a imagem, ver asset_loader.rs
+pub struct Texture {
+    pub id: u32,
+    pub target: GLenum,
+    sampler: Mutex<SamplerHandle>,
+    gl_thread: ThreadId,
+    flip_uv: AtomicBool,
+}
+
+#[allow(dead_code)]
+impl Texture {
+    pub fn new(id: u32, sampler: &SamplerDesc) -> Self {
+        Texture {
+            id,
+            target: gl::TEXTURE_2D,
+            sampler: Mutex::new(load_sampler(sampler)),
+            gl_thread: thread::current().id(),
+            flip_uv: AtomicBool::new(false),
+        }
+    }
+
+    // Textura de cubemap, com 6 faces (ver load_cubemap.rs)
+    pub fn cubemap(id: u32, sampler: &SamplerDesc) -> Self {
+        Texture {
+            id,
+            target: gl::TEXTURE_CUBE_MAP,
+            sampler: Mutex::new(load_sampler(sampler)),
+            gl_thread: thread::current().id(),
+            flip_uv: AtomicBool::new(false),
+        }
+    }
+
+    // Sampler object do OpenGL atual da textura
+    pub fn sampler(&self) -> u32 {
+        self.sampler.lock().unwrap().id
+    }
+
+    pub fn sampler_desc(&self) -> SamplerDesc {
+        self.sampler.lock().unwrap().desc
+    }
+
+    // Troca os parametros de amostragem da textura
+    pub fn set_sampler(&self, sampler: &SamplerDesc) {
+        if self.sampler_desc() != *sampler {
+            *self.sampler.lock().unwrap() = load_sampler(sampler);
+        }
+    }
+
+    // Verdadeiro se os shaders devem amostrar a textura com U e V invertidos
+    pub fn flip_uv(&self) -> bool {
+        self.flip_uv.load(Ordering::Relaxed)
+    }
+
+    pub fn set_flip_uv(&self, flip_uv: bool) {
+        self.flip_uv.store(flip_uv, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        delete_gl_object(GlObject::Texture(self.id), self.gl_thread);
+    }
+}
+
+#[allow(dead_code)]
+// Registro dos recursos carregados por caminho. Guarda apenas referencias fracas:
+// quem mantém o recurso vivo são os handles, e um recurso descartado é carregado de novo
+// no proximo pedido
+pub struct ResourceManager {
+    meshes: HashMap<String, Weak<GpuMesh>>,
+    textures: HashMap<String, Weak<Texture>>,
+}
+
+thread_local! {
+    // Recursos da thread do contexto OpenGL
+    static RESOURCES: RefCell<ResourceManager> = RefCell::new(ResourceManager::new());
+}
+
+#[allow(dead_code)]
+impl ResourceManager {
+    pub fn new() -> Self {
+        ResourceManager {
+            meshes: HashMap::new(),
+            textures: HashMap::new(),
+        }
+    }
+
+    // Malhas com modos de normais diferentes são recursos diferentes
+    pub fn mesh_key(path: &str, normal_mode: NormalMode) -> String {
+        format!("{}#{:?}", path, normal_mode)
+    }
+
+    pub fn mesh(&self, path: &str, normal_mode: NormalMode) -> Option<MeshHandle> {
+        self.meshes
+            .get(&ResourceManager::mesh_key(path, normal_mode))
+            .and_then(|mesh| mesh.upgrade())
+    }
+
+    pub fn insert_mesh(&mut self, path: &str, normal_mode: NormalMode, mesh: &MeshHandle) {
+        self.meshes.insert(
+            ResourceManager::mesh_key(path, normal_mode),
+            Arc::downgrade(mesh),
+        );
+    }
+
+    pub fn texture(&self, path: &str) -> Option<TextureHandle> {
+        self.textures
+            .get(path)
+            .and_then(|texture| texture.upgrade())
+    }
+
+    pub fn insert_texture(&mut self, path: &str, texture: &TextureHandle) {
+        self.textures
+            .insert(path.to_string(), Arc::downgrade(texture));
+    }
+
+    // Deleta os objetos OpenGL descartados em outras threads e remove as entradas de recursos
+    // já descartados. Deve ser chamada na thread do contexto OpenGL
+    pub fn collect(&mut self) {
+        delete_queued_gl_objects();
+        self.meshes.retain(|_, mesh| mesh.upgrade().is_some());
+        self.textures
+            .retain(|_, texture| texture.upgrade().is_some());
+    }
+
+    // Quantidade de malhas e texturas ainda vivas
+    pub fn live_count(&self) -> (usize, usize) {
+        (
+            self.meshes
+                .values()
+                .filter(|mesh| mesh.upgrade().is_some())
+                .count(),
+            self.textures
+                .values()
+                .filter(|texture| texture.upgrade().is_some())
+                .count(),
+        )
+    }
+}
+
+// Carrega uma malha uma unica vez por caminho e modo de normais
+// Enquanto existir um handle, novos pedidos reutilizam os mesmos buffers na GPU
+#[allow(dead_code)]
+pub fn load_mesh(path: &str, normal_mode: NormalMode) -> Result<MeshHandle, MeshError> {
+    if let Some(mesh) = RESOURCES.with(|resources| resources.borrow().mesh(path, normal_mode)) {
+        return Ok(mesh);
+    }
+
+    // Materiais podem carregar texturas pelo gerenciador, então o registro não fica
+    // emprestado durante o carregamento
+    let mesh = Arc::new(GpuMesh::upload(Arc::new(load_cached(path, normal_mode)?)));
+    RESOURCES.with(|resources| resources.borrow_mut().insert_mesh(path, normal_mode, &mesh));
+    Ok(mesh)
+}
+
+// Malha gerada proceduralmente (ex: models::primitives), compartilhada pela chave informada
+// generate só é chamada se não houver um handle vivo para a chave
+#[allow(dead_code)]
+pub fn load_generated_mesh<F>(key: &str, generate: F) -> MeshHandle
+where
+    F: FnOnce() -> MeshData,
+{
+    let key = format!("generated:{}", key);
+    if let Some(mesh) =
+        RESOURCES.with(|resources| resources.borrow().mesh(&key, NormalMode::Imported))
+    {
+        return mesh;
+    }
+
+    let mesh = Arc::new(GpuMesh::upload(Arc::new(generate())));
+    RESOURCES.with(|resources| {
+        resources
+            .borrow_mut()
+            .insert_mesh(&key, NormalMode::Imported, &mesh)
+    });
+    mesh
+}
+
+// Carrega uma textura de cor uma unica vez por caminho
+#[allow(dead_code)]
+pub fn load_texture_resource(path: &str) -> Result<TextureHandle, TextureError> {
+    load_texture_resource_with(path, path, TextureUsage::Color)
+}
+
+// Carrega uma textura de cor com os parametros de amostragem informados
+// A textura é compartilhada por caminho: se já estiver carregada, passa a utilizar o sampler
+#[allow(dead_code)]
+pub fn load_texture_resource_with_sampler(
+    path: &str,
+    sampler: &SamplerDesc,
+) -> Result<TextureHandle, TextureError> {
+    let texture = load_texture_resource(path)?;
+    texture.set_sampler(sampler);
+    Ok(texture)
+}
+
+// Carrega um normal map uma unica vez por caminho. Normal maps são texturas lineares, então são
+// recursos diferentes da mesma imagem carregada como cor
+#[allow(dead_code)]
+pub fn load_normal_map_resource(path: &str) -> Result<TextureHandle, TextureError> {
+    load_texture_resource_with(&format!("{}#linear", path), path, TextureUsage::Data)
+}
+
+fn load_texture_resource_with(
+    key: &str,
+    path: &str,
+    usage: TextureUsage,
+) -> Result<TextureHandle, TextureError> {
+    if let Some(texture) = RESOURCES.with(|resources| resources.borrow().texture(key)) {
+        return Ok(texture);
+    }
+
+    let texture = upload_texture_resource(&decode_texture(path, usage)?);
+    RESOURCES.with(|resources| resources.borrow_mut().insert_texture(key, &texture));
+    Ok(texture)
+}
+
+// Texturas embutidas em arquivos de modelo não tem caminho e não são compartilhadas
+#[allow(dead_code)]
+pub fn load_texture_resource_from_memory(bytes: &[u8]) -> Result<TextureHandle, TextureError> {
+    Ok(upload_texture_resource(&decode_texture_from_memory(
+        bytes,
+        TextureUsage::Color,
+    )?))
+}
+
+#[allow(dead_code)]
+pub fn load_normal_map_resource_from_memory(bytes: &[u8]) -> Result<TextureHandle, TextureError> {
+    Ok(upload_texture_resource(&decode_texture_from_memory(
+        bytes,
+        TextureUsage::Data,
+    )?))
+}
+
+// Envia a imagem para uma nova textura, com a orientação da imagem (ver Texture::flip_uv)
+fn upload_texture_resource(image: &DecodedTexture) -> TextureHandle {
+    let id = unsafe { upload_texture(image) };
+    let texture = Arc::new(Texture::new(id, &SamplerDesc::new()));
+    texture.set_flip_uv(image.flip_uv());
+    texture
+}
+
+// Malha já carregada pelo gerenciador, sem carregar o arquivo se ela não existir
+#[allow(dead_code)]
+pub fn cached_mesh(path: &str, normal_mode: NormalMode) -> Option<MeshHandle> {
+    RESOURCES.with(|resources| resources.borrow().mesh(path, normal_mode))
+}
+
+// Registra uma malha carregada fora do gerenciador (ex: asset_loader.rs), compartilhando-a com
+// os proximos pedidos do mesmo caminho
+#[allow(dead_code)]
+pub fn register_mesh(path: &str, normal_mode: NormalMode, mesh: &MeshHandle) {
+    RESOURCES.with(|resources| resources.borrow_mut().insert_mesh(path, normal_mode, mesh));
+}
+
+#[allow(dead_code)]
+pub fn cached_texture(key: &str) -> Option<TextureHandle> {
+    RESOURCES.with(|resources| resources.borrow().texture(key))
+}
+
+#[allow(dead_code)]
+pub fn register_texture(key: &str, texture: &TextureHandle) {
+    RESOURCES.with(|resources| resources.borrow_mut().insert_texture(key, texture));
+}
+
+// Libera os recursos descartados desde a ultima chamada. Deve ser chamada a cada frame, na thread
+// do contexto OpenGL
+#[allow(dead_code)]
+pub fn collect_resources() {
+    RESOURCES.with(|resources| resources.borrow_mut().collect());
+}
+
+// Quantidade de malhas e texturas carregadas pelo gerenciador ainda em uso
+#[allow(dead_code)]
+pub fn live_resources() -> (usize, usize) {
+    RESOURCES.with(|resources| {
+        let mut resources = resources.borrow_mut();
+        resources.collect();
+        resources.live_count()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Objetos descartados fora da thread do contexto não chamam o OpenGL, apenas entram na fila
+    #[test]
+    fn drop_outside_gl_thread_is_queued() {
+        let gl_thread = thread::current().id();
+        thread::spawn(move || {
+            delete_gl_object(GlObject::Buffer(7), gl_thread);
+            delete_gl_object(GlObject::Texture(0), gl_thread);
+        })
+        .join()
+        .unwrap();
+
+        let mut queue = DELETION_QUEUE.lock().unwrap();
+        assert_eq!(*queue, vec![GlObject::Buffer(7)]);
+        queue.clear();
+    }
+}
@@ -0,0 +1,530 @@
+use models::animation::AnimationClip;
+use models::animation::Channel;
+use models::animation::ChannelValues;
+use models::animation::Interpolation;
+use models::asset_loader::request_generated_mesh;
+use models::matrix::translating_matrix;
+use models::matrix::Transform;
+use models::mesh_cache::load_cached;
+use models::mesh_data::MeshData;
+use models::mesh_data::MeshError;
+use models::normals::NormalMode;
+use models::obj_model::ObjModel;
+use models::quaternion::Quaternion;
+use models::resources::load_generated_mesh;
+use models::resources::load_mesh;
+use models::skeleton::Joint;
+use models::skeleton::Skeleton;
+use models::skeleton::Skin;
+use models::skeleton::MAX_JOINTS;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+// Evita divisão por zero na influencia de vertices sobre o osso
+static ENVELOPE_EPSILON: f32 = 1e-4;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Esqueleto e animações descritos em um arquivo .rig, para malhas sem esqueleto (ex: obj)
+// heads -> Posição de cada junta na pose de repouso, no espaço da malha
+// envelopes -> Raio de influencia dos ossos de cada junta. Juntas com raio 0 apenas marcam
+//              a ponta do osso do pai e não deformam vertices
+pub struct Rig {
+    pub mesh_path: String,
+    pub skeleton: Skeleton,
+    pub heads: Vec<glm::Vec3>,
+    pub envelopes: Vec<f32>,
+    pub clips: Vec<AnimationClip>,
+}
+
+// Propriedade animada por uma linha key
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum KeyProperty {
+    Rotate,
+    Translate,
+    Scale,
+}
+
+// Carrega um arquivo .rig. Formato em texto, uma declaração por linha e comentarios com #:
+//
+// mesh <caminho>                            Malha deformada, relativa ao arquivo .rig
+// joint <nome> <pai ou -> <x> <y> <z> <raio>  Junta na posição de repouso (espaço da malha).
+//                                           O pai deve ser declarado antes
+// clip <nome>                               Inicia um clip. As linhas key seguintes são dele
+// key <junta> <tempo> rotate <x> <y> <z>     Rotação local em angulos de euler (graus)
+// key <junta> <tempo> translate <x> <y> <z>  Deslocamento em relação a posição de repouso
+// key <junta> <tempo> scale <x> <y> <z>      Escala local
+//
+// Os keyframes de cada propriedade são interpolados linearmente (slerp nas rotações)
+#[allow(dead_code)]
+pub fn load_rig(path: &str) -> Result<Rig, MeshError> {
+    let load_error = |line: usize, message: String| MeshError::Load {
+        path: path.to_string(),
+        message: format!("linha {}: {}", line, message),
+    };
+
+    let source = fs::read_to_string(path).map_err(|error| load_error(0, error.to_string()))?;
+    let base_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+
+    let mut mesh_path = None;
+    let mut joints: Vec<Joint> = Vec::new();
+    let mut heads: Vec<glm::Vec3> = Vec::new();
+    let mut envelopes: Vec<f32> = Vec::new();
+    // Keyframes de cada clip: (junta, propriedade, tempo, valor), na ordem do arquivo
+    let mut clips: Vec<(String, Vec<(usize, KeyProperty, f32, glm::Vec3)>)> = Vec::new();
+
+    for (line_id, line) in source.lines().enumerate() {
+        let line_number = line_id + 1;
+        let line = line.split('#').next().unwrap_or("");
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.len() == 0 {
+            continue;
+        }
+
+        let number = |token: &str| -> Result<f32, MeshError> {
+            token
+                .parse::<f32>()
+                .map_err(|_| load_error(line_number, format!("Numero invalido: {}", token)))
+        };
+        let joint_id = |name: &str| -> Result<usize, MeshError> {
+            joints
+                .iter()
+                .position(|joint| joint.name == name)
+                .ok_or_else(|| load_error(line_number, format!("Junta não declarada: {}", name)))
+        };
+
+        match (tokens[0], tokens.len()) {
+            ("mesh", n) if n >= 2 => {
+                let file = tokens[1..].join(" ");
+                mesh_path = Some(base_dir.join(file).to_string_lossy().into_owned());
+            }
+            ("joint", 7) => {
+                if joint_id(tokens[1]).is_ok() {
+                    return Err(load_error(
+                        line_number,
+                        format!("Junta repetida: {}", tokens[1]),
+                    ));
+                }
+                let parent = match tokens[2] {
+                    "-" => None,
+                    name => Some(joint_id(name)?),
+                };
+                let head = glm::vec3(number(tokens[3])?, number(tokens[4])?, number(tokens[5])?);
+
+                // Juntas do .rig não tem rotação na pose de repouso, apenas o deslocamento
+                // em relação ao pai
+                let offset = match parent {
+                    Some(parent) => head - heads[parent],
+                    None => head,
+                };
+                joints.push(Joint {
+                    name: tokens[1].to_string(),
+                    parent,
+                    rest: Transform::new(offset, Quaternion::identity(), glm::vec3(1.0, 1.0, 1.0)),
+                    inverse_bind: translating_matrix(-head.x, -head.y, -head.z),
+                });
+                heads.push(head);
+                envelopes.push(number(tokens[6])?.max(0.0));
+            }
+            ("clip", 2) => clips.push((tokens[1].to_string(), Vec::new())),
+            ("key", 7) => {
+                let joint = joint_id(tokens[1])?;
+                let time = number(tokens[2])?;
+                let property = match tokens[3] {
+                    "rotate" => KeyProperty::Rotate,
+                    "translate" => KeyProperty::Translate,
+                    "scale" => KeyProperty::Scale,
+                    other => {
+                        return Err(load_error(
+                            line_number,
+                            format!("Propriedade desconhecida: {}", other),
+                        ))
+                    }
+                };
+                let value = glm::vec3(number(tokens[4])?, number(tokens[5])?, number(tokens[6])?);
+
+                match clips.last_mut() {
+                    Some(clip) => clip.1.push((joint, property, time, value)),
+                    None => {
+                        return Err(load_error(
+                            line_number,
+                            "Keyframe fora de um clip".to_string(),
+                        ))
+                    }
+                }
+            }
+            _ => {
+                return Err(load_error(
+                    line_number,
+                    format!("Declaração invalida: {}", line.trim()),
+                ))
+            }
+        }
+    }
+
+    let mesh_path = mesh_path.ok_or_else(|| load_error(0, "Malha não declarada".to_string()))?;
+    if joints.len() == 0 || joints.len() > MAX_JOINTS {
+        return Err(load_error(
+            0,
+            format!("{} juntas, suportadas de 1 a {}", joints.len(), MAX_JOINTS),
+        ));
+    }
+
+    let skeleton = Skeleton::new(joints);
+    let clips = clips
+        .iter()
+        .map(|(name, keys)| AnimationClip::new(name, clip_channels(&skeleton, keys)))
+        .collect();
+
+    Ok(Rig {
+        mesh_path,
+        skeleton,
+        heads,
+        envelopes,
+        clips,
+    })
+}
+
+// Agrupa os keyframes de um clip em canais por junta e propriedade, ordenados pelo tempo
+fn clip_channels(
+    skeleton: &Skeleton,
+    keys: &[(usize, KeyProperty, f32, glm::Vec3)],
+) -> Vec<Channel> {
+    let mut channels: Vec<Channel> = Vec::new();
+    let mut targets: Vec<(usize, KeyProperty)> = Vec::new();
+    for &(joint, property, _, _) in keys.iter() {
+        if !targets.contains(&(joint, property)) {
+            targets.push((joint, property));
+        }
+    }
+
+    for (joint, property) in targets {
+        let mut frames: Vec<(f32, glm::Vec3)> = keys
+            .iter()
+            .filter(|key| key.0 == joint && key.1 == property)
+            .map(|key| (key.2, key.3))
+            .collect();
+        frames.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        let rest = skeleton.joints[joint].rest;
+        let values = match property {
+            KeyProperty::Rotate => ChannelValues::Rotation(
+                frames
+                    .iter()
+                    .map(|(_, v)| {
+                        Quaternion::from_euler(v.x.to_radians(), v.y.to_radians(), v.z.to_radians())
+                    })
+                    .collect(),
+            ),
+            KeyProperty::Translate => ChannelValues::Translation(
+                frames.iter().map(|(_, v)| rest.translation + *v).collect(),
+            ),
+            KeyProperty::Scale => ChannelValues::Scale(frames.iter().map(|(_, v)| *v).collect()),
+        };
+
+        channels.push(Channel {
+            joint,
+            interpolation: Interpolation::Linear,
+            times: frames.iter().map(|(time, _)| *time).collect(),
+            values,
+        });
+    }
+    channels
+}
+
+// Copia da malha com juntas e pesos calculados pelos envelopes do rig
+// Cada junta influencia os segmentos até os seus filhos (ou apenas a sua posição, se não tiver
+// filhos), com influencia 1 / d^4, onde d é a distancia ao segmento dividida pelo raio do
+// envelope. As 4 juntas de maior influencia de cada vertice são mantidas, com pesos normalizados
+#[allow(dead_code)]
+pub fn skin_mesh(mesh: &MeshData, rig: &Rig) -> MeshData {
+    let joint_count = rig.skeleton.joints.len();
+
+    let mut segments: Vec<(usize, glm::Vec3, glm::Vec3)> = Vec::new();
+    for joint in 0..joint_count {
+        if rig.envelopes[joint] <= 0.0 {
+            continue;
+        }
+        let children: Vec<usize> = (0..joint_count)
+            .filter(|child| rig.skeleton.joints[*child].parent == Some(joint))
+            .collect();
+        if children.len() == 0 {
+            segments.push((joint, rig.heads[joint], rig.heads[joint]));
+        }
+        for child in children {
+            segments.push((joint, rig.heads[joint], rig.heads[child]));
+        }
+    }
+
+    let mut skinned = mesh.clone();
+    skinned.joints = Vec::with_capacity(4 * mesh.vertex_count());
+    skinned.weights = Vec::with_capacity(4 * mesh.vertex_count());
+
+    let mut influences = vec![0.0f32; joint_count];
+    for vertex in 0..mesh.vertex_count() {
+        let p = mesh.position(vertex);
+        let p = glm::vec3(p.x, p.y, p.z);
+
+        // Juntas com varios ossos usam o mais proximo
+        for influence in influences.iter_mut() {
+            *influence = 0.0;
+        }
+        for &(joint, a, b) in segments.iter() {
+            let d = segment_distance(&p, &a, &b) / rig.envelopes[joint];
+            let d2 = d * d + ENVELOPE_EPSILON;
+            influences[joint] = influences[joint].max(1.0 / (d2 * d2));
+        }
+
+        let mut strongest: Vec<usize> = (0..joint_count).collect();
+        strongest.sort_by(|a, b| {
+            influences[*b]
+                .partial_cmp(&influences[*a])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        strongest.resize(4, strongest[0]);
+
+        let total: f32 = strongest
+            .iter()
+            .take(joint_count.min(4))
+            .map(|j| influences[*j])
+            .sum();
+        for (slot, joint) in strongest.iter().enumerate() {
+            let weight = if slot < joint_count && total > 0.0 {
+                influences[*joint] / total
+            } else {
+                0.0
+            };
+            skinned.joints.push(*joint as u16);
+            skinned.weights.push(weight);
+        }
+    }
+    skinned
+}
+
+// Distancia de um ponto ao segmento ab
+fn segment_distance(p: &glm::Vec3, a: &glm::Vec3, b: &glm::Vec3) -> f32 {
+    let ab = *b - *a;
+    let length_sq = glm::dot(ab, ab);
+    let t = if length_sq > 0.0 {
+        glm::clamp(glm::dot(*p - *a, ab) / length_sq, 0.0, 1.0)
+    } else {
+        0.0
+    };
+    glm::length(*p - (*a + ab * t))
+}
+
+// Carrega a malha de um .rig como obj com esqueleto, parado na pose de repouso
+// A malha com juntas e pesos é compartilhada por todos os objs do mesmo arquivo
+#[allow(dead_code)]
+pub fn load_rig_model(path: &str, normal_mode: NormalMode) -> Result<ObjModel, MeshError> {
+    let rig = load_rig(path)?;
+    let base_mesh = load_mesh(&rig.mesh_path, normal_mode)?;
+
+    let key = format!("rig:{}#{:?}", path, normal_mode);
+    let gpu_mesh = load_generated_mesh(&key, || skin_mesh(&base_mesh.mesh, &rig));
+
+    let skin = Skin::new(Arc::new(rig.skeleton), Arc::new(rig.clips));
+    Ok(ObjModel::from_gpu_mesh(gpu_mesh).with_skin(&skin))
+}
+
+// Como load_rig_model, com a leitura da malha e o calculo dos pesos no rayon
+// O arquivo .rig é lido imediatamente, então o obj já tem o esqueleto e aceita clips, e a malha
+// provisoria é trocada em ObjModel::resolve_assets (ver asset_loader.rs)
+#[allow(dead_code)]
+pub fn load_rig_model_async(path: &str, normal_mode: NormalMode) -> Result<ObjModel, MeshError> {
+    let rig = load_rig(path)?;
+    let skin = Skin::new(Arc::new(rig.skeleton.clone()), Arc::new(rig.clips.clone()));
+
+    let key = format!("rig:{}#{:?}", path, normal_mode);
+    let gpu_mesh = request_generated_mesh(&key, move || {
+        load_cached(&rig.mesh_path, normal_mode).map(|mesh| skin_mesh(&mesh, &rig))
+    });
+    Ok(ObjModel::from_gpu_mesh(gpu_mesh).with_skin(&skin))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::primitives::box_mesh;
+    use std::env;
+    use std::path::PathBuf;
+
+    // Escreve um .rig em um diretorio temporario exclusivo do teste
+    fn write_rig(test: &str, source: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rig_loader_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.rig");
+        fs::write(&path, source).unwrap();
+        path
+    }
+
+    fn load(test: &str, source: &str) -> Result<Rig, MeshError> {
+        load_rig(write_rig(test, source).to_str().unwrap())
+    }
+
+    fn load_error(test: &str, source: &str) -> String {
+        match load(test, source) {
+            Err(MeshError::Load { message, .. }) => message,
+            other => panic!("resultado inesperado {:?}", other),
+        }
+    }
+
+    // Perna vertical de 2 unidades: quadril em y = 1, joelho em y = 0 e ponta do pé em y = -1
+    static LEG: &str = "
+        # Perna
+        mesh leg.obj
+        joint hip - 0 1 0 0.5
+        joint knee hip 0 0 0 0.5
+        joint foot knee 0 -1 0 0
+
+        clip kick
+        key knee 1 rotate 90 0 0
+        key knee 0 rotate 0 0 0
+        key hip 0.5 translate 0 0 1
+    ";
+
+    #[test]
+    fn joints_and_clips() {
+        let path = write_rig("joints_and_clips", LEG);
+        let rig = load_rig(path.to_str().unwrap()).expect("rig invalido");
+
+        assert_eq!(
+            PathBuf::from(&rig.mesh_path),
+            path.parent().unwrap().join("leg.obj")
+        );
+        let joints: Vec<(&str, Option<usize>)> = rig
+            .skeleton
+            .joints
+            .iter()
+            .map(|joint| (joint.name.as_str(), joint.parent))
+            .collect();
+        assert_eq!(
+            joints,
+            vec![("hip", None), ("knee", Some(0)), ("foot", Some(1))]
+        );
+        assert_eq!(rig.envelopes, vec![0.5, 0.5, 0.0]);
+
+        // Repouso relativo ao pai: na pose de ligação a malha não é deformada
+        let knee = &rig.skeleton.joints[1];
+        assert_eq!(knee.rest.translation, glm::vec3(0.0, -1.0, 0.0));
+        for matrix in rig.skeleton.skin_matrices(&rig.skeleton.rest_pose()) {
+            assert_eq!(
+                matrix.to_points(),
+                translating_matrix(0.0, 0.0, 0.0).to_points()
+            );
+        }
+
+        // Um canal por junta e propriedade, com os keyframes ordenados pelo tempo
+        assert_eq!(rig.clips.len(), 1);
+        let clip = &rig.clips[0];
+        assert_eq!(clip.name, "kick");
+        assert_eq!(clip.duration, 1.0);
+        assert_eq!(clip.channels.len(), 2);
+        assert_eq!(clip.channels[0].joint, 1);
+        assert_eq!(clip.channels[0].times, vec![0.0, 1.0]);
+
+        // Translações são somadas a posição de repouso
+        match clip.channels[1].values {
+            ChannelValues::Translation(ref values) => {
+                assert_eq!(values, &vec![glm::vec3(0.0, 1.0, 1.0)])
+            }
+            ref other => panic!("resultado inesperado {:?}", other),
+        }
+    }
+
+    #[test]
+    fn undeclared_joint() {
+        let message = load_error(
+            "undeclared_parent",
+            "mesh leg.obj\njoint knee hip 0 0 0 0.5\n",
+        );
+        assert!(
+            message.contains("linha 2") && message.contains("hip"),
+            "{}",
+            message
+        );
+
+        let message = load_error(
+            "undeclared_key",
+            "mesh leg.obj\njoint hip - 0 1 0 0.5\nclip kick\nkey knee 0 rotate 0 0 0\n",
+        );
+        assert!(
+            message.contains("linha 4") && message.contains("knee"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn key_outside_clip() {
+        let message = load_error(
+            "key_outside_clip",
+            "mesh leg.obj\njoint hip - 0 1 0 0.5\nkey hip 0 rotate 0 0 0\n",
+        );
+        assert!(
+            message.contains("linha 3") && message.contains("clip"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn joint_count_limits() {
+        let mut source = "mesh leg.obj\njoint j0 - 0 0 0 1\n".to_string();
+        for joint in 1..MAX_JOINTS {
+            source.push_str(&format!(
+                "joint j{} j{} 0 {} 0 1\n",
+                joint,
+                joint - 1,
+                joint
+            ));
+        }
+        assert!(load("max_joints", &source).is_ok());
+
+        source.push_str(&format!("joint j{} - 0 0 0 1\n", MAX_JOINTS));
+        let message = load_error("too_many_joints", &source);
+        assert!(
+            message.contains(&format!("{} juntas", MAX_JOINTS + 1)),
+            "{}",
+            message
+        );
+
+        let message = load_error("no_joints", "mesh leg.obj\n");
+        assert!(message.contains("0 juntas"), "{}", message);
+    }
+
+    #[test]
+    fn skin_mesh_weights_sum_to_one() {
+        let rig = load("skin_mesh", LEG).expect("rig invalido");
+        let mesh = box_mesh(0.5, 2.0, 0.5);
+        let skinned = skin_mesh(&mesh, &rig);
+
+        assert!(skinned.is_skinned());
+        assert_eq!(skinned.joints.len(), 4 * mesh.vertex_count());
+        assert_eq!(skinned.weights.len(), 4 * mesh.vertex_count());
+        assert!(skinned.validate().is_ok());
+
+        for vertex in 0..mesh.vertex_count() {
+            let joints = &skinned.joints[4 * vertex..4 * vertex + 4];
+            let weights = &skinned.weights[4 * vertex..4 * vertex + 4];
+            let sum: f32 = weights.iter().sum();
+            assert!((sum - 1.0).abs() < 1e-5, "pesos {:?}", weights);
+
+            // Juntas sem envelope não deformam vertices
+            for (joint, weight) in joints.iter().zip(weights.iter()) {
+                assert!(
+                    *joint < 3 && (*joint != 2 || *weight == 0.0),
+                    "{:?}",
+                    joints
+                );
+            }
+
+            // O osso do joelho vai até a ponta do pé: vertices de baixo seguem o joelho
+            if mesh.position(vertex).y < -0.5 {
+                assert_eq!(joints[0], 1, "{:?} {:?}", joints, weights);
+            }
+        }
+    }
+}
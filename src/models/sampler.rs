@@ -0,0 +1,299 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::sync::Arc;
+use std::sync::Weak;
+use std::thread;
+use std::thread::ThreadId;
+
+use gl;
+use gl::types::GLenum;
+use models::resources::delete_gl_object;
+use models::resources::GlObject;
+
+// Constantes da extensão GL_EXT_texture_filter_anisotropic, fora do OpenGL 3.3 core
+static TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FE;
+static MAX_TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FF;
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+// Comportamento das coordenadas de textura fora de [0..1]
+pub enum TextureWrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder,
+}
+
+impl TextureWrap {
+    fn gl_enum(&self) -> GLenum {
+        match *self {
+            TextureWrap::Repeat => gl::REPEAT,
+            TextureWrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+            TextureWrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+            TextureWrap::ClampToBorder => gl::CLAMP_TO_BORDER,
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+// Filtro de amostragem. Os filtros com mipmap só tem efeito na minificação
+// Ex: LinearMipmapLinear -> Filtro linear nos dois mipmaps mais proximos e entre eles (trilinear)
+pub enum TextureFilter {
+    Nearest,
+    Linear,
+    NearestMipmapNearest,
+    LinearMipmapNearest,
+    NearestMipmapLinear,
+    LinearMipmapLinear,
+}
+
+impl TextureFilter {
+    fn gl_enum(&self) -> GLenum {
+        match *self {
+            TextureFilter::Nearest => gl::NEAREST,
+            TextureFilter::Linear => gl::LINEAR,
+            TextureFilter::NearestMipmapNearest => gl::NEAREST_MIPMAP_NEAREST,
+            TextureFilter::LinearMipmapNearest => gl::LINEAR_MIPMAP_NEAREST,
+            TextureFilter::NearestMipmapLinear => gl::NEAREST_MIPMAP_LINEAR,
+            TextureFilter::LinearMipmapLinear => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+
+    // Filtro de magnificação equivalente: a magnificação não utiliza mipmaps
+    fn without_mipmap(&self) -> Self {
+        match *self {
+            TextureFilter::Nearest
+            | TextureFilter::NearestMipmapNearest
+            | TextureFilter::NearestMipmapLinear => TextureFilter::Nearest,
+            _ => TextureFilter::Linear,
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+// Parametros de amostragem de uma textura
+// wrap_s/wrap_t -> Comportamento fora de [0..1] em U e V
+// min_filter/mag_filter -> Filtros de minificação e magnificação
+// anisotropy -> Nivel de filtro anisotropico (1 desliga). Limitado ao maximo do driver, e ignorado
+//               sem a extensão GL_EXT_texture_filter_anisotropic
+// lod_bias -> Deslocamento do nivel de mipmap escolhido (positivo borra, negativo aguça)
+// border_color -> Cor (rgba) amostrada fora de [0..1] com TextureWrap::ClampToBorder
+pub struct SamplerDesc {
+    pub wrap_s: TextureWrap,
+    pub wrap_t: TextureWrap,
+    pub min_filter: TextureFilter,
+    pub mag_filter: TextureFilter,
+    pub anisotropy: f32,
+    pub lod_bias: f32,
+    pub border_color: [f32; 4],
+}
+
+#[allow(dead_code)]
+impl SamplerDesc {
+    // Parametros padrão das texturas: sem repetição e filtro trilinear
+    // Veja slide 100 do documento "Aula_20_e_21_Mapeamento_de_Texturas.pdf"
+    pub fn new() -> Self {
+        SamplerDesc {
+            wrap_s: TextureWrap::ClampToEdge,
+            wrap_t: TextureWrap::ClampToEdge,
+            min_filter: TextureFilter::LinearMipmapLinear,
+            mag_filter: TextureFilter::Linear,
+            anisotropy: 1.0,
+            lod_bias: 0.0,
+            border_color: [0.0, 0.0, 0.0, 0.0],
+        }
+    }
+
+    // Textura repetida nas duas direções, para materiais que formam padrões (ex: pisos)
+    pub fn repeat() -> Self {
+        SamplerDesc::new().with_wrap(TextureWrap::Repeat, TextureWrap::Repeat)
+    }
+
+    pub fn with_wrap(&self, wrap_s: TextureWrap, wrap_t: TextureWrap) -> Self {
+        SamplerDesc {
+            wrap_s,
+            wrap_t,
+            ..*self
+        }
+    }
+
+    // O filtro de magnificação não utiliza mipmaps, filtros com mipmap são convertidos
+    pub fn with_filters(&self, min_filter: TextureFilter, mag_filter: TextureFilter) -> Self {
+        SamplerDesc {
+            min_filter,
+            mag_filter: mag_filter.without_mipmap(),
+            ..*self
+        }
+    }
+
+    pub fn with_anisotropy(&self, anisotropy: f32) -> Self {
+        SamplerDesc {
+            anisotropy: anisotropy.max(1.0),
+            ..*self
+        }
+    }
+
+    pub fn with_lod_bias(&self, lod_bias: f32) -> Self {
+        SamplerDesc { lod_bias, ..*self }
+    }
+
+    pub fn with_border_color(&self, border_color: &glm::Vec4) -> Self {
+        SamplerDesc {
+            border_color: [
+                border_color.x,
+                border_color.y,
+                border_color.z,
+                border_color.w,
+            ],
+            ..*self
+        }
+    }
+
+    // Chave do descritor no cache de samplers. Os floats são comparados pelos bits
+    fn key(&self) -> SamplerKey {
+        (
+            self.wrap_s,
+            self.wrap_t,
+            self.min_filter,
+            self.mag_filter,
+            self.anisotropy.to_bits(),
+            self.lod_bias.to_bits(),
+            [
+                self.border_color[0].to_bits(),
+                self.border_color[1].to_bits(),
+                self.border_color[2].to_bits(),
+                self.border_color[3].to_bits(),
+            ],
+        )
+    }
+}
+
+type SamplerKey = (
+    TextureWrap,
+    TextureWrap,
+    TextureFilter,
+    TextureFilter,
+    u32,
+    u32,
+    [u32; 4],
+);
+
+#[allow(dead_code)]
+#[derive(Debug)]
+// Sampler object do OpenGL criado a partir de um descritor
+// gl_thread -> Thread do contexto OpenGL em que o sampler foi criado
+pub struct Sampler {
+    pub id: u32,
+    pub desc: SamplerDesc,
+    gl_thread: ThreadId,
+}
+
+// O ultimo handle pode ser descartado fora da thread do contexto, como em resources.rs
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        delete_gl_object(GlObject::Sampler(self.id), self.gl_thread);
+    }
+}
+
+// Sampler compartilhado pelas texturas com o mesmo descritor
+pub type SamplerHandle = Arc<Sampler>;
+
+thread_local! {
+    // Samplers da thread do contexto OpenGL por descritor. Referencias fracas, como em
+    // resources.rs: o sampler é descartado quando nenhuma textura o utiliza
+    static SAMPLERS: RefCell<HashMap<SamplerKey, Weak<Sampler>>> = RefCell::new(HashMap::new());
+    // Maior nivel de anisotropia do driver, 1 sem a extensão. 0 enquanto não consultado
+    static MAX_ANISOTROPY: Cell<f32> = Cell::new(0.0);
+}
+
+// Sampler do descritor, criado apenas se nenhuma textura utilizar um descritor identico
+#[allow(dead_code)]
+pub fn load_sampler(desc: &SamplerDesc) -> SamplerHandle {
+    let key = desc.key();
+    if let Some(sampler) = SAMPLERS.with(|samplers| samplers.borrow().get(&key)?.upgrade()) {
+        return sampler;
+    }
+
+    let sampler = Arc::new(Sampler {
+        id: unsafe { create_sampler(desc) },
+        desc: *desc,
+        gl_thread: thread::current().id(),
+    });
+    SAMPLERS.with(|samplers| {
+        let mut samplers = samplers.borrow_mut();
+        samplers.retain(|_, sampler| sampler.upgrade().is_some());
+        samplers.insert(key, Arc::downgrade(&sampler));
+    });
+    sampler
+}
+
+unsafe fn create_sampler(desc: &SamplerDesc) -> u32 {
+    let mut sampler_id = 0;
+    gl::GenSamplers(1, &mut sampler_id);
+
+    gl::SamplerParameteri(sampler_id, gl::TEXTURE_WRAP_S, desc.wrap_s.gl_enum() as i32);
+    gl::SamplerParameteri(sampler_id, gl::TEXTURE_WRAP_T, desc.wrap_t.gl_enum() as i32);
+
+    // Parâmetros de amostragem da textura.
+    gl::SamplerParameteri(
+        sampler_id,
+        gl::TEXTURE_MIN_FILTER,
+        desc.min_filter.gl_enum() as i32,
+    );
+    gl::SamplerParameteri(
+        sampler_id,
+        gl::TEXTURE_MAG_FILTER,
+        desc.mag_filter.gl_enum() as i32,
+    );
+
+    gl::SamplerParameterf(sampler_id, gl::TEXTURE_LOD_BIAS, desc.lod_bias);
+    gl::SamplerParameterfv(
+        sampler_id,
+        gl::TEXTURE_BORDER_COLOR,
+        desc.border_color.as_ptr(),
+    );
+
+    let max_anisotropy = max_anisotropy();
+    if max_anisotropy > 1.0 {
+        gl::SamplerParameterf(
+            sampler_id,
+            TEXTURE_MAX_ANISOTROPY,
+            desc.anisotropy.min(max_anisotropy).max(1.0),
+        );
+    }
+
+    sampler_id
+}
+
+// Maior nivel de anisotropia suportado, 1 se o driver não tiver a extensão
+#[allow(dead_code)]
+pub fn max_anisotropy() -> f32 {
+    MAX_ANISOTROPY.with(|max_anisotropy| {
+        if max_anisotropy.get() == 0.0 {
+            let mut value = 1.0;
+            unsafe {
+                if has_extension("GL_EXT_texture_filter_anisotropic")
+                    || has_extension("GL_ARB_texture_filter_anisotropic")
+                {
+                    gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut value);
+                }
+            }
+            max_anisotropy.set(value.max(1.0));
+        }
+        max_anisotropy.get()
+    })
+}
+
+// Verdadeiro se o driver anunciar a extensão informada (ex: "GL_EXT_texture_compression_s3tc")
+pub unsafe fn has_extension(name: &str) -> bool {
+    let mut count = 0;
+    gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+    (0..count as u32).any(|i| {
+        let extension = gl::GetStringi(gl::EXTENSIONS, i);
+        !extension.is_null() && CStr::from_ptr(extension as *const _).to_bytes() == name.as_bytes()
+    })
+}
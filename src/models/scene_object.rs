@@ -4,7 +4,18 @@ use super::draw::Draw;
 use super::matrix::GLMatrix;
 use super::matrix::MatrixTransform;
 use super::obj_model::ObjModel;
-use models::load_texture::load_texture;
+use models::gltf_loader::load_gltf_scene;
+use models::load_texture::TextureError;
+use models::normals::NormalMode;
+use models::ray::Ray;
+use models::ray::RayHit;
+use models::resources::load_normal_map_resource;
+use models::resources::load_texture_resource;
+use models::resources::TextureHandle;
+use models::rig_loader::load_rig_model;
+use models::rig_loader::load_rig_model_async;
+use std::path::Path;
+use world::frustum::Frustum;
 
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
@@ -22,8 +33,41 @@ pub enum SceneObject {
 //    A colisão é detectada entre qualquer numero de n objetos complexos
 #[allow(dead_code)]
 impl SceneObject {
+    // Arquivos .gltf e .glb mantem a hierarquia de nós, arquivos .rig carregam a malha
+    // referenciada com esqueleto (ver rig_loader.rs), demais são carregados como obj
     pub fn new(path: &str) -> Self {
-        SceneObject::ObjModel(ObjModel::new(path))
+        let extension = Path::new(path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+
+        match extension.as_ref().map(|extension| extension.as_str()) {
+            Some("gltf") | Some("glb") => match load_gltf_scene(path, NormalMode::default()) {
+                Ok(scene_object) => scene_object,
+                Err(error) => panic!("{}", error),
+            },
+            Some("rig") => match load_rig_model(path, NormalMode::default()) {
+                Ok(obj) => SceneObject::ObjModel(obj),
+                Err(error) => panic!("{}", error),
+            },
+            _ => SceneObject::ObjModel(ObjModel::new(path)),
+        }
+    }
+
+    // Como new, com o carregamento da malha no rayon, ver ObjModel::new_async
+    // Arquivos glTF são carregados imediatamente, pois a hierarquia de nós depende do arquivo
+    pub fn new_async(path: &str) -> Self {
+        let extension = Path::new(path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+
+        match extension.as_ref().map(|extension| extension.as_str()) {
+            Some("gltf") | Some("glb") => SceneObject::new(path),
+            Some("rig") => match load_rig_model_async(path, NormalMode::default()) {
+                Ok(obj) => SceneObject::ObjModel(obj),
+                Err(error) => panic!("{}", error),
+            },
+            _ => SceneObject::ObjModel(ObjModel::new_async(path)),
+        }
     }
 
     // Adiciona filhos no objeto
@@ -31,15 +75,15 @@ impl SceneObject {
         match self {
             SceneObject::ObjModel(obj_model) => match child {
                 SceneObject::ObjModel(c_obj) => SceneObject::CompositeObj(CompositeObj {
-                    root: *obj_model,
-                    children: vec![*c_obj],
+                    root: obj_model.clone(),
+                    children: vec![c_obj.clone()],
                 }),
                 SceneObject::CompositeObj(c_cmp) => SceneObject::ComplexObj(ComplexObj {
-                    root: *obj_model,
+                    root: obj_model.clone(),
                     children: Box::new(vec![SceneObject::CompositeObj(c_cmp.clone())]),
                 }),
                 SceneObject::ComplexObj(c_cplx) => SceneObject::ComplexObj(ComplexObj {
-                    root: *obj_model,
+                    root: obj_model.clone(),
                     children: Box::new(vec![SceneObject::ComplexObj(c_cplx.clone())]),
                 }),
             },
@@ -52,13 +96,13 @@ impl SceneObject {
                         .children
                         .clone()
                         .iter()
-                        .map(|item| SceneObject::ObjModel(*item))
+                        .map(|item| SceneObject::ObjModel(item.clone()))
                         .collect();
 
                     new_children.append(&mut vec![SceneObject::CompositeObj((*c_cmp).clone())]);
 
                     SceneObject::ComplexObj(ComplexObj {
-                        root: cmp_model.root,
+                        root: cmp_model.root.clone(),
                         children: Box::new(new_children),
                     })
                 }
@@ -67,13 +111,13 @@ impl SceneObject {
                         .children
                         .clone()
                         .iter()
-                        .map(|item| SceneObject::ObjModel(*item))
+                        .map(|item| SceneObject::ObjModel(item.clone()))
                         .collect();
 
                     new_children.append(&mut vec![SceneObject::ComplexObj((*c_cplx).clone())]);
 
                     SceneObject::ComplexObj(ComplexObj {
-                        root: cmp_model.root,
+                        root: cmp_model.root.clone(),
                         children: Box::new(new_children),
                     })
                 }
@@ -95,9 +139,9 @@ impl SceneObject {
     // Retorna raiz do obj
     pub fn get_root(&self) -> Self {
         match self {
-            SceneObject::ObjModel(obj) => SceneObject::ObjModel(*obj),
-            SceneObject::CompositeObj(obj) => SceneObject::ObjModel(obj.root),
-            SceneObject::ComplexObj(obj) => SceneObject::ObjModel(obj.root),
+            SceneObject::ObjModel(obj) => SceneObject::ObjModel(obj.clone()),
+            SceneObject::CompositeObj(obj) => SceneObject::ObjModel(obj.root.clone()),
+            SceneObject::ComplexObj(obj) => SceneObject::ObjModel(obj.root.clone()),
         }
     }
 
@@ -126,12 +170,14 @@ impl SceneObject {
         }
     }
 
+    // Identificador da textura que sobreescreve a do obj, 0 se não houver
     pub fn get_texture_override(&self) -> u32 {
-        match self {
-            SceneObject::ObjModel(obj) => obj.texture_override,
-            SceneObject::CompositeObj(obj) => obj.root.texture_override,
-            SceneObject::ComplexObj(obj) => obj.root.texture_override,
-        }
+        let texture_override = match self {
+            SceneObject::ObjModel(obj) => &obj.texture_override,
+            SceneObject::CompositeObj(obj) => &obj.root.texture_override,
+            SceneObject::ComplexObj(obj) => &obj.root.texture_override,
+        };
+        texture_override.as_ref().map_or(0, |texture| texture.id)
     }
 
     pub fn get_ambient_lighting_override(&self) -> glm::Vec3 {
@@ -144,7 +190,7 @@ impl SceneObject {
 
     // Utiliza textura previamente carregada para o obj
     #[allow(dead_code)]
-    pub fn with_texture(&self, texture: &u32, texture_map_type: i32) -> Self {
+    pub fn with_texture(&self, texture: &TextureHandle, texture_map_type: i32) -> Self {
         match self {
             SceneObject::ObjModel(obj) => {
                 SceneObject::ObjModel(obj.with_texture(texture, texture_map_type))
@@ -245,9 +291,31 @@ impl SceneObject {
     }
 
     // Carrega uma textura para o obj
-    pub unsafe fn load_texture(&self, path: &str, texture_map_type: i32) -> Self {
-        let (tex, _) = load_texture(path);
-        self.with_texture(&tex, texture_map_type)
+    pub unsafe fn load_texture(
+        &self,
+        path: &str,
+        texture_map_type: i32,
+    ) -> Result<Self, TextureError> {
+        Ok(self.with_texture(&load_texture_resource(path)?, texture_map_type))
+    }
+
+    // Atribui normal map para o obj, ver ObjModel::resolve_normal_map
+    pub fn with_normal_map(&self, normal_map: &TextureHandle) -> Self {
+        match self {
+            SceneObject::ObjModel(obj) => SceneObject::ObjModel(obj.with_normal_map(normal_map)),
+            SceneObject::CompositeObj(obj) => SceneObject::CompositeObj(CompositeObj {
+                root: obj.root.with_normal_map(normal_map),
+                children: obj.children.clone(),
+            }),
+            SceneObject::ComplexObj(obj) => SceneObject::ComplexObj(ComplexObj {
+                root: obj.root.with_normal_map(normal_map),
+                children: obj.children.clone(),
+            }),
+        }
+    }
+
+    pub unsafe fn load_normal_map(&self, path: &str) -> Result<Self, TextureError> {
+        Ok(self.with_normal_map(&load_normal_map_resource(path)?))
     }
     #[allow(dead_code)]
     // Muda modo de mapeamento de textura para o obj
@@ -267,6 +335,52 @@ impl SceneObject {
         }
     }
 
+    // Aplica uma mudança na raiz e em todos os filhos, recursivamente
+    fn map_obj_models<F: Fn(&ObjModel) -> ObjModel>(&self, change: &F) -> Self {
+        match self {
+            SceneObject::ObjModel(obj) => SceneObject::ObjModel(change(obj)),
+            SceneObject::CompositeObj(obj) => SceneObject::CompositeObj(CompositeObj {
+                root: change(&obj.root),
+                children: obj.children.iter().map(change).collect(),
+            }),
+            SceneObject::ComplexObj(obj) => SceneObject::ComplexObj(ComplexObj {
+                root: change(&obj.root),
+                children: Box::new(
+                    obj.children
+                        .iter()
+                        .map(|child| child.map_obj_models(change))
+                        .collect(),
+                ),
+            }),
+        }
+    }
+
+    // Troca as malhas provisorias da hierarquia pelas malhas já carregadas, ver ObjModel::resolve_assets
+    pub fn resolve_assets(&self) -> Self {
+        self.map_obj_models(&|obj: &ObjModel| obj.resolve_assets())
+    }
+
+    // Animações valem para todos os objs com esqueleto da hierarquia, ver ObjModel::play
+    pub fn play(&self, clip: &str) -> Self {
+        self.map_obj_models(&|obj: &ObjModel| obj.play(clip))
+    }
+
+    pub fn play_once(&self, clip: &str) -> Self {
+        self.map_obj_models(&|obj: &ObjModel| obj.play_once(clip))
+    }
+
+    pub fn cross_fade(&self, clip: &str, duration: f32) -> Self {
+        self.map_obj_models(&|obj: &ObjModel| obj.cross_fade(clip, duration))
+    }
+
+    pub fn blend_clip(&self, clip: &str, weight: f32) -> Self {
+        self.map_obj_models(&|obj: &ObjModel| obj.blend_clip(clip, weight))
+    }
+
+    pub fn update_animation(&self, delta_time: f32) -> Self {
+        self.map_obj_models(&|obj: &ObjModel| obj.update_animation(delta_time))
+    }
+
     // Checa a interseção entra a bbox de 2 objs
     pub fn check_plane_intersection(&self, point: &glm::Vec4, normal: &glm::Vec4) -> bool {
         //let model_translation = obj1.model.matrix.c3;
@@ -363,6 +477,57 @@ impl SceneObject {
             &obj2_bbox_max_pos,
         )
     }
+    // Retorna todos os objs simples que compõe o obj, com as matrizes globais usadas no desenho
+    // A ordem é a mesma do desenho: raiz, seguida dos filhos recursivamente
+    pub fn world_obj_models(&self) -> Vec<ObjModel> {
+        match self {
+            SceneObject::ObjModel(obj) => vec![obj.clone()],
+            SceneObject::CompositeObj(obj) => {
+                let mut objs = vec![obj.root.clone()];
+                obj.children.iter().for_each(|item| {
+                    objs.push(item.from_matrix(&GLMatrix {
+                        matrix: obj.root.model.matrix * item.model.matrix,
+                    }));
+                });
+                objs
+            }
+            SceneObject::ComplexObj(obj) => {
+                let mut objs = vec![obj.root.clone()];
+                obj.children.iter().for_each(|item| {
+                    let child = match item {
+                        SceneObject::ObjModel(obj_model) => {
+                            SceneObject::ObjModel(obj_model.from_matrix(&GLMatrix {
+                                matrix: obj_model.model.matrix * obj.root.model.matrix,
+                            }))
+                        }
+                        _ => item.from_matrix(&GLMatrix {
+                            matrix: obj.root.model.matrix * item.get_matrix().matrix,
+                        }),
+                    };
+                    objs.append(&mut child.world_obj_models());
+                });
+                objs
+            }
+        }
+    }
+
+    // Raycast contra todos os objs simples do obj, retornando a interseção mais proxima
+    pub fn raycast(&self, ray: &Ray) -> Option<RayHit> {
+        let mut closest: Option<RayHit> = None;
+
+        for (idx, obj) in self.world_obj_models().iter().enumerate() {
+            if let Some(hit) = obj.raycast(ray) {
+                if closest.map_or(true, |closest_hit| hit.distance < closest_hit.distance) {
+                    closest = Some(RayHit {
+                        object_index: idx,
+                        ..hit
+                    });
+                }
+            }
+        }
+        closest
+    }
+
     #[allow(dead_code, unused_assignments)]
     // Detecta se um objeto colidiu com outro, buscando recursivamente nos filhos em caso de objs complexos
     pub fn detect_colision(&self, obj2: &SceneObject) -> bool {
@@ -408,6 +573,16 @@ impl Draw for SceneObject {
         }
         self
     }
+
+    fn draw_in_frustum(&self, program: &u32, frustum: &Frustum) -> usize {
+        match self {
+            SceneObject::ObjModel(obj_model) => obj_model.draw_in_frustum(program, frustum),
+            SceneObject::ComplexObj(complex_obj) => complex_obj.draw_in_frustum(program, frustum),
+            SceneObject::CompositeObj(composite_obj) => {
+                composite_obj.draw_in_frustum(program, frustum)
+            }
+        }
+    }
 }
 
 impl MatrixTransform for SceneObject {
@@ -451,6 +626,22 @@ impl MatrixTransform for SceneObject {
     }
 }
 
+// Raycast contra uma lista de objs (ex: fila de desenho)
+// Retorna o indice do obj mais proximo atingido e a interseção
+#[allow(dead_code)]
+pub fn raycast_objects(objects: &[SceneObject], ray: &Ray) -> Option<(usize, RayHit)> {
+    let mut closest: Option<(usize, RayHit)> = None;
+
+    for (idx, obj) in objects.iter().enumerate() {
+        if let Some(hit) = obj.raycast(ray) {
+            if closest.map_or(true, |(_, closest_hit)| hit.distance < closest_hit.distance) {
+                closest = Some((idx, hit));
+            }
+        }
+    }
+    closest
+}
+
 #[allow(dead_code)]
 pub fn check_point_bbox_intersection(
     bbox_min: &glm::Vec4,
@@ -0,0 +1,519 @@
+use models::mesh_data::MeshData;
+use models::mesh_data::MeshLod;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+// Malhas com menos triangulos que isso não recebem niveis de detalhe
+static LOD_MIN_TRIANGLES: usize = 2048;
+// Quantidade maxima de niveis simplificados, cada um com metade dos triangulos do anterior
+static LOD_LEVELS: usize = 3;
+
+#[derive(Debug, Copy, Clone)]
+// Quadrica de erro (Garland e Heckbert): soma dos quadrados das distancias de um ponto a um
+// conjunto de planos, guardada como matriz 4x4 simetrica
+// Valores: a², ab, ac, ad, b², bc, bd, c², cd, d² do plano ax + by + cz + d = 0
+struct Quadric {
+    q: [f64; 10],
+}
+
+impl Quadric {
+    fn zero() -> Self {
+        Quadric { q: [0.0; 10] }
+    }
+
+    fn plane(a: f64, b: f64, c: f64, d: f64) -> Self {
+        Quadric {
+            q: [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ],
+        }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for i in 0..10 {
+            self.q[i] = self.q[i] + other.q[i];
+        }
+    }
+
+    // Soma dos quadrados das distancias do ponto aos planos
+    fn error(&self, p: &[f64; 3]) -> f64 {
+        let q = &self.q;
+        let (x, y, z) = (p[0], p[1], p[2]);
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+// Colapso da aresta (from, to) na fila de prioridade, do menor custo para o maior
+// versions -> Versões dos grupos na criação, colapsos com grupos alterados depois são descartados
+struct Collapse {
+    cost: f64,
+    from: usize,
+    to: usize,
+    versions: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// BinaryHeap retorna o maior elemento: a ordem é invertida para retornar o menor custo
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .partial_cmp(&self.cost)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+// Estado da simplificação. Vertices com a mesma posição formam um grupo, e os colapsos movem
+// todos os triangulos de um grupo para outro grupo vizinho
+struct Simplifier {
+    group_of: Vec<usize>,
+    positions: Vec<[f64; 3]>,
+    quadrics: Vec<Quadric>,
+    locked: Vec<bool>,
+    removed: Vec<bool>,
+    versions: Vec<u32>,
+    triangles: Vec<[u32; 3]>,
+    alive: Vec<bool>,
+    group_triangles: Vec<Vec<usize>>,
+    heap: BinaryHeap<Collapse>,
+}
+
+impl Simplifier {
+    fn new(mesh: &MeshData, triangles: Vec<[u32; 3]>) -> Self {
+        // Grupos por posição identica
+        let mut group_ids: HashMap<[u32; 3], usize> = HashMap::new();
+        let mut positions = Vec::new();
+        let mut variants: Vec<HashSet<u32>> = Vec::new();
+        let mut group_of = Vec::with_capacity(mesh.vertex_count());
+        for vertex in 0..mesh.vertex_count() {
+            let p = &mesh.positions[4 * vertex..4 * vertex + 3];
+            let key = [p[0].to_bits(), p[1].to_bits(), p[2].to_bits()];
+            let next_id = positions.len();
+            let group = *group_ids.entry(key).or_insert(next_id);
+            if group == next_id {
+                positions.push([p[0] as f64, p[1] as f64, p[2] as f64]);
+                variants.push(HashSet::new());
+            }
+            group_of.push(group);
+        }
+
+        let group_count = positions.len();
+        let mut simplifier = Simplifier {
+            group_of,
+            positions,
+            quadrics: vec![Quadric::zero(); group_count],
+            locked: vec![false; group_count],
+            removed: vec![false; group_count],
+            versions: vec![0; group_count],
+            alive: vec![true; triangles.len()],
+            triangles,
+            group_triangles: vec![Vec::new(); group_count],
+            heap: BinaryHeap::new(),
+        };
+
+        // Quadricas dos planos dos triangulos e contagem de triangulos por aresta
+        let mut edge_triangles: HashMap<(usize, usize), u32> = HashMap::new();
+        for triangle in 0..simplifier.triangles.len() {
+            let groups = simplifier.triangle_groups(triangle);
+            if groups[0] == groups[1] || groups[1] == groups[2] || groups[0] == groups[2] {
+                simplifier.alive[triangle] = false;
+                continue;
+            }
+
+            for corner in 0..3 {
+                let group = groups[corner];
+                simplifier.group_triangles[group].push(triangle);
+                variants[group].insert(simplifier.triangles[triangle][corner]);
+
+                let next = groups[(corner + 1) % 3];
+                *edge_triangles
+                    .entry((group.min(next), group.max(next)))
+                    .or_insert(0) += 1;
+            }
+
+            if let Some(normal) = simplifier.normal(&groups, None) {
+                let p = simplifier.positions[groups[0]];
+                let d = -(normal[0] * p[0] + normal[1] * p[1] + normal[2] * p[2]);
+                let plane = Quadric::plane(normal[0], normal[1], normal[2], d);
+                for group in groups.iter() {
+                    simplifier.quadrics[*group].add(&plane);
+                }
+            }
+        }
+
+        // Grupos na borda da malha, em arestas não manifold ou com atributos diferentes
+        // (costuras de textura, normais vincadas) não se movem, preservando o contorno e as costuras
+        for (&(a, b), count) in edge_triangles.iter() {
+            if *count != 2 {
+                simplifier.locked[a] = true;
+                simplifier.locked[b] = true;
+            }
+        }
+        for group in 0..group_count {
+            if variants[group].len() > 1 {
+                simplifier.locked[group] = true;
+            }
+        }
+
+        for group in 0..group_count {
+            simplifier.push_collapses(group);
+        }
+        simplifier
+    }
+
+    fn triangle_groups(&self, triangle: usize) -> [usize; 3] {
+        let t = &self.triangles[triangle];
+        [
+            self.group_of[t[0] as usize],
+            self.group_of[t[1] as usize],
+            self.group_of[t[2] as usize],
+        ]
+    }
+
+    // Normal unitaria do triangulo, com a posição de um grupo opcionalmente substituida
+    // None se o triangulo for degenerado
+    fn normal(&self, groups: &[usize; 3], moved: Option<(usize, [f64; 3])>) -> Option<[f64; 3]> {
+        let position = |group: usize| match moved {
+            Some((from, to)) if from == group => to,
+            _ => self.positions[group],
+        };
+        let (p0, p1, p2) = (
+            position(groups[0]),
+            position(groups[1]),
+            position(groups[2]),
+        );
+        let u = [p1[0] - p0[0], p1[1] - p0[1], p1[2] - p0[2]];
+        let v = [p2[0] - p0[0], p2[1] - p0[1], p2[2] - p0[2]];
+        let n = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+        let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+        if length < 1e-12 {
+            return None;
+        }
+        Some([n[0] / length, n[1] / length, n[2] / length])
+    }
+
+    fn neighbors(&self, group: usize) -> HashSet<usize> {
+        let mut neighbors = HashSet::new();
+        for triangle in self.group_triangles[group].iter() {
+            if self.alive[*triangle] {
+                for other in self.triangle_groups(*triangle).iter() {
+                    if *other != group {
+                        neighbors.insert(*other);
+                    }
+                }
+            }
+        }
+        neighbors
+    }
+
+    // Adiciona na fila os colapsos das arestas do grupo, nos dois sentidos
+    fn push_collapses(&mut self, group: usize) {
+        for neighbor in self.neighbors(group) {
+            for &(from, to) in [(group, neighbor), (neighbor, group)].iter() {
+                if self.locked[from] {
+                    continue;
+                }
+                let mut quadric = self.quadrics[from];
+                quadric.add(&self.quadrics[to]);
+                self.heap.push(Collapse {
+                    cost: quadric.error(&self.positions[to]).max(0.0),
+                    from,
+                    to,
+                    versions: (self.versions[from], self.versions[to]),
+                });
+            }
+        }
+    }
+
+    // Verifica se mover o grupo from para a posição de to mantém a malha manifold e
+    // sem triangulos invertidos
+    fn can_collapse(&self, from: usize, to: usize) -> bool {
+        let mut shared = 0;
+        let mut opposite = HashSet::new();
+        for triangle in self.group_triangles[from].iter() {
+            if !self.alive[*triangle] {
+                continue;
+            }
+            let groups = self.triangle_groups(*triangle);
+            if groups.contains(&to) {
+                shared = shared + 1;
+                for group in groups.iter() {
+                    if *group != from && *group != to {
+                        opposite.insert(*group);
+                    }
+                }
+                continue;
+            }
+
+            // Triangulos que continuam existindo não podem inverter ou degenerar
+            let before = self.normal(&groups, None);
+            let after = self.normal(&groups, Some((from, self.positions[to])));
+            match (before, after) {
+                (Some(before), Some(after)) => {
+                    let cos = before[0] * after[0] + before[1] * after[1] + before[2] * after[2];
+                    if cos < 0.2 {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+
+        // Condição do link: os vizinhos em comum devem ser apenas os vertices opostos a aresta,
+        // caso contrario o colapso cria arestas com mais de 2 triangulos
+        let common = self
+            .neighbors(from)
+            .intersection(&self.neighbors(to))
+            .count();
+        shared > 0 && common == opposite.len()
+    }
+
+    fn collapse(&mut self, from: usize, to: usize) -> usize {
+        // Vertice de destino: o do grupo to usado nos triangulos da aresta. O grupo from tem um
+        // unico vertice, então todos os seus triangulos estão do mesmo lado de qualquer costura de to
+        let mut target = None;
+        for triangle in self.group_triangles[from].iter() {
+            if self.alive[*triangle] {
+                for vertex in self.triangles[*triangle].iter() {
+                    if self.group_of[*vertex as usize] == to {
+                        target = Some(*vertex);
+                    }
+                }
+            }
+        }
+        let target = match target {
+            Some(target) => target,
+            None => return 0,
+        };
+
+        let mut removed_triangles = 0;
+        let triangles = self.group_triangles[from].clone();
+        for triangle in triangles.iter() {
+            if !self.alive[*triangle] {
+                continue;
+            }
+            if self.triangle_groups(*triangle).contains(&to) {
+                self.alive[*triangle] = false;
+                removed_triangles = removed_triangles + 1;
+                continue;
+            }
+            for corner in 0..3 {
+                if self.group_of[self.triangles[*triangle][corner] as usize] == from {
+                    self.triangles[*triangle][corner] = target;
+                }
+            }
+            self.group_triangles[to].push(*triangle);
+        }
+
+        let quadric = self.quadrics[from];
+        self.quadrics[to].add(&quadric);
+        self.removed[from] = true;
+        self.group_triangles[from].clear();
+        self.versions[to] = self.versions[to] + 1;
+
+        let mut group_triangles = self.group_triangles[to].clone();
+        group_triangles.retain(|triangle| self.alive[*triangle]);
+        group_triangles.sort();
+        group_triangles.dedup();
+        self.group_triangles[to] = group_triangles;
+
+        self.push_collapses(to);
+        removed_triangles
+    }
+
+    // Colapsa arestas, da de menor erro para a de maior, até atingir target_triangles ou
+    // não haver mais colapsos validos. Retorna o maior erro (distancia) de um colapso
+    fn run(&mut self, target_triangles: usize) -> f64 {
+        let mut live = self.alive.iter().filter(|alive| **alive).count();
+        let mut max_error = 0.0f64;
+
+        while live > target_triangles {
+            let candidate = match self.heap.pop() {
+                Some(candidate) => candidate,
+                None => break,
+            };
+            let (from, to) = (candidate.from, candidate.to);
+            if self.removed[from]
+                || self.removed[to]
+                || candidate.versions != (self.versions[from], self.versions[to])
+                || !self.can_collapse(from, to)
+            {
+                continue;
+            }
+
+            live = live - self.collapse(from, to);
+            max_error = max_error.max(candidate.cost.sqrt());
+        }
+        max_error
+    }
+}
+
+// Simplifica um nivel de detalhe da malha até target_triangles triangulos (ou o minimo possivel),
+// por colapso de arestas guiado pelas quadricas de erro. Os vertices não são movidos nem criados:
+// o nivel simplificado utiliza um subconjunto dos vertices da malha e compartilha seus buffers
+#[allow(dead_code)]
+pub fn simplify(mesh: &MeshData, source: &MeshLod, target_triangles: usize) -> MeshLod {
+    let mut triangles = Vec::new();
+    let mut triangle_ranges = Vec::new();
+    for (range_id, &(offset, count)) in source.ranges.iter().enumerate() {
+        for triangle in source.indices[offset..offset + count].chunks(3) {
+            triangles.push([triangle[0], triangle[1], triangle[2]]);
+            triangle_ranges.push(range_id);
+        }
+    }
+
+    let mut simplifier = Simplifier::new(mesh, triangles);
+    let error = simplifier.run(target_triangles);
+
+    // Triangulos restantes de cada submesh, na ordem original
+    let mut lod = MeshLod {
+        indices: Vec::new(),
+        ranges: Vec::new(),
+        error: source.error + error as f32,
+    };
+    for range_id in 0..source.ranges.len() {
+        let offset = lod.indices.len();
+        for (triangle, vertices) in simplifier.triangles.iter().enumerate() {
+            if simplifier.alive[triangle] && triangle_ranges[triangle] == range_id {
+                lod.indices.extend_from_slice(vertices);
+            }
+        }
+        lod.ranges.push((offset, lod.indices.len() - offset));
+    }
+    lod
+}
+
+// Gera os niveis de detalhe da malha, cada um com cerca de metade dos triangulos do anterior
+// Para quando a simplificação não consegue mais reduzir a malha (ex: malhas com muitas costuras)
+#[allow(dead_code)]
+pub fn generate_lods(mesh: &mut MeshData) {
+    mesh.lods.clear();
+    if mesh.triangle_count() < LOD_MIN_TRIANGLES {
+        return;
+    }
+
+    let mut lods = Vec::new();
+    let mut source = MeshLod::from_mesh(mesh);
+    for _ in 0..LOD_LEVELS {
+        let source_triangles = source.triangle_count();
+        let lod = simplify(mesh, &source, source_triangles / 2);
+        if lod.triangle_count() as f32 > 0.9 * source_triangles as f32 {
+            break;
+        }
+        lods.push(lod.clone());
+        source = lod;
+    }
+    mesh.lods = lods;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::primitives::plane_mesh;
+    use models::primitives::uv_sphere_mesh;
+
+    fn used_vertices(lod: &MeshLod) -> HashSet<u32> {
+        lod.indices.iter().cloned().collect()
+    }
+
+    // Verifica que cada nivel tem menos triangulos que o anterior e que os vertices fixos
+    // continuam sendo usados por todos os niveis
+    fn assert_lods(mesh: &MeshData, fixed: &[u32]) {
+        assert!(!mesh.lods.is_empty(), "nenhum nivel gerado para {:?}", mesh);
+        let mut previous = mesh.triangle_count();
+        for lod in mesh.lods.iter() {
+            let triangles = lod.triangle_count();
+            assert!(
+                (triangles as f32) <= 0.9 * previous as f32,
+                "resultado inesperado {} de {}",
+                triangles,
+                previous
+            );
+            assert_eq!(lod.indices.len(), 3 * triangles);
+            previous = triangles;
+
+            let used = used_vertices(lod);
+            for vertex in fixed.iter() {
+                assert!(used.contains(vertex), "vertice {} removido", vertex);
+            }
+        }
+    }
+
+    #[test]
+    fn plane_keeps_border() {
+        let mut plane = plane_mesh(2.0, 2.0, 32, 32);
+        generate_lods(&mut plane);
+
+        let border: Vec<u32> = (0..plane.vertex_count())
+            .filter(|vertex| {
+                let p = plane.position(*vertex);
+                p.x.abs() == 1.0 || p.z.abs() == 1.0
+            })
+            .map(|vertex| vertex as u32)
+            .collect();
+        assert_eq!(border.len(), 4 * 32);
+        assert_lods(&plane, &border);
+    }
+
+    #[test]
+    fn sphere_keeps_seam() {
+        let mut sphere = uv_sphere_mesh(1.0, 64, 32);
+        generate_lods(&mut sphere);
+
+        // Vertices da costura da textura (u = 0 e u = 1), fora dos polos
+        let seam: Vec<u32> = (0..sphere.vertex_count())
+            .filter(|vertex| {
+                let u = sphere.texcoords[2 * vertex];
+                let p = sphere.position(*vertex);
+                (u == 0.0 || u == 1.0) && p.y.abs() < 0.999
+            })
+            .map(|vertex| vertex as u32)
+            .collect();
+        assert!(!seam.is_empty());
+        assert_lods(&sphere, &seam);
+    }
+
+    #[test]
+    fn small_meshes_have_no_lods() {
+        let mut plane = plane_mesh(2.0, 2.0, 4, 4);
+        generate_lods(&mut plane);
+        assert!(plane.lods.is_empty());
+    }
+}
@@ -0,0 +1,299 @@
+use models::animation::AnimationClip;
+use models::animation::Animator;
+use models::matrix::identity_matrix;
+use models::matrix::GLMatrix;
+use models::matrix::Transform;
+use std::sync::Arc;
+
+// Maximo de juntas por esqueleto: tamanho do array joint_matrices dos vertex shaders
+pub static MAX_JOINTS: usize = 64;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Junta (osso) de um esqueleto
+// parent -> Indice da junta pai em Skeleton::joints, None para as raizes
+// rest -> Transformação local (relativa ao pai) na pose de repouso, usada nas juntas sem animação
+// inverse_bind -> Inversa da matriz global da junta na pose em que a malha foi ligada ao
+//                 esqueleto. Leva os vertices do espaço da malha para o espaço da junta
+pub struct Joint {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub rest: Transform,
+    pub inverse_bind: GLMatrix,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Hierarquia de juntas que deforma uma malha
+// root -> Matriz aplicada sobre as juntas raiz, do espaço do esqueleto para o espaço da malha
+//         (ex: nós glTF acima do esqueleto que não são juntas)
+pub struct Skeleton {
+    pub joints: Vec<Joint>,
+    pub root: GLMatrix,
+}
+
+#[allow(dead_code)]
+impl Skeleton {
+    pub fn new(joints: Vec<Joint>) -> Self {
+        Skeleton {
+            joints,
+            root: identity_matrix(),
+        }
+    }
+
+    // Busca uma junta pelo nome
+    pub fn joint(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|joint| joint.name == name)
+    }
+
+    // Transformações locais da pose de repouso
+    pub fn rest_pose(&self) -> Vec<Transform> {
+        self.joints.iter().map(|joint| joint.rest).collect()
+    }
+
+    // Matrizes globais (no espaço da malha) das juntas, para uma pose com uma transformação
+    // local por junta. Cada pai é calculado uma unica vez, em qualquer ordem das juntas
+    pub fn global_matrices(&self, pose: &[Transform]) -> Vec<GLMatrix> {
+        let mut globals: Vec<Option<GLMatrix>> = vec![None; self.joints.len()];
+        for joint in 0..self.joints.len() {
+            self.resolve_global(joint, pose, &mut globals);
+        }
+        globals
+            .iter()
+            .map(|global| global.unwrap_or(self.root))
+            .collect()
+    }
+
+    fn resolve_global(
+        &self,
+        joint: usize,
+        pose: &[Transform],
+        globals: &mut Vec<Option<GLMatrix>>,
+    ) -> GLMatrix {
+        if let Some(global) = globals[joint] {
+            return global;
+        }
+
+        let parent = match self.joints[joint].parent {
+            Some(parent) => self.resolve_global(parent, pose, globals),
+            None => self.root,
+        };
+        let local = pose
+            .get(joint)
+            .unwrap_or(&self.joints[joint].rest)
+            .to_matrix();
+        let global = GLMatrix {
+            matrix: parent.matrix * local.matrix,
+        };
+        globals[joint] = Some(global);
+        global
+    }
+
+    // Matrizes de deformação enviadas ao shader: global da junta * inversa da pose de ligação
+    // Na pose de ligação todas são a identidade e a malha não é deformada
+    pub fn skin_matrices(&self, pose: &[Transform]) -> Vec<GLMatrix> {
+        self.global_matrices(pose)
+            .iter()
+            .zip(self.joints.iter())
+            .map(|(global, joint)| GLMatrix {
+                matrix: global.matrix * joint.inverse_bind.matrix,
+            })
+            .collect()
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Esqueleto, animações e estado de reprodução de um obj com malha deformavel
+// Esqueleto e clips são compartilhados pelos clones do obj, cada clone tem o seu animator
+pub struct Skin {
+    pub skeleton: Arc<Skeleton>,
+    pub clips: Arc<Vec<AnimationClip>>,
+    pub animator: Animator,
+}
+
+#[allow(dead_code)]
+impl Skin {
+    // Esqueleto parado na pose de repouso
+    pub fn new(skeleton: Arc<Skeleton>, clips: Arc<Vec<AnimationClip>>) -> Self {
+        Skin {
+            skeleton,
+            clips,
+            animator: Animator::new(),
+        }
+    }
+
+    // Busca um clip pelo nome
+    pub fn clip(&self, name: &str) -> Option<usize> {
+        self.clips.iter().position(|clip| clip.name == name)
+    }
+
+    pub fn clip_names(&self) -> Vec<&str> {
+        self.clips.iter().map(|clip| clip.name.as_str()).collect()
+    }
+
+    // Aplica uma mudança no animator com o clip informado. Clips inexistentes são ignorados
+    fn with_clip<F>(&self, name: &str, change: F) -> Self
+    where
+        F: FnOnce(&Animator, usize) -> Animator,
+    {
+        match self.clip(name) {
+            Some(clip) => Skin {
+                animator: change(&self.animator, clip),
+                ..self.clone()
+            },
+            None => {
+                println!("Clip não encontrado: {}", name);
+                self.clone()
+            }
+        }
+    }
+
+    // Reproduz apenas o clip informado, em loop, ver Animator::play
+    pub fn play(&self, name: &str) -> Self {
+        self.with_clip(name, |animator, clip| animator.play(clip))
+    }
+
+    // Reproduz o clip uma unica vez, parando no ultimo keyframe
+    pub fn play_once(&self, name: &str) -> Self {
+        self.with_clip(name, |animator, clip| animator.play_once(clip))
+    }
+
+    // Transição suave para o clip em duration segundos, ver Animator::cross_fade
+    pub fn cross_fade(&self, name: &str, duration: f32) -> Self {
+        self.with_clip(name, |animator, clip| animator.cross_fade(clip, duration))
+    }
+
+    // Mistura o clip aos clips em reprodução com o peso informado, ver Animator::blend
+    pub fn blend(&self, name: &str, weight: f32) -> Self {
+        self.with_clip(name, |animator, clip| animator.blend(clip, weight))
+    }
+
+    // Avança a reprodução em delta_time segundos
+    pub fn update(&self, delta_time: f32) -> Self {
+        Skin {
+            animator: self.animator.update(&self.clips, delta_time),
+            ..self.clone()
+        }
+    }
+
+    // Pose local atual, mistura dos clips em reprodução
+    pub fn pose(&self) -> Vec<Transform> {
+        self.animator.pose(&self.skeleton, &self.clips)
+    }
+
+    // Matrizes de deformação da pose atual, ver Skeleton::skin_matrices
+    pub fn joint_matrices(&self) -> Vec<GLMatrix> {
+        self.skeleton.skin_matrices(&self.pose())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::quaternion::Quaternion;
+
+    fn assert_identity(matrix: &GLMatrix) {
+        for (x, y) in matrix
+            .to_points()
+            .iter()
+            .zip(identity_matrix().to_points().iter())
+        {
+            assert!((x - y).abs() < 1e-5, "{:?}", matrix.to_points());
+        }
+    }
+
+    // Braço com rotações na pose de repouso. A mão é declarada antes do seu pai
+    // A inversa da pose de ligação é calculada da propria pose de repouso
+    fn arm() -> Skeleton {
+        let joint = |name: &str, parent: Option<usize>, rest: Transform| Joint {
+            name: name.to_string(),
+            parent,
+            rest,
+            inverse_bind: identity_matrix(),
+        };
+        let mut skeleton = Skeleton::new(vec![
+            joint(
+                "hand",
+                Some(2),
+                Transform::new(
+                    glm::vec3(0.0, 1.0, 0.0),
+                    Quaternion::from_euler(0.0, 0.0, 0.4),
+                    glm::vec3(1.0, 1.0, 1.0),
+                ),
+            ),
+            joint(
+                "shoulder",
+                None,
+                Transform::new(
+                    glm::vec3(1.0, 2.0, 3.0),
+                    Quaternion::from_euler(0.3, 0.0, 0.0),
+                    glm::vec3(1.0, 1.0, 1.0),
+                ),
+            ),
+            joint(
+                "elbow",
+                Some(1),
+                Transform::new(
+                    glm::vec3(0.0, 2.0, 0.0),
+                    Quaternion::from_euler(0.0, 0.5, 0.0),
+                    glm::vec3(2.0, 2.0, 2.0),
+                ),
+            ),
+        ]);
+        skeleton.root = identity_matrix().translate(0.0, -1.0, 0.0);
+
+        let globals = skeleton.global_matrices(&skeleton.rest_pose());
+        for (joint, global) in skeleton.joints.iter_mut().zip(globals.iter()) {
+            joint.inverse_bind = global.inverse().expect("matriz singular");
+        }
+        skeleton
+    }
+
+    #[test]
+    fn globals_compose_parents_in_any_order() {
+        let skeleton = arm();
+        let pose = skeleton.rest_pose();
+        let globals = skeleton.global_matrices(&pose);
+
+        let shoulder = GLMatrix {
+            matrix: skeleton.root.matrix * pose[1].to_matrix().matrix,
+        };
+        let elbow = GLMatrix {
+            matrix: shoulder.matrix * pose[2].to_matrix().matrix,
+        };
+        let hand = GLMatrix {
+            matrix: elbow.matrix * pose[0].to_matrix().matrix,
+        };
+        for (global, expected) in globals.iter().zip([hand, shoulder, elbow].iter()) {
+            let inverse = expected.inverse().expect("matriz singular");
+            assert_identity(&GLMatrix {
+                matrix: global.matrix * inverse.matrix,
+            });
+        }
+    }
+
+    #[test]
+    fn skin_matrices_are_identity_at_bind_pose() {
+        let skeleton = arm();
+        let matrices = skeleton.skin_matrices(&skeleton.rest_pose());
+        assert_eq!(matrices.len(), 3);
+        for matrix in matrices.iter() {
+            assert_identity(matrix);
+        }
+
+        // Pose incompleta: juntas sem transformação usam a de repouso
+        let matrices = skeleton.skin_matrices(&[]);
+        for matrix in matrices.iter() {
+            assert_identity(matrix);
+        }
+
+        // Mover o ombro desloca também o cotovelo e a mão
+        let mut pose = skeleton.rest_pose();
+        pose[1].translation = pose[1].translation + glm::vec3(0.0, 0.0, 1.0);
+        for matrix in skeleton.skin_matrices(&pose).iter() {
+            let points = matrix.to_points();
+            assert!((points[11] - 1.0).abs() < 1e-5, "{:?}", points);
+        }
+    }
+}
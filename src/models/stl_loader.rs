@@ -0,0 +1,217 @@
+use models::mesh_data::MeshData;
+use models::mesh_data::MeshError;
+use models::normals::NormalMesh;
+use models::normals::NormalMode;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Angulo de vinco padrão das normais calculadas, em radianos (30 graus)
+// Arquivos STL costumam ser peças de CAD com arestas vivas, que não devem ser suavizadas
+static STL_CREASE_ANGLE: f32 = std::f32::consts::FRAC_PI_6;
+
+// Cabeçalho de 80 bytes seguido do numero de triangulos
+static STL_BINARY_HEADER: usize = 84;
+// Normal, 3 vertices e 2 bytes de atributos
+static STL_BINARY_TRIANGLE: usize = 50;
+
+// Carrega um arquivo STL (ASCII ou binario) como malha de um submesh
+// Vertices repetidos entre triangulos são unificados, e as normais são sempre calculadas:
+// as normais das faces do arquivo não são confiaveis e não existem normais por vertice.
+// NormalMode::Imported utiliza vinco de 30 graus
+#[allow(dead_code)]
+pub fn load_stl(path: &str, normal_mode: NormalMode) -> Result<MeshData, MeshError> {
+    let load_error = |message: String| MeshError::Load {
+        path: path.to_string(),
+        message,
+    };
+
+    let bytes = fs::read(path).map_err(|error| load_error(error.to_string()))?;
+
+    let triangles = if is_binary_stl(&bytes) {
+        parse_binary_stl(&bytes)
+    } else {
+        parse_ascii_stl(&String::from_utf8_lossy(&bytes))
+    }
+    .map_err(load_error)?;
+
+    let normal_mode = match normal_mode {
+        NormalMode::Imported => NormalMode::Crease(STL_CREASE_ANGLE),
+        mode => mode,
+    };
+
+    let name = Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or(String::new());
+
+    let mut mesh_data = MeshData::new();
+    mesh_data.push_mesh(
+        &name,
+        &weld_triangles(&triangles).with_normal_mode(normal_mode),
+        None,
+    );
+    mesh_data.validate()?;
+    Ok(mesh_data)
+}
+
+// Arquivos ASCII também podem começar com "solid", então o binario é identificado pelo tamanho
+fn is_binary_stl(bytes: &[u8]) -> bool {
+    if bytes.len() < STL_BINARY_HEADER {
+        return false;
+    }
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    bytes.len() == STL_BINARY_HEADER + count * STL_BINARY_TRIANGLE
+}
+
+// Retorna 9 valores (3 vertices X Y Z) por triangulo
+fn parse_binary_stl(bytes: &[u8]) -> Result<Vec<f32>, String> {
+    let mut triangles = Vec::new();
+
+    for triangle in bytes[STL_BINARY_HEADER..].chunks(STL_BINARY_TRIANGLE) {
+        if triangle.len() < STL_BINARY_TRIANGLE {
+            return Err("Triangulo incompleto".to_string());
+        }
+        // Ignora a normal da face (12 bytes) e os atributos
+        for v in 0..9 {
+            let offset = 12 + 4 * v;
+            triangles.push(f32::from_le_bytes([
+                triangle[offset],
+                triangle[offset + 1],
+                triangle[offset + 2],
+                triangle[offset + 3],
+            ]));
+        }
+    }
+    Ok(triangles)
+}
+
+fn parse_ascii_stl(text: &str) -> Result<Vec<f32>, String> {
+    let mut triangles = Vec::new();
+    let mut tokens = text.split_whitespace();
+
+    while let Some(token) = tokens.next() {
+        if token != "vertex" {
+            continue;
+        }
+        for _ in 0..3 {
+            let value = tokens
+                .next()
+                .ok_or_else(|| "Vertice incompleto".to_string())?;
+            triangles.push(
+                value
+                    .parse::<f32>()
+                    .map_err(|_| format!("Valor invalido: {}", value))?,
+            );
+        }
+    }
+
+    if triangles.len() % 9 != 0 {
+        return Err("Faceta com numero de vertices diferente de 3".to_string());
+    }
+    Ok(triangles)
+}
+
+// Gera malha indexada, unificando vertices com a mesma posição
+fn weld_triangles(triangles: &[f32]) -> NormalMesh {
+    let mut mesh = NormalMesh {
+        positions: Vec::new(),
+        normals: Vec::new(),
+        texcoords: Vec::new(),
+        colors: Vec::new(),
+        joints: Vec::new(),
+        weights: Vec::new(),
+        indices: Vec::new(),
+    };
+    let mut vertex_ids: HashMap<[u32; 3], u32> = HashMap::new();
+
+    for vertex in triangles.chunks(3) {
+        let key = [
+            vertex[0].to_bits(),
+            vertex[1].to_bits(),
+            vertex[2].to_bits(),
+        ];
+        let next_id = (mesh.positions.len() / 3) as u32;
+        let id = *vertex_ids.entry(key).or_insert(next_id);
+        if id == next_id {
+            mesh.positions.extend_from_slice(vertex);
+        }
+        mesh.indices.push(id);
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Dois triangulos de um quadrado, com a aresta diagonal compartilhada
+    static SQUARE: [f32; 18] = [
+        0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, // Triangulo 1
+        0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, // Triangulo 2
+    ];
+
+    fn binary_stl(triangles: &[f32]) -> Vec<u8> {
+        let mut bytes = vec![0u8; 80];
+        bytes.extend_from_slice(&((triangles.len() / 9) as u32).to_le_bytes());
+        for triangle in triangles.chunks(9) {
+            // Normal da face, ignorada na leitura
+            bytes.extend_from_slice(&[0u8; 12]);
+            for value in triangle.iter() {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            bytes.extend_from_slice(&[0u8; 2]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn parse_ascii() {
+        let text = "solid quadrado
+facet normal 0 0 1
+  outer loop
+    vertex 0 0 0
+    vertex 1 0 0
+    vertex 1 1 0
+  endloop
+endfacet
+facet normal 0 0 1
+  outer loop
+    vertex 0 0 0
+    vertex 1 1 0
+    vertex 0 1 0
+  endloop
+endfacet
+endsolid quadrado
+";
+        assert!(!is_binary_stl(text.as_bytes()));
+        assert_eq!(parse_ascii_stl(text).unwrap(), SQUARE.to_vec());
+        assert!(parse_ascii_stl("facet outer loop vertex 0 0 0 vertex 1 0").is_err());
+    }
+
+    #[test]
+    fn parse_binary() {
+        let bytes = binary_stl(&SQUARE);
+        assert_eq!(bytes.len(), STL_BINARY_HEADER + 2 * STL_BINARY_TRIANGLE);
+        assert!(is_binary_stl(&bytes));
+        assert_eq!(parse_binary_stl(&bytes).unwrap(), SQUARE.to_vec());
+
+        // Cabeçalho binario que começa com "solid" continua sendo binario
+        let mut solid = bytes.clone();
+        solid[..5].copy_from_slice(b"solid");
+        assert!(is_binary_stl(&solid));
+        assert!(!is_binary_stl(&bytes[..bytes.len() - 1]));
+    }
+
+    #[test]
+    fn weld_shared_vertices() {
+        let mesh = weld_triangles(&SQUARE);
+        assert_eq!(mesh.positions.len(), 12);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+
+        let mesh = mesh.with_normal_mode(NormalMode::Crease(STL_CREASE_ANGLE));
+        for normal in mesh.normals.chunks(3) {
+            assert_eq!(normal, &[0.0, 0.0, 1.0]);
+        }
+    }
+}
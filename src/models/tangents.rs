@@ -0,0 +1,259 @@
+use bevy_mikktspace;
+use bevy_mikktspace::Geometry;
+use models::mesh_data::MeshData;
+use std::collections::HashMap;
+
+// Tangente usada nos vertices sem triangulos ou quando não é possivel calcular
+static DEFAULT_TANGENT: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+// Acesso da mikktspace aos triangulos da malha. A tangente é calculada por canto de triangulo
+struct MikkGeometry<'a> {
+    mesh: &'a MeshData,
+    corners: Vec<[f32; 4]>,
+}
+
+impl<'a> MikkGeometry<'a> {
+    fn vertex(&self, face: usize, vert: usize) -> usize {
+        self.mesh.indices[3 * face + vert] as usize
+    }
+}
+
+impl<'a> Geometry for MikkGeometry<'a> {
+    fn num_faces(&self) -> usize {
+        self.mesh.indices.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        let p = &self.mesh.positions[4 * self.vertex(face, vert)..];
+        [p[0], p[1], p[2]]
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        let n = &self.mesh.normals[4 * self.vertex(face, vert)..];
+        [n[0], n[1], n[2]]
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        let uv = &self.mesh.texcoords[2 * self.vertex(face, vert)..];
+        [uv[0], uv[1]]
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.corners[3 * face + vert] = tangent;
+    }
+}
+
+#[allow(dead_code)]
+// Calcula as tangentes da malha com o algoritmo MikkTSpace, o mesmo usado por ferramentas como
+// Blender e Substance para gerar normal maps, e pelo formato glTF
+// Cada tangente tem 4 valores: direção (xyz) e sinal da bitangente (w), com
+// bitangente = w * cross(normal, tangente)
+// Vertices compartilhados por triangulos com tangentes diferentes (ex: costuras do mapeamento UV
+// espelhado) são duplicados. Malhas sem coordenadas de textura ficam sem tangentes
+// Niveis de detalhe são descartados, já que os indices da malha mudam
+pub fn generate_tangents(mesh: &mut MeshData) {
+    mesh.tangents.clear();
+    mesh.lods.clear();
+    if mesh.texcoords.len() == 0 || mesh.indices.len() == 0 {
+        return;
+    }
+
+    let corners = {
+        let mut geometry = MikkGeometry {
+            mesh: mesh,
+            corners: vec![DEFAULT_TANGENT; mesh.indices.len()],
+        };
+        if !bevy_mikktspace::generate_tangents(&mut geometry) {
+            println!(
+                "Não foi possivel calcular as tangentes da malha {}",
+                mesh.describe()
+            );
+            return;
+        }
+        geometry.corners
+    };
+
+    // Tangente de cada vertice: a do primeiro canto que o usa. Cantos com outra tangente
+    // passam a usar uma copia do vertice, compartilhada pelos cantos com a mesma tangente
+    let vertex_count = mesh.vertex_count();
+    let mut assigned = vec![false; vertex_count];
+    let mut tangents = vec![DEFAULT_TANGENT; vertex_count];
+    let mut copies: HashMap<(u32, [u32; 4]), u32> = HashMap::new();
+
+    for (corner, tangent) in corners.iter().enumerate() {
+        let vertex = mesh.indices[corner];
+        if !assigned[vertex as usize] {
+            assigned[vertex as usize] = true;
+            tangents[vertex as usize] = *tangent;
+        } else if tangents[vertex as usize] != *tangent {
+            let key = (
+                vertex,
+                [
+                    tangent[0].to_bits(),
+                    tangent[1].to_bits(),
+                    tangent[2].to_bits(),
+                    tangent[3].to_bits(),
+                ],
+            );
+            let copy = *copies.entry(key).or_insert_with(|| {
+                tangents.push(*tangent);
+                duplicate_vertex(mesh, vertex as usize)
+            });
+            mesh.indices[corner] = copy;
+        }
+    }
+
+    mesh.tangents = tangents
+        .iter()
+        .flat_map(|tangent| tangent.to_vec())
+        .collect();
+}
+
+// Adiciona uma copia dos atributos do vertice no fim dos buffers e retorna o seu indice
+fn duplicate_vertex(mesh: &mut MeshData, vertex: usize) -> u32 {
+    let copy = mesh.vertex_count() as u32;
+    copy_attribute(&mut mesh.positions, vertex, 4);
+    copy_attribute(&mut mesh.normals, vertex, 4);
+    copy_attribute(&mut mesh.texcoords, vertex, 2);
+    if mesh.colors.len() > 0 {
+        copy_attribute(&mut mesh.colors, vertex, 4);
+    }
+    if mesh.weights.len() > 0 {
+        copy_attribute(&mut mesh.joints, vertex, 4);
+        copy_attribute(&mut mesh.weights, vertex, 4);
+    }
+    copy
+}
+
+fn copy_attribute<T: Copy>(values: &mut Vec<T>, vertex: usize, size: usize) {
+    for i in 0..size {
+        let value = values[size * vertex + i];
+        values.push(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::normals::NormalMesh;
+    use models::primitives::box_mesh;
+
+    // Dois quads no plano z = 0 lado a lado, com u = |x|: o mapeamento é espelhado em x = 0
+    // 3 - 4 - 5
+    // | / | / |
+    // 0 - 1 - 2
+    // Cada vertice tem cor e junta proprias, para conferir as copias
+    fn mirrored_quads() -> MeshData {
+        let mut positions = Vec::new();
+        let mut texcoords = Vec::new();
+        for y in 0..2 {
+            for x in -1..2 {
+                positions.extend_from_slice(&[x as f32, y as f32, 0.0]);
+                texcoords.extend_from_slice(&[(x as f32).abs(), y as f32]);
+            }
+        }
+        let mut mesh = MeshData::new();
+        mesh.push_mesh(
+            "mirrored",
+            &NormalMesh {
+                positions,
+                normals: [0.0, 0.0, 1.0].repeat(6),
+                texcoords,
+                colors: (0..6).flat_map(|v| vec![v as f32, 0.0, 0.0, 1.0]).collect(),
+                joints: (0..6).flat_map(|v| vec![v as u16, 0, 0, 0]).collect(),
+                weights: [1.0, 0.0, 0.0, 0.0].repeat(6),
+                indices: vec![0, 1, 4, 0, 4, 3, 1, 2, 5, 1, 5, 4],
+            },
+            None,
+        );
+        mesh
+    }
+
+    fn tangent(mesh: &MeshData, vertex: usize) -> [f32; 4] {
+        let t = &mesh.tangents[4 * vertex..4 * vertex + 4];
+        [t[0], t[1], t[2], t[3]]
+    }
+
+    fn assert_unit_tangents(mesh: &MeshData) {
+        assert_eq!(mesh.tangents.len(), 4 * mesh.vertex_count());
+        for vertex in 0..mesh.vertex_count() {
+            let t = tangent(mesh, vertex);
+            let length = (t[0] * t[0] + t[1] * t[1] + t[2] * t[2]).sqrt();
+            assert!((length - 1.0).abs() < 1e-4, "tangente {:?}", t);
+            assert!(t[3] == 1.0 || t[3] == -1.0, "tangente {:?}", t);
+        }
+    }
+
+    #[test]
+    fn mirrored_seam_duplicates_vertices() {
+        let original = mirrored_quads();
+        let mut mesh = original.clone();
+        generate_tangents(&mut mesh);
+
+        // Os dois vertices da costura (1 e 4) ganham uma copia cada
+        assert_eq!(mesh.vertex_count(), 8);
+        assert!(mesh.validate().is_ok(), "{:?}", mesh.validate());
+        assert_eq!(mesh.colors.len(), 4 * mesh.vertex_count());
+        assert_eq!(mesh.joints.len(), 4 * mesh.vertex_count());
+        assert_eq!(mesh.weights.len(), 4 * mesh.vertex_count());
+        assert_unit_tangents(&mesh);
+
+        for (corner, (&vertex, &source)) in
+            mesh.indices.iter().zip(original.indices.iter()).enumerate()
+        {
+            let (vertex, source) = (vertex as usize, source as usize);
+
+            // A copia tem os mesmos atributos do vertice original
+            assert_eq!(mesh.position(vertex), original.position(source));
+            assert_eq!(
+                mesh.texcoords[2 * vertex..2 * vertex + 2],
+                original.texcoords[2 * source..2 * source + 2]
+            );
+            assert_eq!(mesh.colors[4 * vertex], source as f32);
+            assert_eq!(mesh.joints[4 * vertex], source as u16);
+            assert_eq!(mesh.weights[4 * vertex], 1.0);
+
+            // No quad da esquerda u cresce para -x e a bitangente fica invertida
+            let t = tangent(&mesh, vertex);
+            let expected = if corner < 6 { -1.0 } else { 1.0 };
+            assert!(
+                (t[0] - expected).abs() < 1e-4 && t[3] == expected,
+                "canto {} {:?}",
+                corner,
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn box_faces_keep_their_vertices() {
+        let mut mesh = box_mesh(1.0, 2.0, 3.0);
+        let vertex_count = mesh.vertex_count();
+        generate_tangents(&mut mesh);
+
+        assert_eq!(mesh.vertex_count(), vertex_count);
+        assert_unit_tangents(&mesh);
+        for vertex in 0..vertex_count {
+            let t = tangent(&mesh, vertex);
+            let n = mesh.normal(vertex);
+            assert!(
+                (t[0] * n.x + t[1] * n.y + t[2] * n.z).abs() < 1e-4,
+                "tangente {:?}",
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn no_texcoords_no_tangents() {
+        let mut mesh = mirrored_quads();
+        mesh.texcoords.clear();
+        generate_tangents(&mut mesh);
+        assert!(mesh.tangents.is_empty());
+        assert_eq!(mesh.vertex_count(), 6);
+    }
+}
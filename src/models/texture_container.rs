@@ -0,0 +1,471 @@
+use std::io::Cursor;
+
+use ddsfile::Caps2;
+use ddsfile::D3DFormat;
+use ddsfile::Dds;
+use ddsfile::DxgiFormat;
+use ddsfile::MiscFlag;
+use ktx2;
+use ktx2::Format;
+use models::block_compression::BlockFormat;
+use models::block_compression::CompressedLevels;
+use models::load_texture::DecodedTexture;
+use models::load_texture::PixelFormat;
+use models::load_texture::TextureError;
+use models::load_texture::TextureImage;
+use models::load_texture::TextureUsage;
+
+// Containers de textura (KTX2 e DDS) com a cadeia de mipmaps já calculada, comprimida em blocos
+// (ver block_compression.rs) ou em pixels de 8 e 16 bits
+// Diferente de png e jpg, os niveis não são girados no carregamento: os blocos são enviados
+// como estão, e os shaders amostram a textura com U e V invertidos (ver DecodedTexture::flip_uv)
+// O uso da textura (cor ou dados) vem de quem a carrega, não do formato sRGB do arquivo
+
+static KTX2_MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+static DDS_MAGIC: [u8; 4] = [0x44, 0x44, 0x53, 0x20];
+
+// FourCC "BC5U", usado por alguns exportadores no lugar de "ATI2"
+static FOURCC_BC5U: u32 = 0x5535_4342;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+// Conteudo dos niveis de um container
+// Pixels -> Formato dos pixels e se os canais vermelho e azul estão trocados (BGR/BGRA)
+enum ContainerFormat {
+    Blocks(BlockFormat),
+    Pixels(PixelFormat, bool),
+}
+
+// Verdadeiro se os bytes forem de um container KTX2 ou DDS
+pub fn is_container(bytes: &[u8]) -> bool {
+    bytes.starts_with(&KTX2_MAGIC) || bytes.starts_with(&DDS_MAGIC)
+}
+
+// Decodifica um container KTX2 ou DDS. Apenas texturas 2D simples são aceitas (sem cubemaps,
+// arrays, volumes ou supercompressão)
+pub fn decode_container(
+    path: &str,
+    bytes: &[u8],
+    usage: TextureUsage,
+) -> Result<DecodedTexture, TextureError> {
+    if bytes.starts_with(&KTX2_MAGIC) {
+        decode_ktx2(path, bytes, usage)
+    } else {
+        decode_dds(path, bytes, usage)
+    }
+}
+
+fn decode_ktx2(
+    path: &str,
+    bytes: &[u8],
+    usage: TextureUsage,
+) -> Result<DecodedTexture, TextureError> {
+    let reader = ktx2::Reader::new(bytes).map_err(|error| TextureError::Decode {
+        path: path.to_string(),
+        message: error.to_string(),
+    })?;
+    let header = reader.header();
+
+    if let Some(scheme) = header.supercompression_scheme {
+        return Err(unsupported(
+            path,
+            format!("KTX2 supercomprimido {:?}", scheme),
+        ));
+    }
+    if header.face_count != 1 || header.layer_count > 1 || header.pixel_depth > 1 {
+        return Err(unsupported(
+            path,
+            format!(
+                "KTX2 com {} faces, {} camadas e profundidade {}",
+                header.face_count, header.layer_count, header.pixel_depth
+            ),
+        ));
+    }
+
+    let format = match header.format {
+        Some(Format::BC1_RGB_UNORM_BLOCK)
+        | Some(Format::BC1_RGB_SRGB_BLOCK)
+        | Some(Format::BC1_RGBA_UNORM_BLOCK)
+        | Some(Format::BC1_RGBA_SRGB_BLOCK) => ContainerFormat::Blocks(BlockFormat::Bc1),
+        Some(Format::BC3_UNORM_BLOCK) | Some(Format::BC3_SRGB_BLOCK) => {
+            ContainerFormat::Blocks(BlockFormat::Bc3)
+        }
+        Some(Format::BC5_UNORM_BLOCK) => ContainerFormat::Blocks(BlockFormat::Bc5),
+        Some(Format::BC7_UNORM_BLOCK) | Some(Format::BC7_SRGB_BLOCK) => {
+            ContainerFormat::Blocks(BlockFormat::Bc7)
+        }
+        Some(Format::R8_UNORM) | Some(Format::R8_SRGB) => {
+            ContainerFormat::Pixels(PixelFormat::L8, false)
+        }
+        Some(Format::R8G8B8_UNORM) | Some(Format::R8G8B8_SRGB) => {
+            ContainerFormat::Pixels(PixelFormat::Rgb8, false)
+        }
+        Some(Format::B8G8R8_UNORM) | Some(Format::B8G8R8_SRGB) => {
+            ContainerFormat::Pixels(PixelFormat::Rgb8, true)
+        }
+        Some(Format::R8G8B8A8_UNORM) | Some(Format::R8G8B8A8_SRGB) => {
+            ContainerFormat::Pixels(PixelFormat::Rgba8, false)
+        }
+        Some(Format::B8G8R8A8_UNORM) | Some(Format::B8G8R8A8_SRGB) => {
+            ContainerFormat::Pixels(PixelFormat::Rgba8, true)
+        }
+        Some(Format::R16_UNORM) => ContainerFormat::Pixels(PixelFormat::L16, false),
+        Some(Format::R16G16B16A16_UNORM) => ContainerFormat::Pixels(PixelFormat::Rgba16, false),
+        format => return Err(unsupported(path, format!("KTX2 {:?}", format))),
+    };
+
+    container_levels(
+        path,
+        format,
+        header.pixel_width,
+        header.pixel_height.max(1),
+        reader.levels().collect(),
+        usage,
+    )
+}
+
+fn decode_dds(
+    path: &str,
+    bytes: &[u8],
+    usage: TextureUsage,
+) -> Result<DecodedTexture, TextureError> {
+    let dds = Dds::read(Cursor::new(bytes)).map_err(|error| TextureError::Decode {
+        path: path.to_string(),
+        message: error.to_string(),
+    })?;
+
+    let cubemap = dds.header.caps2.contains(Caps2::CUBEMAP)
+        || dds.header10.as_ref().map_or(false, |header10| {
+            header10.misc_flag.contains(MiscFlag::TEXTURECUBE)
+        });
+    if cubemap || dds.get_num_array_layers() > 1 || dds.get_depth() > 1 {
+        return Err(unsupported(
+            path,
+            "DDS com cubemap, array ou volume".to_string(),
+        ));
+    }
+
+    let dxgi_format = match dds.header.spf.fourcc {
+        Some(ref fourcc) if fourcc.0 == FOURCC_BC5U => Some(DxgiFormat::BC5_UNorm),
+        _ => dds.get_dxgi_format(),
+    };
+    let format = match dxgi_format {
+        Some(DxgiFormat::BC1_UNorm) | Some(DxgiFormat::BC1_UNorm_sRGB) => {
+            ContainerFormat::Blocks(BlockFormat::Bc1)
+        }
+        Some(DxgiFormat::BC3_UNorm) | Some(DxgiFormat::BC3_UNorm_sRGB) => {
+            ContainerFormat::Blocks(BlockFormat::Bc3)
+        }
+        Some(DxgiFormat::BC5_UNorm) => ContainerFormat::Blocks(BlockFormat::Bc5),
+        Some(DxgiFormat::BC7_UNorm) | Some(DxgiFormat::BC7_UNorm_sRGB) => {
+            ContainerFormat::Blocks(BlockFormat::Bc7)
+        }
+        Some(DxgiFormat::R8_UNorm) => ContainerFormat::Pixels(PixelFormat::L8, false),
+        Some(DxgiFormat::R8G8B8A8_UNorm) | Some(DxgiFormat::R8G8B8A8_UNorm_sRGB) => {
+            ContainerFormat::Pixels(PixelFormat::Rgba8, false)
+        }
+        Some(DxgiFormat::B8G8R8A8_UNorm) | Some(DxgiFormat::B8G8R8A8_UNorm_sRGB) => {
+            ContainerFormat::Pixels(PixelFormat::Rgba8, true)
+        }
+        Some(DxgiFormat::R16_UNorm) => ContainerFormat::Pixels(PixelFormat::L16, false),
+        Some(DxgiFormat::R16G16B16A16_UNorm) => ContainerFormat::Pixels(PixelFormat::Rgba16, false),
+        // Arquivos sem o cabeçalho DX10 descrevem pixels não comprimidos por mascaras de bits
+        // Nos nomes do D3D9 os canais vão do bit mais significativo ao menos significativo
+        _ => match dds.get_d3d_format() {
+            Some(D3DFormat::A8B8G8R8) => ContainerFormat::Pixels(PixelFormat::Rgba8, false),
+            Some(D3DFormat::A8R8G8B8) => ContainerFormat::Pixels(PixelFormat::Rgba8, true),
+            Some(D3DFormat::R8G8B8) => ContainerFormat::Pixels(PixelFormat::Rgb8, true),
+            Some(D3DFormat::L8) => ContainerFormat::Pixels(PixelFormat::L8, false),
+            Some(D3DFormat::L16) => ContainerFormat::Pixels(PixelFormat::L16, false),
+            Some(D3DFormat::A16B16G16R16) => ContainerFormat::Pixels(PixelFormat::Rgba16, false),
+            d3d_format => {
+                return Err(unsupported(
+                    path,
+                    format!("DDS {:?} {:?}", dxgi_format, d3d_format),
+                ))
+            }
+        },
+    };
+
+    // Os niveis do DDS ficam em sequencia, do maior ao menor
+    let (width, height) = (dds.get_width(), dds.get_height());
+    let mut levels = Vec::new();
+    let mut offset = 0;
+    for level in 0..dds.get_num_mipmap_levels().max(1) {
+        let size = level_bytes(format, (width >> level).max(1), (height >> level).max(1));
+        if offset + size > dds.data.len() {
+            break;
+        }
+        levels.push(&dds.data[offset..offset + size]);
+        offset = offset + size;
+    }
+    container_levels(path, format, width, height, levels, usage)
+}
+
+// Tamanho em bytes de um nivel, sem espaços entre as linhas
+fn level_bytes(format: ContainerFormat, width: u32, height: u32) -> usize {
+    match format {
+        ContainerFormat::Blocks(format) => format.image_bytes(width, height),
+        ContainerFormat::Pixels(format, _) => (width * height) as usize * format.bytes_per_pixel(),
+    }
+}
+
+// Monta a textura decodificada com os niveis lidos do container, validando o tamanho de cada um
+// Niveis não comprimidos são preparados para o uso da textura (ver TextureImage::prepare)
+fn container_levels(
+    path: &str,
+    format: ContainerFormat,
+    width: u32,
+    height: u32,
+    levels: Vec<&[u8]>,
+    usage: TextureUsage,
+) -> Result<DecodedTexture, TextureError> {
+    if width == 0 || levels.is_empty() {
+        return Err(TextureError::Decode {
+            path: path.to_string(),
+            message: "container sem imagens".to_string(),
+        });
+    }
+
+    for (level, bytes) in levels.iter().enumerate() {
+        let expected = level_bytes(format, (width >> level).max(1), (height >> level).max(1));
+        if bytes.len() < expected {
+            return Err(TextureError::Decode {
+                path: path.to_string(),
+                message: format!(
+                    "nivel {} com {} bytes, esperado {}",
+                    level,
+                    bytes.len(),
+                    expected
+                ),
+            });
+        }
+    }
+
+    match format {
+        ContainerFormat::Blocks(format) => Ok(DecodedTexture::Compressed(CompressedLevels {
+            format,
+            usage,
+            width,
+            height,
+            levels: levels
+                .iter()
+                .enumerate()
+                .map(|(level, bytes)| {
+                    let size =
+                        format.image_bytes((width >> level).max(1), (height >> level).max(1));
+                    bytes[..size].to_vec()
+                })
+                .collect(),
+        })),
+        ContainerFormat::Pixels(pixel_format, swap_red_blue) => Ok(DecodedTexture::Levels(
+            levels
+                .iter()
+                .enumerate()
+                .map(|(level, bytes)| {
+                    let (level_width, level_height) =
+                        ((width >> level).max(1), (height >> level).max(1));
+                    let size = level_bytes(format, level_width, level_height);
+                    let mut pixels = bytes[..size].to_vec();
+
+                    if swap_red_blue {
+                        for pixel in pixels.chunks_mut(pixel_format.bytes_per_pixel()) {
+                            pixel.swap(0, 2);
+                        }
+                    }
+                    // Canais de 16 bits dos containers são little endian
+                    if pixel_format.bytes_per_channel() == 2 {
+                        for value in pixels.chunks_mut(2) {
+                            let native = u16::from_le_bytes([value[0], value[1]]).to_ne_bytes();
+                            value.copy_from_slice(&native);
+                        }
+                    }
+
+                    TextureImage {
+                        width: level_width,
+                        height: level_height,
+                        format: pixel_format,
+                        usage: TextureUsage::Data,
+                        pixels,
+                    }
+                    .prepare(usage)
+                })
+                .collect(),
+        )),
+    }
+}
+
+fn unsupported(path: &str, format: String) -> TextureError {
+    TextureError::UnsupportedFormat {
+        path: path.to_string(),
+        format,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ddsfile::AlphaMode;
+    use ddsfile::D3D10ResourceDimension;
+    use ddsfile::NewD3dParams;
+    use ddsfile::NewDxgiParams;
+
+    // Arquivo KTX2 com os niveis informados, do maior ao menor, sem descritor de formato
+    fn ktx2(vk_format: u32, width: u32, height: u32, faces: u32, levels: &[Vec<u8>]) -> Vec<u8> {
+        // Formato, tamanho do tipo, largura, altura, profundidade, camadas, faces, niveis e
+        // supercompressão, seguidos pelos offsets vazios dos descritores e dados extras
+        let header = [
+            vk_format,
+            1,
+            width,
+            height,
+            0,
+            0,
+            faces,
+            levels.len() as u32,
+            0,
+        ];
+        let mut bytes = KTX2_MAGIC.to_vec();
+        for value in header.iter() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0; 32]);
+
+        let mut offset = 80 + 24 * levels.len();
+        for level in levels.iter() {
+            for value in [offset, level.len(), level.len()].iter() {
+                bytes.extend_from_slice(&(*value as u64).to_le_bytes());
+            }
+            offset = offset + level.len();
+        }
+        for level in levels.iter() {
+            bytes.extend_from_slice(level);
+        }
+        bytes
+    }
+
+    fn dds_bytes(dds: &Dds) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        dds.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn pattern(size: usize) -> Vec<u8> {
+        (0..size).map(|byte| byte as u8).collect()
+    }
+
+    // Valores de VkFormat dos formatos usados nos testes
+    static VK_BC1_RGBA_UNORM: u32 = 133;
+    static VK_B8G8R8A8_UNORM: u32 = 44;
+
+    #[test]
+    fn ktx2_block_levels_are_kept() {
+        // Os blocos são enviados como estão no arquivo, e a textura é amostrada com U e V invertidos
+        let levels = vec![pattern(16), pattern(8)];
+        let bytes = ktx2(VK_BC1_RGBA_UNORM, 8, 4, 1, &levels);
+        assert!(is_container(&bytes));
+
+        let texture = decode_container("teste.ktx2", &bytes, TextureUsage::Color).unwrap();
+        assert!(texture.flip_uv());
+        match texture {
+            DecodedTexture::Compressed(compressed) => {
+                assert_eq!(compressed.format, BlockFormat::Bc1);
+                assert_eq!((compressed.width, compressed.height), (8, 4));
+                assert_eq!(compressed.usage, TextureUsage::Color);
+                assert_eq!(compressed.levels, levels);
+            }
+            texture => panic!("resultado inesperado {:?}", texture),
+        }
+    }
+
+    #[test]
+    fn ktx2_bgra_pixels() {
+        let bytes = ktx2(VK_B8G8R8A8_UNORM, 2, 1, 1, &[vec![1, 2, 3, 4, 5, 6, 7, 8]]);
+        let texture = decode_container("teste.ktx2", &bytes, TextureUsage::Data).unwrap();
+        assert!(texture.flip_uv());
+        match texture {
+            DecodedTexture::Levels(images) => {
+                assert_eq!(images.len(), 1);
+                assert_eq!((images[0].width, images[0].height), (2, 1));
+                assert_eq!(images[0].format, PixelFormat::Rgba8);
+                assert_eq!(images[0].pixels, vec![3, 2, 1, 4, 7, 6, 5, 8]);
+            }
+            texture => panic!("resultado inesperado {:?}", texture),
+        }
+    }
+
+    #[test]
+    fn ktx2_rejects_invalid_files() {
+        let cubemap = ktx2(VK_BC1_RGBA_UNORM, 4, 4, 6, &[pattern(48)]);
+        match decode_container("teste.ktx2", &cubemap, TextureUsage::Color) {
+            Err(TextureError::UnsupportedFormat { .. }) => {}
+            result => panic!("resultado inesperado {:?}", result),
+        }
+
+        // 8x4 pixels em Bc1 ocupam 2 blocos
+        let short = ktx2(VK_BC1_RGBA_UNORM, 8, 4, 1, &[pattern(8)]);
+        match decode_container("teste.ktx2", &short, TextureUsage::Color) {
+            Err(TextureError::Decode { .. }) => {}
+            result => panic!("resultado inesperado {:?}", result),
+        }
+
+        let unknown = ktx2(0, 4, 4, 1, &[pattern(16)]);
+        assert!(decode_container("teste.ktx2", &unknown, TextureUsage::Color).is_err());
+    }
+
+    #[test]
+    fn dds_bc7_levels() {
+        let mut dds = Dds::new_dxgi(NewDxgiParams {
+            height: 8,
+            width: 8,
+            depth: None,
+            format: DxgiFormat::BC7_UNorm,
+            mipmap_levels: Some(2),
+            array_layers: None,
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode: AlphaMode::Unknown,
+        })
+        .unwrap();
+        let size = dds.data.len();
+        dds.data = pattern(size);
+        let bytes = dds_bytes(&dds);
+        assert!(is_container(&bytes));
+
+        match decode_container("teste.dds", &bytes, TextureUsage::Color).unwrap() {
+            DecodedTexture::Compressed(compressed) => {
+                assert_eq!(compressed.format, BlockFormat::Bc7);
+                assert_eq!((compressed.width, compressed.height), (8, 8));
+                assert_eq!(compressed.levels.len(), 2);
+                assert_eq!(compressed.levels[0], dds.data[..64].to_vec());
+                assert_eq!(compressed.levels[1], dds.data[64..80].to_vec());
+            }
+            texture => panic!("resultado inesperado {:?}", texture),
+        }
+    }
+
+    #[test]
+    fn dds_d3d_bgra_pixels() {
+        let mut dds = Dds::new_d3d(NewD3dParams {
+            height: 1,
+            width: 2,
+            depth: None,
+            format: D3DFormat::A8R8G8B8,
+            mipmap_levels: None,
+            caps2: None,
+        })
+        .unwrap();
+        dds.data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+
+        match decode_container("teste.dds", &dds_bytes(&dds), TextureUsage::Data).unwrap() {
+            DecodedTexture::Levels(images) => {
+                assert_eq!(images.len(), 1);
+                assert_eq!(images[0].format, PixelFormat::Rgba8);
+                assert_eq!(images[0].pixels, vec![3, 2, 1, 4, 7, 6, 5, 8]);
+            }
+            texture => panic!("resultado inesperado {:?}", texture),
+        }
+    }
+}
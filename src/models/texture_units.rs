@@ -0,0 +1,96 @@
+use models::resources::Texture;
+use models::resources::TextureHandle;
+use std::cell::Cell;
+
+// Ligação de texturas às unidades de textura do OpenGL
+// As unidades são distribuidas por desenho: cada textura utilizada no desenho recebe a proxima
+// unidade livre, onde são ligados a textura e o seu sampler, e o uniform sampler do shader
+// recebe o numero da unidade. Assim um material pode utilizar varias texturas ao mesmo tempo,
+// e o numero de texturas carregadas não depende do limite de unidades do driver
+
+thread_local! {
+    // GL_MAX_TEXTURE_IMAGE_UNITS, consultado no primeiro desenho. 0 enquanto não consultado
+    static MAX_UNITS: Cell<u32> = Cell::new(0);
+}
+
+// Quantidade de unidades de textura acessiveis pelo fragment shader
+#[allow(dead_code)]
+pub fn max_texture_units() -> u32 {
+    MAX_UNITS.with(|max_units| {
+        if max_units.get() == 0 {
+            let mut units = 0;
+            unsafe {
+                gl::GetIntegerv(gl::MAX_TEXTURE_IMAGE_UNITS, &mut units);
+            }
+            // O OpenGL 3.3 garante pelo menos 16 unidades
+            max_units.set((units as u32).max(16));
+        }
+        max_units.get()
+    })
+}
+
+// Verdadeiro se a textura deve ser amostrada com U e V invertidos (ver Texture::flip_uv)
+// Sem textura o valor não importa, o shader não a amostra
+#[allow(dead_code)]
+pub fn flip_uv(texture: Option<&TextureHandle>) -> bool {
+    texture.map_or(false, |texture| texture.flip_uv())
+}
+
+#[allow(dead_code)]
+// Unidades ocupadas em um desenho
+// bound -> Texturas já ligadas, com a sua unidade. A mesma textura em dois uniforms ocupa uma unidade
+pub struct TextureUnits {
+    max_units: u32,
+    bound: Vec<(u32, u32)>,
+}
+
+#[allow(dead_code)]
+impl TextureUnits {
+    // Unidades de um novo desenho, todas livres
+    pub fn new() -> Self {
+        TextureUnits {
+            max_units: max_texture_units(),
+            bound: Vec::new(),
+        }
+    }
+
+    // Liga a textura e o seu sampler à proxima unidade livre e retorna a unidade
+    // None se todas as unidades do desenho já estiverem ocupadas
+    pub unsafe fn bind(&mut self, texture: &Texture) -> Option<u32> {
+        if let Some(&(_, unit)) = self.bound.iter().find(|&&(id, _)| id == texture.id) {
+            return Some(unit);
+        }
+
+        let unit = self.bound.len() as u32;
+        if unit >= self.max_units {
+            println!(
+                "Textura {} ignorada: limite de {} unidades de textura por desenho",
+                texture.id, self.max_units
+            );
+            return None;
+        }
+
+        gl::ActiveTexture(gl::TEXTURE0 + unit);
+        gl::BindTexture(texture.target, texture.id);
+        gl::BindSampler(unit, texture.sampler());
+        self.bound.push((texture.id, unit));
+        Some(unit)
+    }
+
+    // Liga a textura (ver bind) e aponta o uniform sampler para a sua unidade
+    // Retorna verdadeiro se a textura foi ligada. Sem textura o uniform não é alterado, e o
+    // shader não deve amostrá-lo
+    pub unsafe fn bind_uniform(&mut self, location: i32, texture: Option<&TextureHandle>) -> bool {
+        let unit = match texture {
+            Some(texture) => self.bind(texture),
+            None => None,
+        };
+        match unit {
+            Some(unit) => {
+                gl::Uniform1i(location, unit as i32);
+                true
+            }
+            None => false,
+        }
+    }
+}
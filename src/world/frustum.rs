@@ -0,0 +1,156 @@
+use models::matrix::mat4_to_points;
+use models::matrix::norm;
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone)]
+// Volume de visão da camera, representado por 6 planos (a, b, c, d) com normais apontando para dentro
+// Um ponto p está dentro do plano se a*x + b*y + c*z + d >= 0
+pub struct Frustum {
+    pub planes: [glm::Vec4; 6],
+}
+
+#[allow(dead_code)]
+impl Frustum {
+    // Extrai os planos da matriz projection * view (metodo de Gribb e Hartmann)
+    // Os planos resultantes estão em coordenadas globais
+    pub fn new(projection: &glm::Mat4, view: &glm::Mat4) -> Self {
+        Frustum::from_matrix(&(*projection * *view))
+    }
+
+    pub fn from_matrix(view_projection: &glm::Mat4) -> Self {
+        let m = mat4_to_points(view_projection);
+        let row = |i: usize| glm::vec4(m[4 * i], m[4 * i + 1], m[4 * i + 2], m[4 * i + 3]);
+
+        let (r1, r2, r3, r4) = (row(0), row(1), row(2), row(3));
+
+        Frustum {
+            planes: [
+                normalize_plane(r4 + r1), // Esquerda
+                normalize_plane(r4 - r1), // Direita
+                normalize_plane(r4 + r2), // Baixo
+                normalize_plane(r4 - r2), // Cima
+                normalize_plane(r4 + r3), // Near
+                normalize_plane(r4 - r3), // Far
+            ],
+        }
+    }
+
+    // Distancia com sinal entre ponto e plano
+    fn distance(plane: &glm::Vec4, point: &glm::Vec4) -> f32 {
+        plane.x * point.x + plane.y * point.y + plane.z * point.z + plane.w
+    }
+
+    pub fn contains_point(&self, point: &glm::Vec4) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| Frustum::distance(plane, point) >= 0.0)
+    }
+
+    // Esfera está fora se estiver inteiramente atrás de algum plano
+    pub fn intersects_sphere(&self, center: &glm::Vec4, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| Frustum::distance(plane, center) >= -radius)
+    }
+
+    // Testa a AABB global utilizando o vertice mais positivo em relação a normal de cada plano
+    // Conservador: pode considerar visiveis caixas proximas aos cantos do frustum
+    pub fn intersects_bbox(&self, bbox_min: &glm::Vec4, bbox_max: &glm::Vec4) -> bool {
+        self.planes.iter().all(|plane| {
+            let positive_vertex = glm::vec4(
+                if plane.x >= 0.0 {
+                    bbox_max.x
+                } else {
+                    bbox_min.x
+                },
+                if plane.y >= 0.0 {
+                    bbox_max.y
+                } else {
+                    bbox_min.y
+                },
+                if plane.z >= 0.0 {
+                    bbox_max.z
+                } else {
+                    bbox_min.z
+                },
+                1.0,
+            );
+            Frustum::distance(plane, &positive_vertex) >= 0.0
+        })
+    }
+}
+
+// Normaliza o plano para que a distancia calculada esteja em unidades do mundo
+fn normalize_plane(plane: glm::Vec4) -> glm::Vec4 {
+    let length = norm(plane);
+    if length == 0.0 {
+        plane
+    } else {
+        plane / length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::matrix::identity_matrix;
+    use models::matrix::perspective_matrix;
+
+    // Camera na origem olhando para -Z, com near e far negativos como em View
+    fn frustum() -> Frustum {
+        let projection = perspective_matrix(std::f32::consts::FRAC_PI_3, 1.0, -0.1, -100.0);
+        Frustum::new(&projection.matrix, &identity_matrix().matrix)
+    }
+
+    fn visible(frustum: &Frustum, min: (f32, f32, f32), max: (f32, f32, f32)) -> bool {
+        frustum.intersects_bbox(
+            &glm::vec4(min.0, min.1, min.2, 1.0),
+            &glm::vec4(max.0, max.1, max.2, 1.0),
+        )
+    }
+
+    #[test]
+    fn near_and_far_planes() {
+        let frustum = frustum();
+        let near = &frustum.planes[4];
+        let far = &frustum.planes[5];
+        assert!(Frustum::distance(near, &glm::vec4(0.0, 0.0, -1.0, 1.0)) > 0.0);
+        assert!(Frustum::distance(near, &glm::vec4(0.0, 0.0, -0.05, 1.0)) < 0.0);
+        assert!(Frustum::distance(far, &glm::vec4(0.0, 0.0, -99.0, 1.0)) > 0.0);
+        assert!(Frustum::distance(far, &glm::vec4(0.0, 0.0, -101.0, 1.0)) < 0.0);
+
+        // Planos normalizados: distancias em unidades do mundo
+        let distance = Frustum::distance(far, &glm::vec4(0.0, 0.0, -90.0, 1.0));
+        assert!(
+            (distance - 10.0).abs() < 1e-2,
+            "resultado inesperado {}",
+            distance
+        );
+
+        assert!(frustum.contains_point(&glm::vec4(0.0, 0.0, -10.0, 1.0)));
+        assert!(!frustum.contains_point(&glm::vec4(0.0, 0.0, 10.0, 1.0)));
+    }
+
+    #[test]
+    fn boxes_in_front_behind_and_beyond_far() {
+        let frustum = frustum();
+        assert!(visible(&frustum, (-1.0, -1.0, -5.0), (1.0, 1.0, -4.0)));
+        assert!(!visible(&frustum, (-1.0, -1.0, 4.0), (1.0, 1.0, 5.0)));
+        assert!(!visible(&frustum, (-1.0, -1.0, -200.0), (1.0, 1.0, -150.0)));
+        // Caixas que atravessam o near ou o far continuam visiveis
+        assert!(visible(&frustum, (-1.0, -1.0, -1.0), (1.0, 1.0, 1.0)));
+        assert!(visible(&frustum, (-1.0, -1.0, -120.0), (1.0, 1.0, -90.0)));
+    }
+
+    #[test]
+    fn boxes_at_side_planes() {
+        // A 10 unidades da camera, o campo de visão de 60 graus vai de x = -5.77 a 5.77
+        let frustum = frustum();
+        assert!(visible(&frustum, (5.0, -1.0, -11.0), (7.0, 1.0, -10.0)));
+        assert!(!visible(&frustum, (7.0, -1.0, -11.0), (9.0, 1.0, -10.0)));
+        assert!(visible(&frustum, (-7.0, -1.0, -11.0), (-5.0, 1.0, -10.0)));
+        assert!(!visible(&frustum, (-9.0, -1.0, -11.0), (-7.0, 1.0, -10.0)));
+        assert!(!visible(&frustum, (-1.0, 7.0, -11.0), (1.0, 9.0, -10.0)));
+        assert!(!visible(&frustum, (-1.0, -9.0, -11.0), (1.0, -7.0, -10.0)));
+    }
+}
@@ -1,3 +1,6 @@
 pub mod free_camera;
+pub mod frustum;
 pub mod lighting;
+pub mod screen_projection;
+pub mod skybox;
 pub mod view;
@@ -0,0 +1,129 @@
+use std::cell::Cell;
+
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone)]
+// Matrizes de uma view, usadas para estimar o tamanho de um obj na tela
+// Utilizada na escolha do nivel de detalhe (ver ObjModel::select_lod)
+pub struct ScreenProjection {
+    pub view: glm::Mat4,
+    pub projection: glm::Mat4,
+}
+
+thread_local! {
+    // Projeção da ultima view preparada para desenho (ver View::render)
+    static CURRENT_PROJECTION: Cell<Option<ScreenProjection>> = Cell::new(None);
+}
+
+#[allow(dead_code)]
+impl ScreenProjection {
+    pub fn new(view: &glm::Mat4, projection: &glm::Mat4) -> Self {
+        ScreenProjection {
+            view: *view,
+            projection: *projection,
+        }
+    }
+
+    // Fração da altura da tela ocupada por uma esfera de centro (global) e raio informados
+    // Infinito se a camera estiver dentro da esfera, 0 se o centro da esfera estiver atras da camera
+    pub fn screen_size(&self, center: &glm::Vec4, radius: f32) -> f32 {
+        let view_center = self.view * glm::vec4(center.x, center.y, center.z, 1.0);
+        if glm::length(glm::vec3(view_center.x, view_center.y, view_center.z)) <= radius {
+            return std::f32::INFINITY;
+        }
+
+        // Projeta o centro e um ponto deslocado pelo raio no eixo vertical da camera, o que
+        // funciona tanto para a projeção perspectiva quanto para a ortografica
+        let clip_center = self.projection * view_center;
+        let clip_top = self.projection * (view_center + glm::vec4(0.0, radius, 0.0, 0.0));
+        // w é a profundidade na frente da camera (perspectiva) ou 1 (ortografica): valores
+        // negativos indicam um ponto atras da camera
+        if clip_center.w < 1e-6 || clip_top.w < 1e-6 {
+            return 0.0;
+        }
+
+        // A tela tem altura 2 em coordenadas normalizadas, e o diametro o dobro do raio
+        (clip_top.y / clip_top.w - clip_center.y / clip_center.w).abs()
+    }
+
+    // Torna esta a projeção usada pelos draws seguintes
+    pub fn make_current(&self) {
+        CURRENT_PROJECTION.with(|current| current.set(Some(*self)));
+    }
+}
+
+// Projeção da view atual, se alguma view já foi preparada para desenho
+#[allow(dead_code)]
+pub fn current_projection() -> Option<ScreenProjection> {
+    CURRENT_PROJECTION.with(|current| current.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::matrix::identity_matrix;
+    use models::matrix::ortographic_matrix;
+    use models::matrix::perspective_matrix;
+
+    // Camera na origem olhando para -Z
+    fn projection(perspective: bool) -> ScreenProjection {
+        let projection = if perspective {
+            perspective_matrix(std::f32::consts::FRAC_PI_3, 1.0, -0.1, -100.0)
+        } else {
+            ortographic_matrix(-10.0, 10.0, -10.0, 10.0, -0.1, -100.0)
+        };
+        ScreenProjection::new(&identity_matrix().matrix, &projection.matrix)
+    }
+
+    #[test]
+    fn size_shrinks_with_distance() {
+        let projection = projection(true);
+        let near = projection.screen_size(&glm::vec4(0.0, 0.0, -5.0, 1.0), 1.0);
+        let far = projection.screen_size(&glm::vec4(0.0, 0.0, -20.0, 1.0), 1.0);
+        assert!(
+            near > 0.0 && far > 0.0,
+            "resultado inesperado {} {}",
+            near,
+            far
+        );
+        assert!(
+            (near / far - 4.0).abs() < 1e-3,
+            "resultado inesperado {} {}",
+            near,
+            far
+        );
+    }
+
+    #[test]
+    fn behind_camera_is_zero() {
+        let projection = projection(true);
+        assert_eq!(
+            projection.screen_size(&glm::vec4(0.0, 0.0, 5.0, 1.0), 1.0),
+            0.0
+        );
+        assert_eq!(
+            projection.screen_size(&glm::vec4(3.0, 0.0, 20.0, 1.0), 1.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn inside_sphere_is_infinite() {
+        let projection = projection(true);
+        let size = projection.screen_size(&glm::vec4(0.0, 0.0, 0.5, 1.0), 1.0);
+        assert_eq!(size, std::f32::INFINITY);
+    }
+
+    #[test]
+    fn orthographic_ignores_distance() {
+        let projection = projection(false);
+        let near = projection.screen_size(&glm::vec4(0.0, 0.0, -5.0, 1.0), 1.0);
+        let far = projection.screen_size(&glm::vec4(0.0, 0.0, -50.0, 1.0), 1.0);
+        assert!((near - 0.1).abs() < 1e-4, "resultado inesperado {}", near);
+        assert!(
+            (far - near).abs() < 1e-4,
+            "resultado inesperado {} {}",
+            near,
+            far
+        );
+    }
+}
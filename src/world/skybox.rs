@@ -0,0 +1,106 @@
+use models::load_cubemap::load_cubemap_resource;
+use models::load_cubemap::load_equirectangular_resource;
+use models::load_texture::TextureError;
+use models::resources::TextureHandle;
+use models::texture_units::TextureUnits;
+use shader::shader_program::Shader;
+use std::cell::Cell;
+use std::ffi::CString;
+use std::mem;
+use world::view::View;
+
+// Programa e VAO do desenho do skybox, criados no primeiro desenho e compartilhados pelos skyboxes
+// O triangulo que cobre a tela é gerado no vertex shader, então a VAO não tem atributos
+thread_local! {
+    static SKYBOX_PASS: Cell<Option<(u32, u32)>> = Cell::new(None);
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+// Fundo da cena, desenhado com um cubemap atrás de todos os objs
+// Cada cena escolhe o seu skybox (ver GameState::skybox). Sem skybox o fundo é a cor de limpeza
+pub struct Skybox {
+    pub cubemap: TextureHandle,
+}
+
+#[allow(dead_code)]
+impl Skybox {
+    pub fn new(cubemap: &TextureHandle) -> Self {
+        Skybox {
+            cubemap: cubemap.clone(),
+        }
+    }
+
+    // Skybox de 6 imagens, na ordem +X, -X, +Y, -Y, +Z, -Z (ver load_cubemap.rs)
+    pub fn from_faces(paths: &[&str; 6]) -> Result<Self, TextureError> {
+        Ok(Skybox::new(&load_cubemap_resource(paths)?))
+    }
+
+    // Skybox de uma imagem equirretangular, convertida em cubemap no carregamento
+    pub fn from_equirectangular(path: &str) -> Result<Self, TextureError> {
+        Ok(Skybox::new(&load_equirectangular_resource(path)?))
+    }
+
+    // Desenha o skybox nos pixels ainda sem objs. Deve ser chamado depois dos objs opacos:
+    // o fundo fica no far plane e só passa no teste de profundidade onde nada foi desenhado
+    // A direção de cada pixel usa apenas a rotação da camera, então o fundo não se aproxima
+    pub unsafe fn draw(&self, view: &View) {
+        let inverse_rotation_projection = match view.inverse_rotation_projection() {
+            Some(matrix) => matrix,
+            None => return,
+        };
+        let (program, vao) = skybox_pass();
+
+        // O programa atual é restaurado no fim: View::render envia os uniforms da camera ao
+        // programa ligado
+        let mut previous_program = 0;
+        gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut previous_program);
+        gl::UseProgram(program);
+        let inverse_uniform = gl::GetUniformLocation(
+            program,
+            CString::new("inverse_rotation_projection")
+                .unwrap()
+                .as_ptr(),
+        );
+        let skybox_uniform =
+            gl::GetUniformLocation(program, CString::new("skybox").unwrap().as_ptr());
+
+        gl::UniformMatrix4fv(
+            inverse_uniform,
+            1,
+            gl::FALSE,
+            mem::transmute(&inverse_rotation_projection.matrix[0]),
+        );
+        let mut texture_units = TextureUnits::new();
+        texture_units.bind_uniform(skybox_uniform, Some(&self.cubemap));
+
+        // Profundidade 1 é igual à do buffer limpo, e o fundo não esconde nada desenhado depois
+        gl::DepthFunc(gl::LEQUAL);
+        gl::DepthMask(gl::FALSE);
+
+        gl::BindVertexArray(vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        gl::BindVertexArray(0);
+
+        gl::DepthMask(gl::TRUE);
+        gl::DepthFunc(gl::LESS);
+        gl::UseProgram(previous_program as u32);
+    }
+}
+
+unsafe fn skybox_pass() -> (u32, u32) {
+    SKYBOX_PASS.with(|pass| {
+        if let Some(pass) = pass.get() {
+            return pass;
+        }
+        let program = Shader::new(
+            "src/data/shader/vertex/skybox.glsl",
+            "src/data/shader/fragment/skybox.glsl",
+        )
+        .program;
+        let mut vao = 0;
+        gl::GenVertexArrays(1, &mut vao);
+        pass.set(Some((program, vao)));
+        (program, vao)
+    })
+}
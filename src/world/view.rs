@@ -1,9 +1,14 @@
 use glm::Matrix4;
+use models::matrix::identity_matrix;
 use models::matrix::ortographic_matrix;
 use models::matrix::perspective_matrix;
+use models::matrix::GLMatrix;
+use models::ray::Ray;
 use std::mem;
 use world::free_camera::FreeCamera;
+use world::frustum::Frustum;
 use world::lighting::Lighting;
+use world::screen_projection::ScreenProjection;
 static FIELD_OF_VIEW: f32 = 3.141592 / 3.0;
 static G_SCREEN_RATIO: f32 = 1.0;
 use std::ffi::CString;
@@ -43,8 +48,10 @@ impl View {
     }
 
     // Prepara para desenhar, sempre chamado antes do draw dos objs
+    // Também torna esta a view usada na escolha dos niveis de detalhe dos objs
     pub fn render(&self, program: &u32) -> Self {
         let camera_origin = glm::vec4(0.0, 0.0, 0.0, 1.0);
+        self.screen_projection().make_current();
 
         unsafe {
             // Atribui uniforms
@@ -52,6 +59,8 @@ impl View {
                 gl::GetUniformLocation(*program, CString::new("view").unwrap().as_ptr());
             let projection_uniform =
                 gl::GetUniformLocation(*program, CString::new("projection").unwrap().as_ptr());
+            let view_inverse_uniform =
+                gl::GetUniformLocation(*program, CString::new("view_inverse").unwrap().as_ptr());
 
             let global_lighting_uniform =
                 gl::GetUniformLocation(*program, CString::new("global_lighting").unwrap().as_ptr());
@@ -82,6 +91,19 @@ impl View {
                 gl::FALSE,
                 mem::transmute(&self.camera.view_matrix.matrix[0]),
             );
+
+            // Inversa da view calculada uma vez por frame, utilizada para obter a posição da camera
+            let view_inverse = self
+                .camera
+                .view_matrix
+                .inverse()
+                .unwrap_or(identity_matrix());
+            gl::UniformMatrix4fv(
+                view_inverse_uniform,
+                1,
+                gl::FALSE,
+                mem::transmute(&view_inverse.matrix[0]),
+            );
             gl::UniformMatrix4fv(
                 projection_uniform,
                 1,
@@ -114,6 +136,49 @@ impl View {
         }
         *self
     }
+    // Raio global que parte da camera e passa pelo ponto (x, y) da tela, em pixels
+    // a partir do canto superior esquerdo de uma janela de tamanho width x height
+    pub fn screen_ray(&self, x: f32, y: f32, width: f32, height: f32) -> Option<Ray> {
+        // Coordenadas normalizadas do dispositivo, com y para cima
+        let ndc_x = 2.0 * x / width - 1.0;
+        let ndc_y = 1.0 - 2.0 * y / height;
+
+        let inverse_view_projection = GLMatrix {
+            matrix: self.projection_matrix * self.camera.view_matrix.matrix,
+        }
+        .inverse()?;
+
+        // Desfaz a projeção dos pontos correspondentes no near e far plane
+        let near = inverse_view_projection.matrix * glm::vec4(ndc_x, ndc_y, -1.0, 1.0);
+        let far = inverse_view_projection.matrix * glm::vec4(ndc_x, ndc_y, 1.0, 1.0);
+        let near = near / near.w;
+        let far = far / far.w;
+
+        Some(Ray::new(near, far - near))
+    }
+
+    // Matrizes da camera e projeção atuais, para estimar o tamanho dos objs na tela
+    pub fn screen_projection(&self) -> ScreenProjection {
+        ScreenProjection::new(&self.camera.view_matrix.matrix, &self.projection_matrix)
+    }
+
+    // Inversa de projection * view considerando apenas a rotação da camera
+    // Leva pontos da tela às direções globais vistas pela camera, sem depender da sua posição
+    // (ver Skybox::draw)
+    pub fn inverse_rotation_projection(&self) -> Option<GLMatrix> {
+        let mut rotation = self.camera.view_matrix.matrix;
+        rotation.c3 = glm::vec4(0.0, 0.0, 0.0, 1.0);
+        GLMatrix {
+            matrix: self.projection_matrix * rotation,
+        }
+        .inverse()
+    }
+
+    // Frustum da camera atual com a projeção atual, em coordenadas globais
+    pub fn frustum(&self) -> Frustum {
+        Frustum::new(&self.projection_matrix, &self.camera.view_matrix.matrix)
+    }
+
     pub fn update_camera(&mut self, camera: &FreeCamera) -> Self {
         self.camera = camera.clone();
         *self